@@ -0,0 +1,100 @@
+use axum::{
+    Json,
+    body::Bytes,
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::{AppState, is_json_media, quota_reached, strip_hook_token};
+
+/// The checks a hook request has to pass before its body is read: `HOOK_TOKEN` (whose
+/// segment is cut out of the returned URI), `QUOTA_POLICY=reject`, `CAPTURE_CONTENT_TYPES`
+/// and `REQUIRE_HEADERS`. `Err` is the answer the sender gets instead.
+pub async fn admit(state: &AppState, uri: Uri, headers: &HeaderMap, content_type: &str) -> Result<Uri, Response> {
+    let uri = match &state.hook_token {
+        Some(token) => strip_hook_token(&uri, token).ok_or_else(|| {
+            (StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid or missing hook token"}))).into_response()
+        })?,
+        None => uri,
+    };
+
+    // `QUOTA_POLICY=reject` keeps what's stored and turns new captures away instead. This
+    // answers before the body is read; the insert checks again, so concurrent ones can't overshoot
+    if state.reject_over_quota {
+        let stored: i64 = state
+            .db
+            .run(|db| db.query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0)))
+            .await
+            .map_err(rusqlite::Error::from)
+            .and_then(|count| count)
+            .unwrap_or(0);
+        if stored >= state.capture_quota {
+            return Err(quota_reached(state));
+        }
+    }
+
+    if !state.capture_content_types.is_empty()
+        && !state.capture_content_types.iter().any(|t| content_type.starts_with(t.as_str()))
+    {
+        return Err(state.default_response.clone().into_response());
+    }
+
+    let missing: Vec<&str> = state
+        .require_headers
+        .iter()
+        .filter(|name| !headers.contains_key(name.as_str()))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "missing required headers", "missing": missing})),
+        )
+            .into_response());
+    }
+    Ok(uri)
+}
+
+/// `REQUIRE_JSON`: whether a body sent as JSON fails to parse. Unless
+/// `REQUIRE_JSON_STORE_REJECTED` records it anyway, the sender is turned away with
+/// `unsupported_json`.
+pub fn invalid_json(state: &AppState, content_type: &str, body: &Bytes) -> bool {
+    state.require_json
+        && is_json_media(content_type)
+        && serde_json::from_slice::<serde::de::IgnoredAny>(body).is_err()
+}
+
+/// `IDEMPOTENCY_HEADER`: the request's key and the id of the first capture stored with it.
+/// With `IDEMPOTENCY_DUPLICATES=skip` a repeat is answered without being stored.
+pub async fn idempotency(state: &AppState, headers: &HeaderMap) -> Result<(Option<String>, Option<String>), Response> {
+    let key = state
+        .idempotency_header
+        .as_ref()
+        .and_then(|name| headers.get(name.as_str()))
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let duplicate_of: Option<String> = match key.clone() {
+        Some(key) => state
+            .db
+            .run(move |db| {
+                db.query_row(
+                    "SELECT id FROM requests WHERE idempotency_key = ?1 ORDER BY timestamp, received_ns, id LIMIT 1",
+                    rusqlite::params![key],
+                    |row| row.get(0),
+                )
+                .ok()
+            })
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    if state.skip_duplicates
+        && let Some(original) = &duplicate_of
+    {
+        tracing::info!("skipped duplicate of {original} ({})", key.as_deref().unwrap_or_default());
+        return Err(state.default_response.clone().into_response());
+    }
+    Ok((key, duplicate_of))
+}
@@ -0,0 +1,47 @@
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::AppState;
+
+/// Periodically compares the stored request count to `threshold` and posts a single alert
+/// to `url` when it is crossed. The alert re-arms only once the count drops back below
+/// 90% of the threshold, so hovering around the limit doesn't spam the receiver.
+pub fn spawn(state: Arc<AppState>, url: String, threshold: i64, interval: Duration) {
+    let rearm_below = threshold * 9 / 10;
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut armed = true;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            let count: i64 = state
+                .db
+                .run(|db| db.query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0)).unwrap_or(0))
+                .await
+                .unwrap_or(0);
+
+            if armed && count >= threshold {
+                let payload = json!({
+                    "type": "count_threshold",
+                    "count": count,
+                    "threshold": threshold,
+                    "max_requests": state.max_requests,
+                });
+                match client.post(&url).json(&payload).send().await {
+                    Ok(resp) if resp.status().is_success() => {
+                        tracing::info!("count alert sent: {count} stored requests (threshold {threshold})");
+                        armed = false;
+                    }
+                    // Leave armed so the next tick retries
+                    Ok(resp) => tracing::warn!("count alert rejected by {url}: {}", resp.status()),
+                    Err(e) => tracing::warn!("count alert to {url} failed: {e}"),
+                }
+            } else if !armed && count < rearm_below {
+                armed = true;
+            }
+        }
+    });
+}
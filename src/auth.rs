@@ -0,0 +1,55 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::constant_time_eq;
+
+/// `AUTH_TOKEN`: everything but `/hook` (external senders can't authenticate) needs
+/// `Authorization: Bearer <token>` or HTTP Basic with the token as the password. Browsers
+/// get a Basic prompt and then resend the credential, including on the `/ws` upgrade.
+pub async fn require(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if path == "/hook" || path.starts_with("/hook/") {
+        return next.run(request).await;
+    }
+
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(credential)
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()));
+    if authorized {
+        return next.run(request).await;
+    }
+
+    let mut response = (StatusCode::UNAUTHORIZED, Json(json!({"error": "missing or invalid credentials"}))).into_response();
+    let challenges = response.headers_mut();
+    challenges.append(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    challenges.append(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static("Basic realm=\"http_logger\", charset=\"UTF-8\""),
+    );
+    response
+}
+
+/// The presented secret: the bearer token, or the password of a Basic credential.
+fn credential(authorization: &str) -> Option<String> {
+    let (scheme, value) = authorization.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") {
+        return Some(value.trim().to_string());
+    }
+    if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = String::from_utf8(BASE64.decode(value.trim()).ok()?).ok()?;
+        return decoded.split_once(':').map(|(_, password)| password.to_string());
+    }
+    None
+}
@@ -0,0 +1,526 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::blob::ZeroBlob;
+use rusqlite::{Connection, DatabaseName};
+use rusqlite::types::Value as SqlValue;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::pool::{Unavailable, checkout, run_on};
+use crate::{AppState, body_limit, is_json_media, is_ndjson_media, is_text_media, parse_form, rules};
+
+// Bytes copied into a BLOB per write call
+const BLOB_CHUNK: usize = 64 * 1024;
+
+// With `BODY_STORAGE=blob`, bodies over `BODY_INLINE_LIMIT` live here rather than in files
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS body_blobs (
+        id TEXT PRIMARY KEY,
+        body BLOB NOT NULL
+    );
+    CREATE TRIGGER IF NOT EXISTS requests_delete_body_blob AFTER DELETE ON requests BEGIN
+        DELETE FROM body_blobs WHERE id = old.id;
+    END;
+";
+
+/// `BODY_INLINE_LIMIT`: bodies larger than `inline_limit` bytes are written raw to
+/// `dir/<id>` and the row only keeps the path, so big uploads don't bloat the database.
+pub struct BodyStore {
+    pub dir: PathBuf,
+    pub inline_limit: usize,
+}
+
+impl BodyStore {
+    /// Files live in `BODIES_DIR`, or a `bodies/` directory next to `DB_PATH`.
+    pub fn from_env(db_path: &str) -> Self {
+        let dir = std::env::var("BODIES_DIR").map(PathBuf::from).unwrap_or_else(|_| {
+            Path::new(db_path)
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join("bodies")
+        });
+        Self {
+            dir,
+            inline_limit: crate::env_or("BODY_INLINE_LIMIT", 64 * 1024),
+        }
+    }
+
+    /// Whether a body of `len` bytes is kept out of the row.
+    pub fn spills(&self, len: usize) -> bool {
+        self.inline_limit != 0 && len > self.inline_limit
+    }
+
+    /// Writes the body out if it's over the limit; `None` means store it inline.
+    /// A failed write also falls back to inline so the capture isn't lost.
+    pub async fn spill(&self, id: &str, body: &[u8]) -> Option<String> {
+        if !self.spills(body.len()) {
+            return None;
+        }
+        let path = self.dir.join(id);
+        let written = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&path, body).await
+        };
+        match written.await {
+            Ok(()) => Some(path.to_string_lossy().into_owned()),
+            Err(e) => {
+                tracing::warn!("BODY_INLINE_LIMIT: failed to write {}: {e}", path.display());
+                None
+            }
+        }
+    }
+}
+
+/// `BODY_STORAGE=blob`: fills a preallocated BLOB (the row's `body_blob`, or a `body_blobs`
+/// row) through SQLite's incremental blob I/O a chunk at a time, so no base64 or bound copy
+/// of the body is made.
+pub fn write_blob(db: &Connection, table: &str, column: &str, rowid: i64, body: &[u8]) -> rusqlite::Result<()> {
+    let mut blob = db.blob_open(DatabaseName::Main, table, column, rowid, false)?;
+    for (i, chunk) in body.chunks(BLOB_CHUNK).enumerate() {
+        blob.write_at(chunk, i * BLOB_CHUNK)?;
+    }
+    Ok(())
+}
+
+/// Adds a buffered body to `body_blobs`, for bodies that arrived without a Content-Length.
+pub fn insert_blob(db: &Connection, id: &str, body: &[u8]) -> rusqlite::Result<()> {
+    db.execute(
+        "INSERT INTO body_blobs (id, body) VALUES (?1, ?2)",
+        rusqlite::params![id, ZeroBlob(body.len() as i32)],
+    )?;
+    write_blob(db, "body_blobs", "body", db.last_insert_rowid(), body)
+}
+
+pub fn read_blob(db: &Connection, id: &str) -> rusqlite::Result<Vec<u8>> {
+    db.query_row("SELECT body FROM body_blobs WHERE id = ?1", rusqlite::params![id], |row| row.get(0))
+}
+
+/// Removes `body_blobs` rows left by uploads that were cut off by a restart.
+pub fn remove_orphans(db: &Connection) -> rusqlite::Result<usize> {
+    db.execute("DELETE FROM body_blobs WHERE id NOT IN (SELECT id FROM requests)", [])
+}
+
+/// A body being written into its `body_blobs` row as it arrives, which is preallocated
+/// from the Content-Length so the upload is never held in memory whole. Unless `keep` is
+/// called, e.g. when the sender goes away mid-upload or the capture isn't stored, dropping
+/// it deletes the row. Everything from the preallocation to that cleanup goes to the file it
+/// was created in, even if `DB_ROTATION` rolls over mid-upload.
+pub struct StagedBlob {
+    pool: Pool<SqliteConnectionManager>,
+    id: String,
+    rowid: i64,
+    written: usize,
+    pending: Vec<u8>,
+    sha256: Sha256,
+    // The first `HEXDUMP_BYTES`, for the live `hexdump` frame
+    head: Vec<u8>,
+    head_limit: usize,
+    kept: bool,
+}
+
+impl StagedBlob {
+    /// Like everything else the blob does, the preallocation runs on the blocking pool.
+    pub async fn create(state: &Arc<AppState>, id: &str, len: usize) -> rusqlite::Result<Self> {
+        let (state, id) = (state.clone(), id.to_string());
+        tokio::task::spawn_blocking(move || {
+            // Staged in the file the capture will be inserted into
+            if let Some(rotation) = &state.rotation {
+                rotation.roll(&state.db);
+            }
+            Self::create_in(state.db.pool(), &id, len, state.hexdump_bytes)
+        })
+        .await
+        .map_err(|e| rusqlite::Error::from(Unavailable::from(e)))?
+    }
+
+    fn create_in(pool: Pool<SqliteConnectionManager>, id: &str, len: usize, head: usize) -> rusqlite::Result<Self> {
+        let db = checkout(&pool)?;
+        db.execute(
+            "INSERT INTO body_blobs (id, body) VALUES (?1, ?2)",
+            rusqlite::params![id, ZeroBlob(len as i32)],
+        )?;
+        let rowid = db.last_insert_rowid();
+        drop(db);
+        Ok(Self {
+            pool,
+            id: id.to_string(),
+            rowid,
+            written: 0,
+            pending: Vec::with_capacity(BLOB_CHUNK),
+            sha256: Sha256::new(),
+            head: Vec::new(),
+            head_limit: head,
+            kept: false,
+        })
+    }
+
+    /// Takes the next frame, writing to the blob whenever a whole chunk has built up.
+    pub async fn write(&mut self, frame: &[u8]) -> rusqlite::Result<()> {
+        self.sha256.update(frame);
+        let room = self.head_limit - self.head.len();
+        self.head.extend_from_slice(&frame[..frame.len().min(room)]);
+        self.pending.extend_from_slice(frame);
+        if self.pending.len() >= BLOB_CHUNK {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Writes what's left once the body has ended; returns its SHA-256.
+    pub async fn finish(&mut self) -> rusqlite::Result<String> {
+        self.flush().await?;
+        Ok(format!("{:x}", self.sha256.clone().finalize()))
+    }
+
+    /// The body read back whole, for features that have to see all of it.
+    pub async fn read(&self) -> rusqlite::Result<Vec<u8>> {
+        let id = self.id.clone();
+        run_on(self.pool.clone(), move |db| read_blob(db, &id)).await?
+    }
+
+    /// The first bytes of the body, as many as `HEXDUMP_BYTES`.
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    /// The pool of the file the blob is in, which the capture's row has to go to as well.
+    pub fn pool(&self) -> &Pool<SqliteConnectionManager> {
+        &self.pool
+    }
+
+    /// Leaves the row in place once the capture is stored or buffered for storing.
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+
+    /// Hands the buffered chunk to the blocking pool and takes the buffer back once written.
+    async fn flush(&mut self) -> rusqlite::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let (rowid, offset, pending) = (self.rowid, self.written, std::mem::take(&mut self.pending));
+        let mut pending = run_on(self.pool.clone(), move |db| {
+            let mut blob = db.blob_open(DatabaseName::Main, "body_blobs", "body", rowid, false)?;
+            blob.write_at(&pending, offset)?;
+            Ok::<_, rusqlite::Error>(pending)
+        })
+        .await??;
+        self.written += pending.len();
+        pending.clear();
+        self.pending = pending;
+        Ok(())
+    }
+}
+
+impl Drop for StagedBlob {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let (pool, id) = (self.pool.clone(), std::mem::take(&mut self.id));
+        tokio::task::spawn_blocking(move || {
+            let deleted = checkout(&pool)
+                .map_err(rusqlite::Error::from)
+                .and_then(|db| db.execute("DELETE FROM body_blobs WHERE id = ?1", rusqlite::params![id]));
+            if let Err(e) = deleted {
+                tracing::warn!("failed to remove staged body {id}: {e}");
+            }
+        });
+    }
+}
+
+/// A hook request's body as received, before anything is done to it for storage.
+pub struct Received {
+    /// Empty for a streamed body nothing needs whole (see `needs_whole_body`)
+    pub body: Bytes,
+    pub size: i64,
+    pub sha256: String,
+    /// Chunk sizes as JSON, for `Transfer-Encoding: chunked` requests
+    pub chunks: Option<String>,
+    /// Lowercased `Content-Encoding`, unless absent or `identity`
+    pub content_encoding: Option<String>,
+    /// `DROP_BODY_PATHS` matched: the body is hashed and measured but not kept
+    pub dropped: bool,
+    /// The body's `body_blobs` row, when it was streamed there
+    pub staged: Option<StagedBlob>,
+}
+
+/// Reads the body within its `BODY_LIMITS` limit, streaming it into a `StagedBlob` when it's
+/// known to be over `BODY_INLINE_LIMIT` with `BODY_STORAGE=blob`. `Err` is the answer for a
+/// body that is too large, cut off or couldn't be written.
+pub async fn receive(
+    state: &Arc<AppState>,
+    id: &str,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    content_type: &str,
+    body: Body,
+) -> Result<Received, Response> {
+    let chunked = headers
+        .get(header::TRANSFER_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"));
+    let limit = body_limit(&state.body_limits, uri.path());
+    let dropped = state.drop_body_paths.iter().any(|p| uri.path().starts_with(p.as_str()));
+    let redacting = !state.redact_json_fields.is_empty() && is_json_media(content_type);
+    let content_length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    // `BODY_STORAGE=blob`: a body known to be over `BODY_INLINE_LIMIT` goes straight into its
+    // BLOB as it arrives. Without a Content-Length there's nothing to preallocate, so it's buffered
+    let mut staged = match content_length {
+        Some(len) if state.blob_bodies && state.body_store.spills(len) && len <= limit && !dropped && !redacting => {
+            StagedBlob::create(state, id, len)
+                .await
+                .inspect_err(|e| tracing::warn!("BODY_STORAGE=blob: failed to preallocate {len} bytes, buffering: {e}"))
+                .ok()
+        }
+        _ => None,
+    };
+    let (body, chunk_sizes) = read_body(body, limit, staged.as_mut()).await?;
+    let chunks = chunked.then(|| serde_json::to_string(&chunk_sizes).unwrap_or_default());
+    let content_encoding = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty() && v != "identity");
+    let (body, size, sha256) = match &mut staged {
+        Some(blob) => {
+            let streamed = async {
+                let sha256 = blob.finish().await?;
+                let whole = needs_whole_body(state, content_type, content_encoding.is_some(), content_length.unwrap_or_default());
+                let body = if whole { blob.read().await? } else { Vec::new() };
+                Ok::<_, rusqlite::Error>((Bytes::from(body), sha256))
+            };
+            match streamed.await {
+                Ok((body, sha256)) => (body, content_length.unwrap_or_default() as i64, sha256),
+                Err(e) => {
+                    tracing::error!("BODY_STORAGE=blob: failed to write {method} {uri}: {e}");
+                    return Err(failed_to_store());
+                }
+            }
+        }
+        None => {
+            let sha256 = format!("{:x}", Sha256::digest(&body));
+            let size = body.len() as i64;
+            (body, size, sha256)
+        }
+    };
+    Ok(Received {
+        body,
+        size,
+        sha256,
+        chunks,
+        content_encoding,
+        dropped,
+        staged,
+    })
+}
+
+fn failed_to_store() -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "failed to store request"}))).into_response()
+}
+
+/// Collects the body frame by frame, recording each data frame's size. hyper yields one
+/// frame per chunk of a chunked body unless a chunk spans several socket reads, so the
+/// sizes are a close (best-effort) picture of how the sender chunked it. With `staged`
+/// each frame is written to the blob instead and the returned bytes are empty.
+async fn read_body(body: Body, limit: usize, mut staged: Option<&mut StagedBlob>) -> Result<(Bytes, Vec<usize>), Response> {
+    let mut body = Limited::new(body, limit);
+    let mut data = Vec::new();
+    let mut sizes = Vec::new();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|e| {
+            if e.downcast_ref::<LengthLimitError>().is_some() {
+                StatusCode::PAYLOAD_TOO_LARGE.into_response()
+            } else {
+                StatusCode::BAD_REQUEST.into_response()
+            }
+        })?;
+        if let Ok(chunk) = frame.into_data() {
+            sizes.push(chunk.len());
+            match staged.as_deref_mut() {
+                Some(blob) => blob.write(&chunk).await.map_err(|e| {
+                    tracing::error!("BODY_STORAGE=blob: failed to write body: {e}");
+                    failed_to_store()
+                })?,
+                None => data.extend_from_slice(&chunk),
+            }
+        }
+    }
+    Ok((data.into(), sizes))
+}
+
+/// Whether anything enabled looks at the whole body, so a streamed one has to be read back
+/// from its blob: validation, forwarding, tag rules, decoding, parsing (form, text, NDJSON)
+/// and sinks.
+fn needs_whole_body(state: &AppState, content_type: &str, encoded: bool, len: usize) -> bool {
+    state.spec.is_some()
+        || state.forward.is_some()
+        || state.sink.is_some()
+        || state.capture_pipe.is_some()
+        || rules::match_bodies(&state.tag_rules)
+        || (state.require_json && is_json_media(content_type))
+        || (encoded && (state.measure_compression || state.store_decoded_body))
+        || (state.parse_form && content_type == "application/x-www-form-urlencoded")
+        || (len <= state.body_text_max_bytes && is_text_media(content_type))
+        || is_ndjson_media(content_type)
+}
+
+/// The body as it is stored, and what's parsed out of it.
+pub struct Shaped {
+    /// Empty if dropped, redacted with `REDACT_JSON_FIELDS`, otherwise as received
+    pub body: Bytes,
+    pub redacted: bool,
+    /// Where a body over `BODY_INLINE_LIMIT` was written, outside blob mode
+    pub file: Option<String>,
+    /// Kept out of the row, in a file or `body_blobs`
+    pub spilled: bool,
+    pub form: Option<String>,
+    pub text: Option<String>,
+    pub ndjson: Option<String>,
+    pub ndjson_count: Option<i64>,
+}
+
+/// Applies `DROP_BODY_PATHS` and `REDACT_JSON_FIELDS`, spills the result if it's large and
+/// parses it per `PARSE_FORM`, `BODY_TEXT_MAX_BYTES` and NDJSON media types. `streamed`
+/// says the body is already in its `body_blobs` row.
+pub async fn shape(state: &AppState, id: &str, content_type: &str, body: Bytes, dropped: bool, streamed: bool) -> Shaped {
+    let mut redacted = false;
+    let body = if dropped {
+        Bytes::new()
+    } else if !state.redact_json_fields.is_empty() && is_json_media(content_type) {
+        let redacted_body = redact_json_body(body.clone(), &state.redact_json_fields);
+        redacted = redacted_body != body;
+        redacted_body
+    } else {
+        body
+    };
+    // Blob mode keeps large bodies in `body_blobs` rather than files
+    let file = if state.blob_bodies { None } else { state.body_store.spill(id, &body).await };
+    let spilled = file.is_some() || state.blob_bodies && (streamed || state.body_store.spills(body.len()));
+    let form = (state.parse_form && !dropped && content_type == "application/x-www-form-urlencoded")
+        .then(|| parse_form(&body));
+
+    let text = (!dropped && body.len() <= state.body_text_max_bytes && is_text_media(content_type))
+        .then(|| std::str::from_utf8(&body).ok().map(str::to_string))
+        .flatten();
+    let (ndjson, ndjson_count) = if !dropped && is_ndjson_media(content_type) {
+        let records = parse_ndjson(&body);
+        (Some(serde_json::to_string(&records).unwrap_or_default()), Some(records.len() as i64))
+    } else {
+        (None, None)
+    };
+    Shaped {
+        body,
+        redacted,
+        file,
+        spilled,
+        form,
+        text,
+        ndjson,
+        ndjson_count,
+    }
+}
+
+/// Replaces the values of `fields` (case-insensitive, at any depth) with `***`.
+/// Bodies that don't parse as JSON are stored unchanged.
+fn redact_json_body(body: Bytes, fields: &[String]) -> Bytes {
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return body;
+    };
+    redact_json_value(&mut value, fields);
+    serde_json::to_vec(&value).map(Into::into).unwrap_or(body)
+}
+
+fn redact_json_value(value: &mut serde_json::Value, fields: &[String]) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                if fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                    *field = json!("***");
+                } else {
+                    redact_json_value(field, fields);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_json_value(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// One value per non-empty line; lines that aren't valid JSON are kept as raw strings.
+fn parse_ndjson(body: &[u8]) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(body)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| json!(line)))
+        .collect()
+}
+
+/// Deletes the matching rows along with any body files they reference.
+pub fn delete_requests(db: &Connection, where_clause: &str, params: &[SqlValue]) -> rusqlite::Result<usize> {
+    let files: Vec<String> = db
+        .prepare(&format!("SELECT body_file FROM requests {where_clause} AND body_file IS NOT NULL"))?
+        .query_map(rusqlite::params_from_iter(params), |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    let deleted = db.execute(
+        &format!("DELETE FROM requests {where_clause}"),
+        rusqlite::params_from_iter(params),
+    )?;
+    for file in files {
+        if let Err(e) = std::fs::remove_file(&file)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!("failed to remove body file {file}: {e}");
+        }
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::Db;
+
+    #[tokio::test]
+    async fn staged_blob_stays_in_its_file_when_the_database_rolls_mid_upload() {
+        let dir = std::env::temp_dir().join(format!("staged-blob-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (before, after) = (dir.join("before.db"), dir.join("after.db"));
+        let db = Db::open(&before.to_string_lossy(), 2).unwrap();
+        let body: Vec<u8> = (0..3 * BLOB_CHUNK).map(|i| i as u8).collect();
+
+        let mut blob = StagedBlob::create_in(db.pool(), "upload", body.len(), 16).unwrap();
+        blob.write(&body[..BLOB_CHUNK + 10]).await.unwrap();
+        // What `Rotation::roll` does at midnight
+        db.reopen(&after.to_string_lossy()).unwrap();
+        blob.write(&body[BLOB_CHUNK + 10..]).await.unwrap();
+        blob.finish().await.unwrap();
+        assert_eq!(blob.read().await.unwrap(), body);
+        assert_eq!(blob.head(), &body[..16]);
+        blob.keep();
+
+        assert_eq!(read_blob(&Connection::open(&before).unwrap(), "upload").unwrap(), body);
+        let moved: i64 = db.get().unwrap().query_row("SELECT COUNT(*) FROM body_blobs", [], |row| row.get(0)).unwrap();
+        assert_eq!(moved, 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
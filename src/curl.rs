@@ -0,0 +1,195 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::replay::Outgoing;
+use crate::{AppState, SearchQuery, fetch_request, pool, select_requests};
+
+#[derive(Deserialize)]
+pub struct ScriptQuery {
+    base: Option<String>,
+}
+
+/// A bash script replaying the stored requests (or those matching the `GET /api/requests`
+/// filters) oldest first, one curl per request. Requests go to `$BASE_URL` plus the original
+/// path and query; it defaults to `?base=` or else this server.
+pub async fn script(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<SearchQuery>,
+    Query(query): Query<ScriptQuery>,
+    headers: HeaderMap,
+) -> Result<Response, pool::Unavailable> {
+    let (where_clause, mut params) = match filter.where_clause() {
+        Ok(sql) => sql,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()),
+    };
+    let page = match filter.limit {
+        Some(limit) => {
+            params.push(rusqlite::types::Value::Integer(limit.max(0)));
+            params.push(rusqlite::types::Value::Integer(filter.offset.unwrap_or(0).max(0)));
+            "LIMIT ? OFFSET ?"
+        }
+        None => "",
+    };
+    // Bodies are read along with the rows, oldest first
+    let requests: Vec<_> = state
+        .db
+        .run(move |db| {
+            let mut requests = select_requests(db, &where_clause, page, &params);
+            requests.reverse();
+            requests
+                .into_iter()
+                .map(|req| {
+                    let outgoing = Outgoing::from_logged(db, &req, "", true);
+                    (req, outgoing)
+                })
+                .collect()
+        })
+        .await?;
+
+    let base = query.base.unwrap_or_else(|| {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("localhost");
+        format!("http://{host}")
+    });
+
+    let mut script = String::from("#!/usr/bin/env bash\nset -e\n\n");
+    script.push_str(&format!(
+        "# {} requests exported from {} at {}\n",
+        requests.len(),
+        env!("CARGO_PKG_NAME"),
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    ));
+    script.push_str(&format!(
+        "BASE_URL=\"${{BASE_URL:-{}}}\"\n",
+        base.trim_end_matches('/').replace(['\\', '"', '$', '`'], "")
+    ));
+    for (req, outgoing) in &requests {
+        script.push_str(&format!("\n# {} {} {}\n", req.timestamp, req.method, req.id));
+        script.push_str(&command(outgoing, &format!("\"$BASE_URL\"{}", quote(&outgoing.url))));
+        script.push('\n');
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/x-shellscript; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"http_logger_replay.sh\""),
+        ],
+        script,
+    )
+        .into_response())
+}
+
+/// A curl command reproducing one capture against the host it was sent to.
+pub async fn single(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Response, pool::Unavailable> {
+    let found = state
+        .db
+        .run(move |db| {
+            let req = fetch_request(db, &id)?;
+            Some(Outgoing::from_logged(db, &req, &crate::har::absolute_url(&req), false))
+        })
+        .await?;
+    let Some(outgoing) = found else {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response());
+    };
+    let mut command = command(&outgoing, &quote(&outgoing.url));
+    command.push('\n');
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], command).into_response())
+}
+
+/// One curl invocation for `outgoing`; `url` is already shell-quoted. Text bodies are
+/// inlined, binary ones are piped in through `base64 -d`.
+pub fn command(outgoing: &Outgoing, url: &str) -> String {
+    let mut args = vec!["curl".to_string(), "-sS".to_string()];
+    match outgoing.method.as_str() {
+        // -X HEAD would make curl wait for a body that never comes
+        "HEAD" => args.push("--head".to_string()),
+        "GET" if outgoing.body.is_empty() => {}
+        method => args.extend(["-X".to_string(), quote(method)]),
+    }
+    for (name, value) in &outgoing.headers {
+        args.extend(["-H".to_string(), quote(&format!("{name}: {value}"))]);
+    }
+
+    let mut pipe = None;
+    if !outgoing.body.is_empty() {
+        match std::str::from_utf8(&outgoing.body) {
+            // A leading '@' would make curl read a file instead
+            Ok(text) if !text.contains('\0') && !text.starts_with('@') => {
+                args.extend(["--data-binary".to_string(), quote(text)])
+            }
+            _ => {
+                pipe = Some(format!("printf '%s' '{}' | base64 -d | ", BASE64.encode(&outgoing.body)));
+                args.extend(["--data-binary".to_string(), "@-".to_string()]);
+            }
+        }
+    }
+    args.push(url.to_string());
+    format!("{}{}", pipe.unwrap_or_default(), args.join(" "))
+}
+
+/// POSIX single-quoting: everything is literal except `'`, which is closed, escaped and reopened.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// What `sh` makes of `script`'s standard output.
+    fn sh(script: &str) -> Vec<u8> {
+        let output = Command::new("sh").arg("-c").arg(script).output().unwrap();
+        assert!(output.status.success(), "{script}: {}", String::from_utf8_lossy(&output.stderr));
+        output.stdout
+    }
+
+    fn outgoing(body: &[u8]) -> Outgoing {
+        Outgoing {
+            method: "POST".to_string(),
+            url: "http://localhost/hook?a=1&b=$(id)".to_string(),
+            headers: vec![("X-Note".to_string(), "it's `uname`".to_string())],
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn quoted_values_reach_the_shell_literally() {
+        for value in ["it's", "'", "''", "$(rm -rf /)", "${HOME}", "`id`", "a\nb\n", "\\n", "!", "\"", ""] {
+            assert_eq!(sh(&format!("printf '%s' {}", quote(value))), value.as_bytes(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn binary_bodies_are_piped_through_base64() {
+        let body = [0xff, 0xfe, 0x00, b'\'', b'\n', 0x80];
+        let command = command(&outgoing(&body), &quote("http://localhost/hook"));
+        assert!(command.contains("| base64 -d | curl"), "{command}");
+
+        // A stand-in curl that prints the body it's given on stdin
+        assert_eq!(sh(&format!("curl() {{ cat; }}\n{command}")), body);
+    }
+
+    #[test]
+    fn generated_script_parses() {
+        let mut script = String::from("BASE_URL=http://localhost\n");
+        for body in [&b"it's a $(test)\n`with` newlines\n"[..], &[0xc3, 0x28], b"@/etc/passwd", b""] {
+            let outgoing = outgoing(body);
+            script.push_str(&command(&outgoing, &format!("\"$BASE_URL\"{}", quote(&outgoing.url))));
+            script.push('\n');
+        }
+        let checked = Command::new("sh").arg("-n").arg("-c").arg(&script).output().unwrap();
+        assert!(checked.status.success(), "{script}\n{}", String::from_utf8_lossy(&checked.stderr));
+    }
+}
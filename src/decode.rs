@@ -0,0 +1,193 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::{AppState, LoggedRequest, fetch_request, is_json_media, is_truthy, parse_form, pool};
+
+// Refuse to inflate past this so a compression bomb can't exhaust memory
+const MAX_DECODED_BYTES: u64 = 64 * 1024 * 1024;
+// Measuring keeps nothing in memory, so it can afford to count further
+const MAX_MEASURED_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct BodyQuery {
+    decode: Option<String>,
+}
+
+/// The stored body as raw bytes, or with `?decode=1` as JSON describing it: decompressed
+/// per `Content-Encoding`, pretty-printed if JSON, split into fields if form-encoded. The
+/// stored body is never changed; a failed decompression falls back to the raw bytes and
+/// says so in `warning`.
+pub async fn body(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<BodyQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let found = state
+        .db
+        .run(move |db| {
+            let req = fetch_request(db, &id)?;
+            let raw = crate::stored_body(db, &req);
+            Some((req, raw))
+        })
+        .await?;
+    let Some((req, raw)) = found else {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response());
+    };
+    let headers: Vec<(String, String)> = serde_json::from_str(&req.headers).unwrap_or_default();
+    let header_value = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    };
+    let content_type = header_value("content-type");
+
+    if !query.decode.as_deref().is_some_and(is_truthy) {
+        let content_type = content_type.unwrap_or_else(|| "application/octet-stream".to_string());
+        return Ok(([(header::CONTENT_TYPE, content_type)], raw).into_response());
+    }
+
+    let encoding = header_value("content-encoding").map(|e| e.trim().to_ascii_lowercase());
+    let (bytes, decompressed, warning) = match encoding.as_deref() {
+        None | Some("") | Some("identity") => (raw, false, None),
+        Some(encoding) => match decompress(encoding, &raw) {
+            Ok(bytes) => (bytes, true, None),
+            Err(e) => (raw, false, Some(format!("could not decode {encoding} body: {e}"))),
+        },
+    };
+
+    let media = content_type
+        .as_deref()
+        .and_then(|v| v.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let (format, body) = if is_json_media(&media)
+        && let Ok(value) = serde_json::from_slice::<Value>(&bytes)
+    {
+        ("json", Value::from(serde_json::to_string_pretty(&value).unwrap_or_default()))
+    } else if media == "application/x-www-form-urlencoded" {
+        ("form", serde_json::from_str(&parse_form(&bytes)).unwrap_or_default())
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(text) => ("text", Value::from(text)),
+            Err(e) => ("base64", Value::from(BASE64.encode(e.into_bytes()))),
+        }
+    };
+
+    Ok(Json(json!({
+        "id": req.id,
+        "content_type": content_type,
+        "content_encoding": encoding,
+        "decompressed": decompressed,
+        "warning": warning,
+        "format": format,
+        "body": body,
+    }))
+    .into_response())
+}
+
+/// The stored body decompressed per its `Content-Encoding` (reusing `decoded_body` when it
+/// was stored), or the raw bytes when there is none.
+pub fn decompressed_body(db: &Connection, req: &LoggedRequest) -> Result<Vec<u8>, String> {
+    if let Some(decoded) = &req.decoded_body {
+        return BASE64.decode(decoded).map_err(|e| e.to_string());
+    }
+    let raw = crate::stored_body(db, req);
+    let headers: Vec<(String, String)> = serde_json::from_str(&req.headers).unwrap_or_default();
+    let encoding = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-encoding"))
+        .map(|(_, v)| v.trim().to_ascii_lowercase());
+    match encoding.as_deref() {
+        None | Some("") | Some("identity") => Ok(raw),
+        Some(encoding) => decompress(encoding, &raw),
+    }
+}
+
+/// Runs `read` over a decoder for `encoding`.
+fn with_decoder<T>(encoding: &str, raw: &[u8], read: impl Fn(&mut dyn Read) -> std::io::Result<T>) -> Result<T, String> {
+    let result = match encoding {
+        "gzip" | "x-gzip" => read(&mut MultiGzDecoder::new(raw)),
+        // HTTP "deflate" is meant to be zlib-wrapped, but some senders use raw deflate
+        "deflate" => read(&mut ZlibDecoder::new(raw)).or_else(|_| read(&mut DeflateDecoder::new(raw))),
+        other => return Err(format!("unsupported encoding {other:?}")),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+pub fn decompress(encoding: &str, raw: &[u8]) -> Result<Vec<u8>, String> {
+    let out = with_decoder(encoding, raw, |decoder| {
+        let mut out = Vec::new();
+        decoder.take(MAX_DECODED_BYTES + 1).read_to_end(&mut out)?;
+        Ok(out)
+    })?;
+    if out.len() as u64 > MAX_DECODED_BYTES {
+        return Err(format!("decoded body is over {MAX_DECODED_BYTES} bytes"));
+    }
+    Ok(out)
+}
+
+/// How many bytes `raw` inflates to, counting no further than `MAX_MEASURED_BYTES`.
+pub fn decoded_size(encoding: &str, raw: &[u8]) -> Result<u64, String> {
+    with_decoder(encoding, raw, |decoder| {
+        std::io::copy(&mut decoder.take(MAX_MEASURED_BYTES), &mut std::io::sink())
+    })
+}
+
+/// What `MEASURE_COMPRESSION` and `STORE_DECODED_BODY` record about an encoded capture.
+#[derive(Default)]
+pub struct Inflated {
+    pub decoded_size: Option<i64>,
+    pub compression_ratio: Option<f64>,
+    pub decoded_body: Option<String>,
+    pub decoded_from: Option<String>,
+}
+
+/// Inflates a received `encoding` body on the blocking pool for whichever of the two is on.
+/// A body that fails to inflate is logged and gets nothing; `what` names the request there.
+pub async fn inflate(state: &AppState, encoding: Option<String>, body: &Bytes, size: i64, dropped: bool, what: &str) -> Inflated {
+    let Some(encoding) = encoding.filter(|_| !body.is_empty()) else {
+        return Inflated::default();
+    };
+    let mut inflated = Inflated::default();
+    if state.measure_compression {
+        let (for_decoder, compressed) = (encoding.clone(), body.clone());
+        inflated.decoded_size = tokio::task::spawn_blocking(move || decoded_size(&for_decoder, &compressed))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|size| size)
+            .inspect_err(|e| tracing::warn!("MEASURE_COMPRESSION: {what}: {e}"))
+            .ok()
+            .map(|size| size as i64);
+        inflated.compression_ratio = inflated.decoded_size.map(|decoded| decoded as f64 / size as f64);
+    }
+    if state.store_decoded_body && !dropped {
+        let (for_decoder, compressed) = (encoding.clone(), body.clone());
+        match tokio::task::spawn_blocking(move || decompress(&for_decoder, &compressed))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|decoded| decoded)
+        {
+            Ok(decoded) => {
+                inflated.decoded_body = Some(BASE64.encode(decoded));
+                inflated.decoded_from = Some(encoding);
+            }
+            Err(e) => tracing::warn!("STORE_DECODED_BODY: {what}: {e}"),
+        }
+    }
+    inflated
+}
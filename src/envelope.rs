@@ -0,0 +1,60 @@
+use axum::{
+    Json,
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Value, json};
+
+/// Wraps every JSON `/api/` response as `{"data": ..., "total": ..., "error": ...}` so
+/// typed clients can rely on a single shape. `total` is the array length for lists and
+/// the handler's own `total` (if any) for objects; failures carry `data: null` and the
+/// handler's error message.
+pub async fn wrap(request: Request, next: Next) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    // Non-JSON successes (downloads, exports) and 304s are passed through untouched
+    if !is_json && (response.status().is_success() || response.status().is_redirection()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return parts.status.into_response();
+    };
+    let value: Value = if is_json {
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+    } else {
+        // Plain-text errors such as extractor rejections
+        json!({"error": String::from_utf8_lossy(&bytes).trim()})
+    };
+
+    let enveloped = if parts.status.is_success() {
+        let total = match &value {
+            Value::Array(items) => json!(items.len()),
+            Value::Object(object) => object.get("total").cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+        json!({"data": value, "total": total, "error": null})
+    } else {
+        let error = value
+            .get("error")
+            .filter(|e| e.as_str() != Some(""))
+            .cloned()
+            .unwrap_or_else(|| json!(parts.status.canonical_reason().unwrap_or("error")));
+        json!({"data": null, "total": null, "error": error})
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::CONTENT_TYPE);
+    (parts, Json(enveloped)).into_response()
+}
@@ -0,0 +1,175 @@
+use axum::body::Bytes;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{AppState, LoggedRequest};
+
+// How often a degraded logger retries the database when no new capture has come in to try
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// `FALLBACK_BUFFER`: captures whose insert failed, kept in memory (oldest dropped past
+/// capacity) until the database takes writes again. `FALLBACK_AFTER_FAILURES` consecutive
+/// failures mark storage as degraded, which `/health` and a `storage` notice report.
+pub struct Fallback {
+    capacity: usize,
+    threshold: u32,
+    buffered: Mutex<Buffered>,
+    // Held while writing the buffer back, so two flushes never insert the same capture
+    flushing: Mutex<()>,
+}
+
+#[derive(Default)]
+struct Buffered {
+    captures: VecDeque<(LoggedRequest, Bytes)>,
+    failures: u32,
+    degraded_since: Option<String>,
+    dropped: u64,
+    last_error: Option<String>,
+}
+
+impl Fallback {
+    pub fn new(capacity: usize, threshold: u32) -> Self {
+        Self {
+            capacity,
+            threshold: threshold.max(1),
+            buffered: Mutex::new(Buffered::default()),
+            flushing: Mutex::new(()),
+        }
+    }
+
+    /// Keeps a capture whose insert failed with `error`; true when this failure is the one
+    /// that makes storage degraded.
+    pub fn push(&self, req: LoggedRequest, body: Bytes, error: &str) -> bool {
+        let mut buffered = self.buffered.lock().unwrap();
+        buffered.failures += 1;
+        buffered.last_error = Some(error.to_string());
+        if buffered.captures.len() >= self.capacity {
+            buffered.captures.pop_front();
+            buffered.dropped += 1;
+        }
+        buffered.captures.push_back((req, body));
+        if buffered.degraded_since.is_none() && buffered.failures >= self.threshold {
+            buffered.degraded_since = Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+            return true;
+        }
+        false
+    }
+
+    /// Records a successful insert; true when buffered captures are waiting to be written back.
+    pub fn succeeded(&self) -> bool {
+        let mut buffered = self.buffered.lock().unwrap();
+        if buffered.captures.is_empty() {
+            buffered.failures = 0;
+        }
+        !buffered.captures.is_empty()
+    }
+
+    pub fn find(&self, id: &str) -> Option<LoggedRequest> {
+        let buffered = self.buffered.lock().unwrap();
+        buffered.captures.iter().find(|(req, _)| req.id == id).map(|(req, _)| req.clone())
+    }
+
+    /// Buffered captures, oldest first.
+    pub fn captures(&self) -> Vec<LoggedRequest> {
+        let buffered = self.buffered.lock().unwrap();
+        buffered.captures.iter().map(|(req, _)| req.clone()).collect()
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.buffered.lock().unwrap().degraded_since.is_some()
+    }
+
+    /// The `storage` object of `/health`, also sent to dashboard clients as a `storage` notice.
+    pub fn status(&self) -> serde_json::Value {
+        let buffered = self.buffered.lock().unwrap();
+        json!({
+            "degraded": buffered.degraded_since.is_some(),
+            "degraded_since": buffered.degraded_since,
+            "buffered": buffered.captures.len(),
+            "capacity": self.capacity,
+            "dropped": buffered.dropped,
+            "consecutive_failures": buffered.failures,
+            "last_error": buffered.last_error,
+        })
+    }
+
+    /// The `{"type":"storage"}` message telling clients storage became degraded or recovered.
+    pub fn notice(&self) -> String {
+        json!({"type": "storage", "storage": self.status()}).to_string()
+    }
+}
+
+/// Writes buffered captures back oldest first, stopping at the first failure. Once all of
+/// them are stored a degraded logger is healthy again and clients are told.
+pub fn flush(state: &AppState) {
+    let Some(fallback) = &state.fallback else {
+        return;
+    };
+    let Ok(_flushing) = fallback.flushing.try_lock() else {
+        return;
+    };
+    let mut flushed = 0;
+    loop {
+        let next = fallback.buffered.lock().unwrap().captures.front().cloned();
+        let Some((req, body)) = next else {
+            break;
+        };
+        // An insert whose eviction step failed is already stored; don't retry it forever
+        let stored = crate::store_request(state, &req, &body).or_else(|e| {
+            let exists = state.db.get().is_ok_and(|db| crate::fetch_request(&db, &req.id).is_some());
+            if exists { Ok(None) } else { Err(e) }
+        });
+        match stored {
+            Ok(_) => flushed += 1,
+            // `QUOTA_POLICY=reject` filled up meanwhile; retrying won't make room for it
+            Err(e) if e.to_string() == crate::QUOTA_REACHED => {
+                tracing::warn!("dropped buffered capture {}: {e}", req.id);
+            }
+            Err(e) => {
+                let mut buffered = fallback.buffered.lock().unwrap();
+                buffered.failures += 1;
+                buffered.last_error = Some(e.to_string());
+                return;
+            }
+        }
+        let mut buffered = fallback.buffered.lock().unwrap();
+        // Capacity may have pushed it out meanwhile; only drop it if it's still first
+        if buffered.captures.front().is_some_and(|(front, _)| front.id == req.id) {
+            buffered.captures.pop_front();
+        }
+    }
+
+    let recovered = {
+        let mut buffered = fallback.buffered.lock().unwrap();
+        buffered.failures = 0;
+        buffered.degraded_since.take().is_some()
+    };
+    if flushed > 0 {
+        tracing::info!("wrote {flushed} buffered captures back to the database");
+    }
+    if recovered {
+        tracing::info!("storage recovered");
+        let _ = state.tx.send(fallback.notice());
+    }
+}
+
+/// Retries the database every `RETRY_INTERVAL` while captures are buffered, so a quiet
+/// logger still writes them back once storage recovers.
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(RETRY_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let pending = state
+                .fallback
+                .as_ref()
+                .is_some_and(|f| !f.buffered.lock().unwrap().captures.is_empty());
+            if pending {
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || flush(&state)).await.ok();
+            }
+        }
+    });
+}
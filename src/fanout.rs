@@ -0,0 +1,56 @@
+use axum::http::HeaderMap;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::storage::Stored;
+use crate::{AppState, LoggedRequest, hexdump, replay};
+
+/// Passes a capture on once it's stored: `SINK_URL` and `CAPTURE_PIPE` get it whole, dashboard
+/// clients get it (and its `hexdump` frame) over the broadcast channel and `CHAIN_TARGET`
+/// replays it. A collapsed repeat only updates the original; a sampled-out capture goes
+/// nowhere. `head` is the start of a streamed body, which `body` then doesn't hold.
+pub fn announce(
+    state: &Arc<AppState>,
+    req: &LoggedRequest,
+    stored: &Stored,
+    body: &[u8],
+    head: Option<&[u8]>,
+    path: &str,
+    headers: &HeaderMap,
+) {
+    if let Some(original) = &stored.repeat_of {
+        let _ = state.tx.send(json!({"type": "update", "request": original}).to_string());
+        return;
+    }
+    if stored.sampled_out {
+        return;
+    }
+    tracing::info!("captured {} {} ({} bytes)", req.method, req.url, req.body_size);
+
+    // Sinks get the whole capture, spilled body included
+    if state.sink.is_some() || state.capture_pipe.is_some() {
+        let full = if req.spilled {
+            serde_json::to_string(&LoggedRequest { body: BASE64.encode(body), ..req.clone() })
+        } else {
+            serde_json::to_string(req)
+        };
+        let full = full.unwrap_or_default();
+        if let Some(sink) = &state.sink {
+            sink.send(full.clone());
+        }
+        if let Some(capture_pipe) = &state.capture_pipe {
+            capture_pipe.send(full);
+        }
+    }
+
+    let msg = json!({"type": "new", "request": req}).to_string();
+    let _ = state.tx.send(msg);
+    // Dumped now, while the body is at hand; only `?hexdump=1` connections pass it on
+    let _ = state.tx.send(hexdump::frame(&req.id, head.unwrap_or(body), req.body_size, state.hexdump_bytes));
+
+    if state.chain.as_ref().is_some_and(|c| c.matches(path, headers)) {
+        replay::Chain::spawn(state.clone(), req);
+    }
+}
@@ -0,0 +1,97 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::{AppState, MAX_SEARCH_LIMIT, pool};
+
+const DEFAULT_FEED_ITEMS: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct FeedItem {
+    id: String,
+    method: String,
+    path: String,
+    size: i64,
+    time: String,
+}
+
+/// Compact summaries of the newest requests for pollers. The `ETag` covers the items, so a
+/// matching `If-None-Match` (or an `If-Modified-Since` no older than the newest item) gets `304`.
+pub async fn feed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, pool::Unavailable> {
+    let limit = query.limit.unwrap_or(DEFAULT_FEED_ITEMS).clamp(1, MAX_SEARCH_LIMIT);
+    let items: Vec<FeedItem> = state
+        .db
+        .run(move |db| {
+            let mut stmt = db
+                .prepare(
+                    "SELECT id, method, url, body_size, timestamp FROM requests
+                     ORDER BY timestamp DESC, received_ns DESC, id DESC LIMIT ?1",
+                )
+                .unwrap();
+            stmt.query_map(rusqlite::params![limit], |row| {
+                let url: String = row.get(2)?;
+                let path = crate::replay::path_and_query(&url);
+                Ok(FeedItem {
+                    id: row.get(0)?,
+                    method: row.get(1)?,
+                    path: path.split('?').next().unwrap_or(path).to_string(),
+                    size: row.get(3)?,
+                    time: row.get(4)?,
+                })
+            })
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+        })
+        .await?;
+
+    let body = serde_json::to_vec(&items).unwrap_or_default();
+    let etag = format!("\"{:x}\"", Sha256::digest(&body));
+    let updated = items
+        .first()
+        .and_then(|item| DateTime::parse_from_rfc3339(&item.time).ok())
+        .map(|t| t.with_timezone(&Utc));
+
+    let not_modified = match headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        Some(tags) => tags.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"),
+        // HTTP dates have whole seconds, so compare at that precision
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+            .zip(updated)
+            .is_some_and(|(since, updated)| updated.timestamp() <= since.timestamp()),
+    };
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Some(updated) = updated
+        && let Ok(value) = HeaderValue::from_str(&updated.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    {
+        response_headers.insert(header::LAST_MODIFIED, value);
+    }
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    let updated = updated.map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    Ok((response_headers, Json(json!({"updated": updated, "items": items}))).into_response())
+}
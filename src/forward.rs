@@ -0,0 +1,203 @@
+use axum::{
+    Json,
+    body::Bytes,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::fmt;
+use std::time::Duration;
+
+use crate::mock;
+use crate::replay::{is_hop_by_hop, path_and_query};
+use crate::{AppState, unsupported_json};
+
+/// `FORWARD_URL`: every capture is also relayed upstream and the caller gets the upstream's
+/// answer, turning the logger into a recording proxy.
+pub struct Forward {
+    pub url: String,
+    pub timeout: Duration,
+    /// `FORWARD_PATH_REWRITE` prefix pairs, tried in order
+    pub rewrites: Vec<(String, String)>,
+}
+
+/// Why a forward produced no upstream response.
+pub enum ForwardError {
+    /// No complete response within `FORWARD_TIMEOUT_MS`
+    Timeout,
+    Failed(String),
+}
+
+/// What the upstream answered.
+pub struct Upstream {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Parses `FORWARD_PATH_REWRITE`: comma-separated `from=to` prefix pairs, e.g. `/hook=` to
+/// strip `/hook` or `/a=/b` to map `/a/...` to `/b/...`.
+pub fn parse_rewrites(spec: &[String]) -> Vec<(String, String)> {
+    spec.iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((from, to)) if from.starts_with('/') => {
+                (from.trim_end_matches('/').to_string(), to.trim_end_matches('/').to_string())
+            }
+            _ => panic!("Invalid FORWARD_PATH_REWRITE entry {entry:?}: expected \"/from=/to\""),
+        })
+        .collect()
+}
+
+impl Forward {
+    /// Applies the first rewrite whose prefix matches whole path segments; the query is kept.
+    fn rewrite<'a>(&self, path_and_query: &'a str) -> std::borrow::Cow<'a, str> {
+        let (path, query) = match path_and_query.find('?') {
+            Some(i) => path_and_query.split_at(i),
+            None => (path_and_query, ""),
+        };
+        for (from, to) in &self.rewrites {
+            if let Some(rest) = path.strip_prefix(from.as_str())
+                && (rest.is_empty() || rest.starts_with('/'))
+            {
+                // An emptied path is fine: it's appended to `FORWARD_URL`
+                return format!("{to}{rest}{query}").into();
+            }
+        }
+        path_and_query.into()
+    }
+
+    /// Sends the request as received (before any redaction) to `FORWARD_URL` + its path and
+    /// query, after `FORWARD_PATH_REWRITE`.
+    pub async fn send(
+        &self,
+        client: &reqwest::Client,
+        method: &Method,
+        url: &str,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> Result<Upstream, ForwardError> {
+        let target = format!("{}{}", self.url.trim_end_matches('/'), self.rewrite(path_and_query(url)));
+        let method = reqwest::Method::from_bytes(method.as_str().as_bytes())
+            .map_err(|e| ForwardError::Failed(e.to_string()))?;
+
+        let mut builder = client.request(method, &target).timeout(self.timeout).body(body);
+        for (name, value) in headers {
+            if !is_hop_by_hop(name.as_str()) {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+        }
+
+        let failed = |e: reqwest::Error| match e.is_timeout() {
+            true => ForwardError::Timeout,
+            false => ForwardError::Failed(format!("{target}: {e}")),
+        };
+        let resp = builder.send().await.map_err(failed)?;
+        let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let mut response_headers = HeaderMap::new();
+        for (name, value) in resp.headers() {
+            if is_hop_by_hop(name.as_str()) {
+                continue;
+            }
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_str().as_bytes()),
+                HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                response_headers.append(name, value);
+            }
+        }
+        let body = resp.bytes().await.map_err(failed)?;
+        Ok(Upstream {
+            status,
+            headers: response_headers,
+            body,
+        })
+    }
+}
+
+impl Upstream {
+    pub fn into_response(self) -> Response {
+        (self.status, self.headers, self.body).into_response()
+    }
+}
+
+impl ForwardError {
+    /// `504` for timeouts, `502` for everything else.
+    pub fn into_response(self) -> Response {
+        let status = match self {
+            ForwardError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ForwardError::Failed(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, Json(json!({"error": format!("upstream request failed: {self}")}))).into_response()
+    }
+}
+
+impl fmt::Display for ForwardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForwardError::Timeout => f.write_str("timeout"),
+            ForwardError::Failed(e) => f.write_str(e),
+        }
+    }
+}
+
+/// The hook's answer, buffered so the capture can record it; every one is already in memory.
+pub struct Answer {
+    pub parts: axum::http::response::Parts,
+    pub body: Bytes,
+    /// Why `FORWARD_URL` gave no upstream response
+    pub forward_error: Option<String>,
+}
+
+impl Answer {
+    pub async fn buffer(response: Response, forward_error: Option<String>) -> Self {
+        let (parts, body) = response.into_parts();
+        Self {
+            parts,
+            body: axum::body::to_bytes(body, usize::MAX).await.unwrap_or_default(),
+            forward_error,
+        }
+    }
+}
+
+/// The answer for a request that is recorded but neither relayed nor mocked: `415` for
+/// invalid JSON `REQUIRE_JSON_STORE_REJECTED` keeps, `400` for WebSocket upgrade attempts.
+pub fn refusal(invalid_json: bool, websocket_attempt: bool) -> Option<Response> {
+    if invalid_json {
+        Some(unsupported_json())
+    } else if websocket_attempt {
+        let error = "the hook only accepts plain HTTP requests, not WebSocket upgrades";
+        Some((StatusCode::BAD_REQUEST, Json(json!({"error": error}))).into_response())
+    } else {
+        None
+    }
+}
+
+/// The upstream's response in proxy mode, otherwise the `RESPONSE_RULES_FILE`,
+/// `METHOD_RESPONSES` or default canned response for `path`, with any `__status` override.
+pub async fn answer(
+    state: &AppState,
+    method: &Method,
+    url: &str,
+    path: &str,
+    headers: &HeaderMap,
+    body: &Bytes,
+    status: Option<u16>,
+) -> Answer {
+    if let Some(forward) = &state.forward {
+        return match forward.send(&state.http, method, url, headers, body.clone()).await {
+            Ok(upstream) => Answer::buffer(upstream.into_response(), None).await,
+            Err(e) => {
+                tracing::warn!("forward of {method} {url} failed: {e}");
+                let error = e.to_string();
+                Answer::buffer(e.into_response(), Some(error)).await
+            }
+        };
+    }
+    let mut canned = mock::match_rules(&state.response_rules, path)
+        .or_else(|| state.method_responses.get(method.as_str()).cloned())
+        .unwrap_or_else(|| state.default_response.clone());
+    if let Some(status) = status {
+        canned.status = status;
+    }
+    Answer::buffer(canned.into_response(), None).await
+}
@@ -0,0 +1,149 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::{AppState, LoggedRequest, SearchQuery, pool, select_requests};
+
+/// All stored requests (or those matching the `GET /api/requests` filters) as a HAR 1.2 log.
+pub async fn export(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let (where_clause, mut params) = match query.where_clause() {
+        Ok(sql) => sql,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()),
+    };
+    let page = match query.limit {
+        Some(limit) => {
+            params.push(rusqlite::types::Value::Integer(limit.max(0)));
+            params.push(rusqlite::types::Value::Integer(query.offset.unwrap_or(0).max(0)));
+            "LIMIT ? OFFSET ?"
+        }
+        None => "",
+    };
+    let requests = state
+        .db
+        .run(move |db| select_requests(db, &where_clause, page, &params))
+        .await?;
+
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+            "entries": requests.iter().map(entry).collect::<Vec<_>>(),
+        }
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"http_logger.har\""),
+        ],
+        Json(har),
+    )
+        .into_response())
+}
+
+fn entry(req: &LoggedRequest) -> Value {
+    let headers: Vec<(String, String)> = serde_json::from_str(&req.headers).unwrap_or_default();
+    let mime_type = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.clone())
+        .unwrap_or_default();
+
+    let mut request = json!({
+        "method": req.method,
+        "url": absolute_url(req),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": headers.iter().map(|(k, v)| json!({"name": k, "value": v})).collect::<Vec<_>>(),
+        "queryString": query_string(&req.url),
+        "headersSize": -1,
+        "bodySize": req.body_size,
+    });
+    if let Some(post_data) = post_data(req, &mime_type) {
+        request["postData"] = post_data;
+    }
+
+    let time = req.duration_ms.unwrap_or(0);
+    json!({
+        "startedDateTime": req.timestamp,
+        "time": time,
+        "request": request,
+        "response": response(req),
+        "cache": {},
+        "timings": {"send": 0, "wait": time, "receive": 0},
+    })
+}
+
+/// What the hook answered. Response headers aren't recorded, and the body only as its first
+/// `MAX_STORED_RESPONSE_BYTES`; captures from before responses were recorded have status 0.
+fn response(req: &LoggedRequest) -> Value {
+    let status = req.response_status.unwrap_or(0);
+    let status_text = u16::try_from(status)
+        .ok()
+        .and_then(|s| StatusCode::from_u16(s).ok())
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    let text = req.response_body.as_deref().unwrap_or("");
+    let mut content = json!({"size": text.len(), "mimeType": "x-unknown"});
+    if !text.is_empty() {
+        content["text"] = json!(text);
+    }
+    let mut response = json!({
+        "status": status,
+        "statusText": status_text,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": [],
+        "content": content,
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": if req.response_body.is_some() { text.len() as i64 } else { -1 },
+    });
+    if let Some(error) = &req.forward_error {
+        response["_error"] = json!(error);
+    }
+    response
+}
+
+/// HAR needs absolute URLs; origin-form targets are resolved against the recorded host.
+pub fn absolute_url(req: &LoggedRequest) -> String {
+    if req.url.contains("://") {
+        return req.url.clone();
+    }
+    format!("http://{}{}", req.host.as_deref().unwrap_or("localhost"), req.url)
+}
+
+fn query_string(url: &str) -> Vec<Value> {
+    let Some((_, query)) = url.split_once('?') else {
+        return Vec::new();
+    };
+    form_urlencoded::parse(query.as_bytes())
+        .map(|(name, value)| json!({"name": name, "value": value}))
+        .collect()
+}
+
+fn post_data(req: &LoggedRequest, mime_type: &str) -> Option<Value> {
+    // Exports are lists too, so spilled bodies are only referenced
+    if req.spilled {
+        let comment = format!("body not inlined, see /api/requests/{}/body", req.id);
+        return Some(json!({"mimeType": mime_type, "text": "", "comment": comment}));
+    }
+    if req.body.is_empty() {
+        return None;
+    }
+    let body = BASE64.decode(&req.body).ok()?;
+    Some(match String::from_utf8(body) {
+        Ok(text) => json!({"mimeType": mime_type, "text": text}),
+        // HAR has no postData encoding field, so binary bodies stay base64 and say so
+        Err(_) => json!({"mimeType": mime_type, "text": req.body, "comment": "base64-encoded"}),
+    })
+}
@@ -0,0 +1,85 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{AppState, fetch_request, pool};
+
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Deserialize)]
+pub struct HexdumpQuery {
+    bytes: Option<usize>,
+}
+
+/// `GET /api/requests/{id}/hexdump`: the first `?bytes=` (default `HEXDUMP_BYTES`) bytes
+/// of the stored body as plain text.
+pub async fn single(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<HexdumpQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let body = state
+        .db
+        .run(move |db| fetch_request(db, &id).map(|req| crate::stored_body(db, &req)))
+        .await?;
+    let Some(body) = body else {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response());
+    };
+    let limit = query.bytes.unwrap_or(state.hexdump_bytes);
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        dump(&body[..body.len().min(limit)]),
+    )
+        .into_response())
+}
+
+/// The `{"type":"hexdump"}` frame that `/ws?hexdump=1` sends after each new capture. It's
+/// built from the body while that's still in memory, so a spilled one is dumped as well;
+/// `body` may be just its first bytes, and `total` is the size of all of it.
+pub fn frame(id: &str, body: &[u8], total: i64, limit: usize) -> String {
+    let shown = body.len().min(limit);
+    json!({
+        "type": "hexdump",
+        "id": id,
+        "shown": shown,
+        "total": total,
+        "dump": dump(&body[..shown]),
+    })
+    .to_string()
+}
+
+/// The capture a broadcast `hexdump` frame belongs to; `None` for every other message.
+pub fn frame_id(msg: &str) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(msg).ok()?;
+    if value.get("type")?.as_str()? != "hexdump" {
+        return None;
+    }
+    Some(value.get("id")?.as_str()?.to_string())
+}
+
+/// `hexdump -C`-style lines: offset, sixteen hex bytes in two groups of eight, printable ASCII.
+pub fn dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        out.push_str(&format!("{:08x}  ", line * BYTES_PER_LINE));
+        for i in 0..BYTES_PER_LINE {
+            match chunk.get(i) {
+                Some(b) => out.push_str(&format!("{b:02x} ")),
+                None => out.push_str("   "),
+            }
+            if i == BYTES_PER_LINE / 2 - 1 {
+                out.push(' ');
+            }
+        }
+        out.push(' ');
+        out.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
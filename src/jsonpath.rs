@@ -0,0 +1,150 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::{AppState, decode, fetch_request, pool};
+
+#[derive(Deserialize)]
+pub struct PathQuery {
+    path: Option<String>,
+}
+
+enum Step {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    // `..`: the step applied to the node and every node below it
+    Descend(Box<Step>),
+}
+
+/// `GET /api/requests/{id}/json?path=`: the values a JSONPath (default `$`) selects from the
+/// decompressed JSON body, as `matches`. Supports `.name`, `['name']`, `[n]` (negative counts
+/// from the end), `*` and `..`; a path that selects nothing gives an empty list.
+pub async fn select(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<PathQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let path = query.path.unwrap_or_else(|| "$".to_string());
+    let steps = match parse(&path) {
+        Ok(steps) => steps,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()),
+    };
+    let found = state
+        .db
+        .run(move |db| {
+            let req = fetch_request(db, &id)?;
+            let body = decode::decompressed_body(db, &req);
+            Some((req, body))
+        })
+        .await?;
+    let Some((req, body)) = found else {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response());
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            let error = format!("could not decode body: {e}");
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": error}))).into_response());
+        }
+    };
+    let Ok(value) = serde_json::from_slice::<Value>(&body) else {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "body is not JSON"}))).into_response());
+    };
+
+    let mut nodes = vec![&value];
+    for step in &steps {
+        nodes = nodes.into_iter().flat_map(|node| apply(step, node)).collect();
+    }
+    Ok(Json(json!({"id": req.id, "path": path, "matches": nodes})).into_response())
+}
+
+fn parse(path: &str) -> Result<Vec<Step>, String> {
+    let Some(mut rest) = path.trim().strip_prefix('$') else {
+        return Err(format!("invalid path {path:?}: must start with '$'"));
+    };
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        let (step, after) = if let Some(after) = rest.strip_prefix("..") {
+            let (step, after) = parse_step(after, path)?;
+            (Step::Descend(Box::new(step)), after)
+        } else if let Some(after) = rest.strip_prefix('.') {
+            parse_step(after, path)?
+        } else if rest.starts_with('[') {
+            parse_step(rest, path)?
+        } else {
+            return Err(format!("invalid path {path:?}: unexpected {rest:?}"));
+        };
+        steps.push(step);
+        rest = after;
+    }
+    Ok(steps)
+}
+
+/// One name, `*` or bracketed selector from the front of `rest`.
+fn parse_step<'a>(rest: &'a str, path: &str) -> Result<(Step, &'a str), String> {
+    if let Some(inner) = rest.strip_prefix('[') {
+        let Some(end) = inner.find(']') else {
+            return Err(format!("invalid path {path:?}: unclosed '['"));
+        };
+        let selector = inner[..end].trim();
+        let step = if selector == "*" {
+            Step::Wildcard
+        } else if let Some(name) = selector
+            .strip_prefix('\'')
+            .and_then(|s| s.strip_suffix('\''))
+            .or_else(|| selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+        {
+            Step::Child(name.to_string())
+        } else {
+            let index = selector
+                .parse()
+                .map_err(|_| format!("invalid path {path:?}: bad selector [{selector}]"))?;
+            Step::Index(index)
+        };
+        return Ok((step, &inner[end + 1..]));
+    }
+    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+    let step = match &rest[..end] {
+        "" => return Err(format!("invalid path {path:?}: empty name")),
+        "*" => Step::Wildcard,
+        name => Step::Child(name.to_string()),
+    };
+    Ok((step, &rest[end..]))
+}
+
+fn apply<'a>(step: &Step, node: &'a Value) -> Vec<&'a Value> {
+    match (step, node) {
+        (Step::Child(name), Value::Object(object)) => object.get(name).into_iter().collect(),
+        (Step::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            usize::try_from(index).ok().and_then(|i| items.get(i)).into_iter().collect()
+        }
+        (Step::Wildcard, Value::Object(object)) => object.values().collect(),
+        (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+        (Step::Descend(step), node) => {
+            let mut below = vec![node];
+            descendants(node, &mut below);
+            below.into_iter().flat_map(|node| apply(step, node)).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn descendants<'a>(node: &'a Value, out: &mut Vec<&'a Value>) {
+    let children: Vec<&Value> = match node {
+        Value::Object(object) => object.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => return,
+    };
+    for child in children {
+        out.push(child);
+        descendants(child, out);
+    }
+}
@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+use tokio::sync::broadcast;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// `MakeWriter` that prints each formatted tracing event to stdout and, when
+/// log streaming is enabled, also publishes it to `/ws/logs` subscribers.
+#[derive(Clone)]
+pub struct LogStream {
+    tx: Option<broadcast::Sender<String>>,
+}
+
+impl LogStream {
+    pub fn new(tx: Option<broadcast::Sender<String>>) -> Self {
+        Self { tx }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogStream {
+    type Writer = EventWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventWriter {
+            buf: Vec::new(),
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+/// Buffers a single event; the subscriber drops it once the event is fully written.
+pub struct EventWriter {
+    buf: Vec<u8>,
+    tx: Option<broadcast::Sender<String>>,
+}
+
+impl Write for EventWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventWriter {
+    fn drop(&mut self) {
+        let _ = io::stdout().write_all(&self.buf);
+        if let Some(tx) = &self.tx {
+            let line = String::from_utf8_lossy(&self.buf).trim_end().to_string();
+            let _ = tx.send(line);
+        }
+    }
+}
@@ -1,24 +1,131 @@
+mod admission;
+mod alert;
+mod auth;
+mod bodies;
+mod curl;
+mod decode;
+mod envelope;
+mod fallback;
+mod fanout;
+mod feed;
+mod forward;
+mod har;
+mod hexdump;
+mod jsonpath;
+mod logstream;
+mod mock;
+mod openapi;
+mod pipe;
+mod pool;
+mod preview;
+mod replay;
+mod retention;
+mod rotation;
+mod rules;
+mod sampling;
+mod sink;
+mod snapshots;
+mod storage;
+mod table;
+mod verify;
+
+use axum::extract::DefaultBodyLimit;
+use axum::extract::connect_info::{ConnectInfo, Connected};
+use axum::serve::IncomingStream;
 use axum::{
-    Router,
-    extract::{Path, State, WebSocketUpgrade, ws},
-    http::{HeaderMap, Method, StatusCode, Uri, header},
+    BoxError, Json, Router,
+    error_handling::HandleErrorLayer,
+    extract::{Path, Query, State, WebSocketUpgrade, ws},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, Uri, header},
     response::{Html, IntoResponse, Response},
-    routing::{any, delete, get},
+    routing::{any, get, post},
 };
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
-use rusqlite::Connection;
+use rusqlite::blob::ZeroBlob;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, Transaction, TransactionBehavior};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
-use axum::extract::DefaultBodyLimit;
+use tower::ServiceBuilder;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
 
 const INDEX_HTML: &str = include_str!("../static/index.html");
 const APP_JS: &str = include_str!("../static/app.js");
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
 const PAGE_SIZE: usize = 100;
+const MAX_STORED_RESPONSE_BYTES: usize = 4096;
+// How `store_request` reports an insert that `QUOTA_POLICY=reject` turned away
+const QUOTA_REACHED: &str = "capture quota reached";
+const SELECT_COLUMNS: &str = "id, timestamp, method, url, headers, body, body_size, form, expect_continue, gap_ms, body_dropped, spec_valid, spec_error, tags, body_sha256, host, pinned, websocket_attempt, chunks, chain_result, connection_seq, remote_addr, ndjson, ndjson_count, response_status, body_text, body_file, idempotency_key, duplicate_of, forward_error, label, body_redacted, response_body, duration_ms, decoded_size, compression_ratio, endpoint, raw_method, header_order, repeat_count, received_ns, body_blob, decoded_body, decoded_from, replay_of, EXISTS (SELECT 1 FROM body_blobs WHERE body_blobs.id = requests.id)";
+
+// Columns added after the initial schema; applied in order to existing databases.
+// The schema version is the initial table (1) plus one per migration.
+const COLUMN_MIGRATIONS: &[(&str, &str)] = &[
+    ("form", "TEXT"),
+    ("expect_continue", "INTEGER NOT NULL DEFAULT 0"),
+    ("gap_ms", "INTEGER"),
+    ("body_dropped", "INTEGER NOT NULL DEFAULT 0"),
+    ("spec_valid", "INTEGER"),
+    ("spec_error", "TEXT"),
+    ("tags", "TEXT NOT NULL DEFAULT '[]'"),
+    ("body_sha256", "TEXT"),
+    ("host", "TEXT"),
+    ("pinned", "INTEGER NOT NULL DEFAULT 0"),
+    ("websocket_attempt", "INTEGER NOT NULL DEFAULT 0"),
+    ("chunks", "TEXT"),
+    ("chain_result", "TEXT"),
+    ("connection_seq", "INTEGER"),
+    ("remote_addr", "TEXT"),
+    ("ndjson", "TEXT"),
+    ("ndjson_count", "INTEGER"),
+    ("response_status", "INTEGER"),
+    ("body_text", "TEXT"),
+    ("body_file", "TEXT"),
+    ("idempotency_key", "TEXT"),
+    ("duplicate_of", "TEXT"),
+    ("forward_error", "TEXT"),
+    ("label", "TEXT"),
+    ("body_redacted", "INTEGER NOT NULL DEFAULT 0"),
+    ("response_body", "TEXT"),
+    ("duration_ms", "INTEGER"),
+    ("decoded_size", "INTEGER"),
+    ("compression_ratio", "REAL"),
+    ("endpoint", "TEXT"),
+    ("raw_method", "TEXT"),
+    ("header_order", "TEXT"),
+    ("repeat_count", "INTEGER NOT NULL DEFAULT 0"),
+    ("received_ns", "INTEGER"),
+    ("body_blob", "BLOB"),
+    ("decoded_body", "TEXT"),
+    ("decoded_from", "TEXT"),
+    ("replay_of", "TEXT"),
+];
 
-#[derive(Clone, Serialize, Deserialize)]
+// WebSocket protocol: the version is bumped whenever an existing message changes
+// meaning; new commands are additive and only advertised through `CAPABILITIES`
+const PROTOCOL_VERSION: u32 = 1;
+const CAPABILITIES: &[&str] = &["load_more", "pin", "get_stats", "subscribe_stats"];
+// Floor and default for `subscribe_stats` intervals
+const MIN_STATS_INTERVAL_MS: u64 = 500;
+const DEFAULT_STATS_INTERVAL_MS: u64 = 5000;
+// Last frame every WebSocket client gets before the server exits
+const SHUTDOWN_MESSAGE: &str = r#"{"type":"shutdown"}"#;
+
+// Numeric columns that `/api/histogram` may bucket
+const HISTOGRAM_FIELDS: &[&str] = &["body_size", "gap_ms", "duration_ms", "compression_ratio"];
+const MAX_HISTOGRAM_BUCKETS: usize = 100;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 struct LoggedRequest {
     id: String,
     timestamp: String,
@@ -27,199 +134,1558 @@ struct LoggedRequest {
     headers: String,
     body: String,
     body_size: i64,
+    form: Option<String>,
+    expect_continue: bool,
+    gap_ms: Option<i64>,
+    body_dropped: bool,
+    spec_valid: Option<bool>,
+    spec_error: Option<String>,
+    tags: String,
+    body_sha256: Option<String>,
+    host: Option<String>,
+    pinned: bool,
+    websocket_attempt: bool,
+    /// JSON array of data frame sizes as read, for `Transfer-Encoding: chunked` requests
+    chunks: Option<String>,
+    /// JSON outcome of the `CHAIN_TARGET` replay, once it has finished
+    chain_result: Option<String>,
+    /// 1 for the first request on a TCP connection, 2 for the next one reusing it, ...
+    connection_seq: Option<i64>,
+    /// Client IP: the TCP peer, or the first `X-Forwarded-For` hop with `TRUST_FORWARDED_FOR`
+    remote_addr: Option<String>,
+    /// JSON array of the records of an NDJSON body, one per line
+    ndjson: Option<String>,
+    ndjson_count: Option<i64>,
+    /// Status the hook answered with (the upstream's in `FORWARD_URL` mode)
+    response_status: Option<i64>,
+    /// Decoded body for `LIKE` search; only text bodies up to `BODY_TEXT_MAX_BYTES`
+    body_text: Option<String>,
+    /// Where the body lives when it's over `BODY_INLINE_LIMIT`. Only single-capture endpoints
+    /// read it back into `body`; lists, history and WebSocket messages leave `body` empty
+    #[serde(skip)]
+    body_file: Option<String>,
+    /// Whether the body is in a `BODY_INLINE_LIMIT` file rather than in `body`
+    spilled: bool,
+    /// Value of the IDEMPOTENCY_HEADER header
+    idempotency_key: Option<String>,
+    /// Id of the first capture that carried the same idempotency key
+    duplicate_of: Option<String>,
+    /// Why the FORWARD_URL relay failed (timeout for FORWARD_TIMEOUT_MS)
+    forward_error: Option<String>,
+    /// Sender-chosen label taken from the LABEL_PARAM query parameter
+    label: Option<String>,
+    /// Whether REDACT_JSON_FIELDS rewrote the body, so it no longer matches body_sha256
+    body_redacted: bool,
+    /// What the hook answered, as UTF-8 (lossy), cut at `MAX_STORED_RESPONSE_BYTES`
+    response_body: Option<String>,
+    /// Milliseconds from arrival until the response was ready (excluding `__delay`)
+    duration_ms: Option<i64>,
+    /// Size after undoing `Content-Encoding`, with `MEASURE_COMPRESSION` (capped at 256 MB)
+    decoded_size: Option<i64>,
+    /// `decoded_size / body_size`; far above ~20 is worth a look
+    compression_ratio: Option<f64>,
+    /// Named capture endpoint: the first path segment after `/hook/`, limited to `HOOKS` if set
+    endpoint: Option<String>,
+    /// Method exactly as received, casing included (`RECORD_HEADER_ORDER`)
+    raw_method: Option<String>,
+    /// `RECORD_HEADER_ORDER`: JSON array of `{index, name, value}` in received order, values
+    /// untruncated. Names arrive lowercased and repeats of a name sit together at its first
+    /// position, as hyper delivers them
+    header_order: Option<String>,
+    /// Identical requests from the same address folded into this one (`COLLAPSE_REPEATS_MS`)
+    repeat_count: i64,
+    /// Receipt time in Unix nanoseconds; with `TIMESTAMP_PRECISION=ns` unique and strictly increasing,
+    /// otherwise `timestamp`'s millisecond
+    received_ns: Option<i64>,
+    /// `STORE_DECODED_BODY`: the body with `Content-Encoding` undone, base64; `body` stays as received
+    decoded_body: Option<String>,
+    /// The `Content-Encoding` that `decoded_body` was inflated from
+    decoded_from: Option<String>,
+    /// For a replay recorded with `?record=1`: the id of the capture that was replayed
+    replay_of: Option<String>,
+    /// Short body summary for list views; derived on read, not stored
+    preview: Option<String>,
 }
 
 struct AppState {
-    db: Mutex<Connection>,
+    db: pool::Db,
     tx: broadcast::Sender<String>,
     max_requests: i64,
+    reject_over_quota: bool,
+    capture_quota: i64,
+    parse_form: bool,
+    response_rules: Vec<mock::ResponseRule>,
+    method_responses: HashMap<String, mock::CannedResponse>,
+    default_response: mock::CannedResponse,
+    log_tx: Option<broadcast::Sender<String>>,
+    sink: Option<sink::Sink>,
+    capture_pipe: Option<pipe::CapturePipe>,
+    last_capture: Mutex<Option<Instant>>,
+    drop_body_paths: Vec<String>,
+    spec: Option<openapi::Spec>,
+    tag_rules: Vec<rules::CaptureRule>,
+    max_header_value_bytes: usize,
+    static_dir: Option<String>,
+    http: reqwest::Client,
+    require_json: bool,
+    store_rejected_json: bool,
+    redact_json_fields: Vec<String>,
+    capture_content_types: Vec<String>,
+    require_headers: Vec<String>,
+    body_limits: Vec<(String, usize)>,
+    chain: Option<replay::Chain>,
+    rotation: Option<rotation::Rotation>,
+    // With `DB_ROTATION`, the file snapshots are kept in; otherwise they share `db`
+    snapshot_db: Option<pool::Db>,
+    ws_max_message_bytes: usize,
+    trust_forwarded_for: bool,
+    hook_token: Option<String>,
+    hook_response_headers: bool,
+    forward: Option<forward::Forward>,
+    body_text_max_bytes: usize,
+    body_store: bodies::BodyStore,
+    idempotency_header: Option<String>,
+    skip_duplicates: bool,
+    label_param: Option<String>,
+    confirm_storage: bool,
+    measure_compression: bool,
+    store_decoded_body: bool,
+    hooks: Vec<String>,
+    record_header_order: bool,
+    collapse_repeats_ms: u64,
+    hexdump_bytes: usize,
+    replay_jobs: replay::Jobs,
+    nanosecond_timestamps: bool,
+    last_received_ns: AtomicI64,
+    blob_bodies: bool,
+    sampler: Option<sampling::Sampler>,
+    fallback: Option<fallback::Fallback>,
 }
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
 
+    let log_tx = env_flag("ENABLE_LOG_STREAM", false).then(|| broadcast::channel::<String>(256).0);
+    tracing_subscriber::fmt()
+        .with_writer(logstream::LogStream::new(log_tx.clone()))
+        .with_ansi(log_tx.is_none())
+        .init();
+
     let port: u16 = std::env::var("PORT")
         .ok()
         .and_then(|v| v.parse().ok())
         .unwrap_or(3000);
-    let max_requests: i64 = std::env::var("MAX_REQUESTS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(1000);
+    let max_requests: i64 = env_or("MAX_REQUESTS", 1000);
+    let capture_quota: i64 = env_or("CAPTURE_QUOTA", max_requests);
     let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "./data.db".to_string());
+    let parse_form = env_flag("PARSE_FORM", true);
+    let response_rules = std::env::var("RESPONSE_RULES_FILE")
+        .ok()
+        .map(|path| mock::load_rules(&path).expect("Failed to load RESPONSE_RULES_FILE"))
+        .unwrap_or_default();
+    let drop_body_paths = env_list("DROP_BODY_PATHS");
+    let max_header_value_bytes: usize = env_or("MAX_HEADER_VALUE_BYTES", 0);
+    let spec = std::env::var("OPENAPI_SPEC_PATH").ok().map(|path| {
+        let base = std::env::var("OPENAPI_BASE_PATH").unwrap_or_else(|_| "/hook".to_string());
+        openapi::Spec::load(&path, &base).expect("Failed to load OPENAPI_SPEC_PATH")
+    });
+    let tag_rules = std::env::var("TAG_RULES_FILE")
+        .ok()
+        .map(|path| rules::load_rules(&path).expect("Failed to load TAG_RULES_FILE"))
+        .unwrap_or_default();
+    let body_limits = std::env::var("BODY_LIMITS")
+        .ok()
+        .map(|v| parse_body_limits(&v).expect("Invalid BODY_LIMITS"))
+        .unwrap_or_default();
+    let chain = std::env::var("CHAIN_TARGET").ok().map(|target| replay::Chain {
+        target,
+        path: std::env::var("CHAIN_PATH")
+            .ok()
+            .map(|p| regex::Regex::new(&p).expect("Invalid CHAIN_PATH")),
+        preserve_path: env_flag("CHAIN_PRESERVE_PATH", false),
+    });
+    let method_responses = std::env::var("METHOD_RESPONSES")
+        .ok()
+        .map(|v| mock::parse_method_responses(&v).expect("Invalid METHOD_RESPONSES"))
+        .unwrap_or_default();
 
-    let conn = Connection::open(&db_path).expect("Failed to open SQLite database");
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS requests (
-            id TEXT PRIMARY KEY,
-            timestamp TEXT NOT NULL,
-            method TEXT NOT NULL,
-            url TEXT NOT NULL,
-            headers TEXT NOT NULL,
-            body TEXT NOT NULL DEFAULT '',
-            body_size INTEGER NOT NULL DEFAULT 0
-        )",
-    )
-    .expect("Failed to create table");
+    let default_response = mock::CannedResponse::from_env().expect("Invalid hook response");
+    let rotation = match std::env::var("DB_ROTATION").ok().as_deref() {
+        None | Some("") | Some("none") => None,
+        Some("daily") => Some(rotation::Rotation::new(&db_path, env_or("DB_ROTATION_KEEP", 0))),
+        Some(other) => panic!("Invalid DB_ROTATION {other:?}: expected \"daily\""),
+    };
+    let skip_duplicates = match std::env::var("IDEMPOTENCY_DUPLICATES").ok().as_deref() {
+        None | Some("") | Some("mark") => false,
+        Some("skip") => true,
+        Some(other) => panic!("Invalid IDEMPOTENCY_DUPLICATES {other:?}: expected \"mark\" or \"skip\""),
+    };
+    let reject_over_quota = match std::env::var("QUOTA_POLICY").ok().as_deref() {
+        None | Some("") | Some("evict") => false,
+        Some("reject") => true,
+        Some(other) => panic!("Invalid QUOTA_POLICY {other:?}: expected \"evict\" or \"reject\""),
+    };
+    let nanosecond_timestamps = match std::env::var("TIMESTAMP_PRECISION").ok().as_deref() {
+        None | Some("") | Some("ms") => false,
+        Some("ns") => true,
+        Some(other) => panic!("Invalid TIMESTAMP_PRECISION {other:?}: expected \"ms\" or \"ns\""),
+    };
+    let blob_bodies = match std::env::var("BODY_STORAGE").ok().as_deref() {
+        None | Some("") | Some("base64") => false,
+        Some("blob") => true,
+        Some(other) => panic!("Invalid BODY_STORAGE {other:?}: expected \"base64\" or \"blob\""),
+    };
+    let pool_size = env_or("DB_POOL_SIZE", 4);
+    let db = match &rotation {
+        Some(rotation) => rotation.open_current(pool_size),
+        None => pool::Db::open(&db_path, pool_size),
+    }
+    .expect("Failed to open SQLite database");
+    let snapshot_db = rotation.as_ref().map(|rotation| {
+        pool::Db::open(&rotation.snapshots_path().to_string_lossy(), 2).expect("Failed to open the snapshot database")
+    });
+
+    let startup = db.get().expect("Failed to get a database connection");
+    match bodies::remove_orphans(&startup) {
+        Ok(0) => {}
+        Ok(removed) => tracing::info!("removed {removed} bodies of uploads cut off by a restart"),
+        Err(e) => tracing::warn!("failed to remove orphaned bodies: {e}"),
+    }
+
+    if env_flag("CLEAR_ON_START", false) {
+        let cleared = bodies::delete_requests(&startup, "WHERE 1 = 1", &[]).expect("Failed to clear requests");
+        tracing::info!("CLEAR_ON_START: cleared {cleared} stored requests");
+    }
+    drop(startup);
 
     let (tx, _) = broadcast::channel::<String>(100);
 
+    let sink = std::env::var("SINK_URL").ok().map(|url| {
+        sink::Sink::spawn(url, env_or("SINK_QUEUE_SIZE", 1000), env_or("SINK_RETRIES", 3))
+    });
+
     let state = Arc::new(AppState {
-        db: Mutex::new(conn),
+        db,
         tx,
         max_requests,
+        reject_over_quota,
+        capture_quota,
+        parse_form,
+        response_rules,
+        method_responses,
+        default_response,
+        log_tx,
+        sink,
+        capture_pipe: std::env::var("CAPTURE_PIPE").ok().map(pipe::CapturePipe::spawn),
+        last_capture: Mutex::new(None),
+        drop_body_paths,
+        spec,
+        tag_rules,
+        max_header_value_bytes,
+        static_dir: std::env::var("STATIC_DIR").ok(),
+        http: reqwest::Client::new(),
+        require_json: env_flag("REQUIRE_JSON", false),
+        store_rejected_json: env_flag("REQUIRE_JSON_STORE_REJECTED", false),
+        redact_json_fields: env_list("REDACT_JSON_FIELDS"),
+        capture_content_types: env_list("CAPTURE_CONTENT_TYPES")
+            .into_iter()
+            .map(|t| t.to_ascii_lowercase())
+            .collect(),
+        require_headers: env_list("REQUIRE_HEADERS"),
+        body_limits,
+        chain,
+        rotation,
+        snapshot_db,
+        ws_max_message_bytes: env_or("WS_MAX_MESSAGE_BYTES", 64 * 1024),
+        trust_forwarded_for: env_flag("TRUST_FORWARDED_FOR", false),
+        hook_token: std::env::var("HOOK_TOKEN").ok().filter(|t| !t.is_empty()),
+        hook_response_headers: env_flag("HOOK_RESPONSE_HEADERS", false),
+        body_text_max_bytes: env_or("BODY_TEXT_MAX_BYTES", 64 * 1024),
+        body_store: bodies::BodyStore::from_env(&db_path),
+        idempotency_header: std::env::var("IDEMPOTENCY_HEADER").ok().filter(|h| !h.is_empty()),
+        skip_duplicates,
+        confirm_storage: env_flag("CONFIRM_STORAGE", false),
+        measure_compression: env_flag("MEASURE_COMPRESSION", false),
+        store_decoded_body: env_flag("STORE_DECODED_BODY", false),
+        hooks: env_list("HOOKS"),
+        record_header_order: env_flag("RECORD_HEADER_ORDER", false),
+        collapse_repeats_ms: env_or("COLLAPSE_REPEATS_MS", 0),
+        hexdump_bytes: env_or("HEXDUMP_BYTES", 512),
+        replay_jobs: replay::Jobs::default(),
+        nanosecond_timestamps,
+        last_received_ns: AtomicI64::new(0),
+        blob_bodies,
+        sampler: Some(env_or("SAMPLE_ABOVE_RPS", 0.0))
+            .filter(|&rps| rps > 0.0)
+            .map(sampling::Sampler::new),
+        fallback: Some(env_or("FALLBACK_BUFFER", 0usize))
+            .filter(|&capacity| capacity > 0)
+            .map(|capacity| fallback::Fallback::new(capacity, env_or("FALLBACK_AFTER_FAILURES", 3))),
+        label_param: std::env::var("LABEL_PARAM").ok().filter(|p| !p.is_empty()),
+        forward: std::env::var("FORWARD_URL").ok().map(|url| forward::Forward {
+            url,
+            timeout: Duration::from_millis(env_or("FORWARD_TIMEOUT_MS", 30_000)),
+            rewrites: forward::parse_rewrites(&env_list("FORWARD_PATH_REWRITE")),
+        }),
     });
 
-    let app = Router::new()
+    if let Ok(hours) = std::env::var("RETENTION_HOURS") {
+        let hours: u64 = hours.parse().expect("Invalid RETENTION_HOURS");
+        let interval = Duration::from_secs(env_or("RETENTION_SWEEP_SECONDS", 60).max(1));
+        retention::spawn(state.clone(), hours, interval);
+    }
+
+    if let Ok(url) = std::env::var("ALERT_URL") {
+        let limit = if state.reject_over_quota { state.capture_quota } else { state.max_requests };
+        let threshold = env_or("ALERT_COUNT_THRESHOLD", limit * 9 / 10);
+        let interval = Duration::from_secs(env_or("ALERT_CHECK_SECONDS", 30).max(1));
+        alert::spawn(state.clone(), url, threshold, interval);
+    }
+
+    if state.fallback.is_some() {
+        fallback::spawn(state.clone());
+    }
+
+    let mut app = Router::new()
         .route("/", get(serve_index))
+        .route("/health", get(health))
         .route("/app.js", get(serve_js))
         .route("/ws", get(ws_handler))
-        .route("/api/requests", delete(clear_all))
-        .route("/api/requests/{id}", delete(delete_one))
-        .route("/hook", any(log_request))
-        .layer(DefaultBodyLimit::max(10 * 1024 * 1024))
-        .with_state(state);
+        .route("/api/requests", get(search).delete(clear_all))
+        .route("/api/requests/html", get(table::page))
+        .route("/api/requests/recent", get(recent))
+        .route("/api/requests/{id}", get(get_one).delete(delete_one))
+        .route("/api/requests/{id}/replay", post(replay::one))
+        .route("/api/requests/{id}/body", get(decode::body))
+        .route("/api/requests/{id}/curl", get(curl::single))
+        .route("/api/requests/{id}/hexdump", get(hexdump::single))
+        .route("/api/requests/{id}/json", get(jsonpath::select))
+        .route("/api/export.har", get(har::export))
+        .route("/api/export/curl-script", get(curl::script))
+        .route("/api/feed", get(feed::feed))
+        .route("/api/histogram", get(histogram))
+        .route("/api/senders", get(senders))
+        .route("/api/stats", get(stats))
+        .route("/api/schema", get(schema))
+        .route("/api/admin/verify", get(verify::verify))
+        .route("/api/days", get(rotation::list_days))
+        .route("/api/days/{day}", get(rotation::get_day))
+        .route("/api/replay/timed", post(replay::timed))
+        .route("/api/replay/range", post(replay::range))
+        .route("/api/replay/jobs/{id}", get(replay::job))
+        .route("/api/snapshots", post(snapshots::create).get(snapshots::list))
+        .route(
+            "/api/snapshots/{name}",
+            get(snapshots::get_one).delete(snapshots::delete_one),
+        )
+        .route("/snapshot/{name}", get(snapshots::serve_ui))
+        .merge(hook_routes(
+            env_or("MAX_CONCURRENT_REQUESTS", 0),
+            env_or("RESPONSE_COMPRESS_MIN_BYTES", 1024),
+        ));
+    if state.log_tx.is_some() {
+        app = app.route("/ws/logs", get(log_ws_handler));
+    }
+    if env_flag("API_ENVELOPE", false) {
+        app = app.layer(axum::middleware::from_fn(envelope::wrap));
+    }
+    if let Ok(token) = std::env::var("AUTH_TOKEN")
+        && !token.is_empty()
+    {
+        app = app.layer(axum::middleware::from_fn_with_state(Arc::<str>::from(token), auth::require));
+    }
+    let app = app
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(state.clone());
 
     let lan_ip = local_ip_address::local_ip()
         .map(|ip| ip.to_string())
         .unwrap_or_else(|_| "127.0.0.1".to_string());
 
-    let addr = format!("0.0.0.0:{port}");
-    println!("Neo HTTP Logger → http://{lan_ip}:{port}/");
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let until_shutdown = |mut rx: tokio::sync::watch::Receiver<bool>| async move {
+        let _ = rx.wait_for(|stopping| *stopping).await;
+    };
+
+    let unix_socket = std::env::var("UNIX_SOCKET").ok().filter(|p| !p.is_empty());
+    let mut servers = tokio::task::JoinSet::new();
+    if let Some(path) = &unix_socket {
+        remove_stale_socket(path);
+        let listener = tokio::net::UnixListener::bind(path)
+            .unwrap_or_else(|e| panic!("Failed to bind UNIX_SOCKET {path}: {e}"));
+        println!("Neo HTTP Logger → unix:{path}");
+        let serve = axum::serve(listener, app.clone().into_make_service_with_connect_info::<ConnectionInfo>())
+            .with_graceful_shutdown(until_shutdown(shutdown_rx.clone()));
+        servers.spawn(async move { serve.await.unwrap() });
+    }
+    if unix_socket.is_none() || !env_flag("UNIX_SOCKET_ONLY", false) {
+        let addr = format!("0.0.0.0:{port}");
+        println!("Neo HTTP Logger → http://{lan_ip}:{port}/");
+        let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+        let serve = axum::serve(listener, app.into_make_service_with_connect_info::<ConnectionInfo>())
+            .with_graceful_shutdown(until_shutdown(shutdown_rx));
+        servers.spawn(async move { serve.await.unwrap() });
+    }
+
+    shutdown_signal().await;
+    tracing::info!("shutting down");
+    // WebSocket sessions forward this, close and drop their receiver; the listeners stop
+    // accepting and wait for in-flight requests
+    let _ = state.tx.send(SHUTDOWN_MESSAGE.to_string());
+    let _ = shutdown_tx.send(true);
+    let drain = async {
+        servers.join_all().await;
+        while state.tx.receiver_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    };
+    let drain_timeout = Duration::from_secs(env_or("SHUTDOWN_TIMEOUT_SECONDS", 10));
+    if tokio::time::timeout(drain_timeout, drain).await.is_err() {
+        tracing::warn!("connections still open after {}s, exiting anyway", drain_timeout.as_secs());
+    }
+
+    // Fold the WAL back into the database so no -wal file is left behind
+    let checkpoint = state.db.get().map_err(rusqlite::Error::from);
+    if let Err(e) = checkpoint.and_then(|db| db.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")) {
+        tracing::warn!("WAL checkpoint failed: {e}");
+    }
+    if let Some(path) = &unix_socket {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Resolves on Ctrl-C or SIGTERM.
+async fn shutdown_signal() {
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+}
+
+/// A socket left behind by a previous run would make the bind fail, so it's removed;
+/// anything that isn't a socket is left alone.
+fn remove_stale_socket(path: &str) {
+    use std::os::unix::fs::FileTypeExt;
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => {
+            std::fs::remove_file(path).unwrap_or_else(|e| panic!("Failed to remove stale socket {path}: {e}"));
+        }
+        Ok(_) => panic!("UNIX_SOCKET {path} exists and is not a socket"),
+        Err(_) => {}
+    }
+}
+
+/// Created once per accepted connection and shared by every request on it.
+#[derive(Clone)]
+struct ConnectionInfo {
+    /// Peer IP, or `unix` for `UNIX_SOCKET` peers, which have no address
+    remote_addr: String,
+    requests: Arc<AtomicU64>,
+}
+
+impl Connected<IncomingStream<'_, tokio::net::TcpListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, tokio::net::TcpListener>) -> Self {
+        Self {
+            remote_addr: stream.remote_addr().ip().to_string(),
+            requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for ConnectionInfo {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self {
+            remote_addr: "unix".to_string(),
+            requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Comma-separated list; empty entries are ignored.
+fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+fn env_flag(name: &str, default: bool) -> bool {
+    match std::env::var(name) {
+        Ok(v) => is_truthy(&v),
+        Err(_) => default,
+    }
+}
+
+/// Flag values accepted in env vars and query strings.
+fn is_truthy(value: &str) -> bool {
+    matches!(value.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+/// Opens (creating if needed) a capture database and brings its schema up to date.
+fn open_db(path: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    // WAL lets pooled readers run alongside the writer; the setting persists in the file
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS requests (
+            id TEXT PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            method TEXT NOT NULL,
+            url TEXT NOT NULL,
+            headers TEXT NOT NULL,
+            body TEXT NOT NULL DEFAULT '',
+            body_size INTEGER NOT NULL DEFAULT 0
+        )",
+    )?;
+    migrate(conn)?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS requests_body_sha256 ON requests (body_sha256);
+         CREATE INDEX IF NOT EXISTS requests_body_text ON requests (body_text);
+         CREATE INDEX IF NOT EXISTS requests_idempotency_key ON requests (idempotency_key);
+         CREATE INDEX IF NOT EXISTS requests_label ON requests (label);
+         CREATE INDEX IF NOT EXISTS requests_endpoint ON requests (endpoint);
+         CREATE INDEX IF NOT EXISTS requests_remote_addr ON requests (remote_addr, timestamp);
+         CREATE INDEX IF NOT EXISTS requests_timestamp_id ON requests (timestamp, id);
+         CREATE INDEX IF NOT EXISTS requests_timestamp_received_id ON requests (timestamp, received_ns, id);",
+    )?;
+    conn.execute_batch(snapshots::SCHEMA)?;
+    conn.execute_batch(bodies::SCHEMA)
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let existing: Vec<String> = conn
+        .prepare("PRAGMA table_info(requests)")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(1))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })?;
+
+    for (name, decl) in COLUMN_MIGRATIONS {
+        if !existing.iter().any(|c| c == name) {
+            conn.execute_batch(&format!("ALTER TABLE requests ADD COLUMN {name} {decl}"))?;
+        }
+    }
+    if !existing.iter().any(|c| c == "received_ns") {
+        // Older rows get their millisecond timestamp so history pages order them correctly
+        conn.execute_batch(
+            "UPDATE requests SET received_ns =
+                 CAST(strftime('%s', timestamp) AS INTEGER) * 1000000000
+                 + CAST(substr(timestamp, 21, 3) AS INTEGER) * 1000000",
+        )?;
+    }
+    conn.pragma_update(None, "user_version", schema_version())
+}
+
+fn schema_version() -> i64 {
+    COLUMN_MIGRATIONS.len() as i64 + 1
+}
+
+async fn schema(State(state): State<Arc<AppState>>) -> Result<Response, pool::Unavailable> {
+    let columns: Vec<serde_json::Value> = state
+        .db
+        .run(|db| {
+            let mut stmt = db.prepare("PRAGMA table_info(requests)").unwrap();
+            stmt.query_map([], |row| {
+                Ok(json!({
+                    "name": row.get::<_, String>(1)?,
+                    "type": row.get::<_, String>(2)?,
+                    "not_null": row.get::<_, bool>(3)?,
+                    "default": row.get::<_, Option<String>>(4)?,
+                    "primary_key": row.get::<_, i64>(5)? > 0,
+                }))
+            })
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+        })
+        .await?;
+    Ok(Json(json!({
+        "schema_version": schema_version(),
+        "table": "requests",
+        "columns": columns,
+    }))
+    .into_response())
+}
+
+/// The capture routes, optionally behind a shared concurrency limit (0 = unlimited) that
+/// sheds excess requests with 503 so the UI and WebSocket routes stay responsive.
+fn hook_routes(max_concurrent: usize, compress_min_bytes: u16) -> Router<Arc<AppState>> {
+    // Small acks like `{"ok":1}` aren't worth the compression overhead
+    let compression = CompressionLayer::new()
+        .compress_when(SizeAbove::new(compress_min_bytes).and(NotForContentType::IMAGES));
+    let hook = Router::new()
+        .route("/hook", any(log_request))
+        .route("/hook/{*path}", any(log_request))
+        .layer(compression);
+    if max_concurrent == 0 {
+        return hook;
+    }
+    hook.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(overloaded))
+            .load_shed()
+            .layer(GlobalConcurrencyLimitLayer::new(max_concurrent)),
+    )
+}
 
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+async fn overloaded(_: BoxError) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, "1")],
+        Json(json!({"error": "too many concurrent requests"})),
+    )
+        .into_response()
 }
 
-async fn serve_index() -> Html<&'static str> {
-    Html(INDEX_HTML)
+/// Reads `name` from `STATIC_DIR` when set, so frontend edits show up without a rebuild.
+fn static_asset(state: &AppState, name: &str, embedded: &'static str) -> Cow<'static, str> {
+    let Some(dir) = &state.static_dir else {
+        return Cow::Borrowed(embedded);
+    };
+    match std::fs::read_to_string(std::path::Path::new(dir).join(name)) {
+        Ok(contents) => Cow::Owned(contents),
+        Err(e) => {
+            tracing::warn!("failed to read {name} from STATIC_DIR, serving embedded copy: {e}");
+            Cow::Borrowed(embedded)
+        }
+    }
 }
 
-async fn serve_js() -> Response {
+async fn serve_index(State(state): State<Arc<AppState>>) -> Html<Cow<'static, str>> {
+    Html(static_asset(&state, "index.html", INDEX_HTML))
+}
+
+async fn serve_js(State(state): State<Arc<AppState>>) -> Response {
     (
         StatusCode::OK,
         [(header::CONTENT_TYPE, "application/javascript")],
-        APP_JS,
+        static_asset(&state, "app.js", APP_JS),
     )
         .into_response()
 }
 
 async fn log_request(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(connection): ConnectInfo<ConnectionInfo>,
     method: Method,
     uri: Uri,
     headers: HeaderMap,
-    body: axum::body::Bytes,
-) -> impl IntoResponse {
+    body: axum::body::Body,
+) -> Response {
+    let started = Instant::now();
+    let content_type = media_type(&headers);
+    let uri = match admission::admit(&state, uri, &headers, &content_type).await {
+        Ok(uri) => uri,
+        Err(response) => return response,
+    };
+
     let id = uuid::Uuid::new_v4().to_string();
-    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-    let url = uri.to_string();
+    let bodies::Received {
+        body,
+        size: body_size,
+        sha256: body_sha256,
+        chunks,
+        content_encoding,
+        dropped: body_dropped,
+        staged,
+    } = match bodies::receive(&state, &id, &method, &uri, &headers, &content_type, body).await {
+        Ok(received) => received,
+        Err(response) => return response,
+    };
+    let invalid_json = admission::invalid_json(&state, &content_type, &body);
+    if invalid_json && !state.store_rejected_json {
+        return unsupported_json();
+    }
+    let (idempotency_key, duplicate_of) = match admission::idempotency(&state, &headers).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let connection_seq = connection.requests.fetch_add(1, Ordering::Relaxed) as i64 + 1;
+    let forwarded_for = state
+        .trust_forwarded_for
+        .then(|| headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()))
+        .flatten()
+        .and_then(|v| v.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+    let remote_addr = forwarded_for.unwrap_or_else(|| connection.remote_addr.clone());
+
+    // Inter-arrival gap on the monotonic clock, unaffected by wall-clock adjustments
+    let gap_ms = {
+        let now = Instant::now();
+        let mut last = state.last_capture.lock().unwrap();
+        let gap = last.map(|prev| now.duration_since(prev).as_millis() as i64);
+        *last = Some(now);
+        gap
+    };
+
+    let (timestamp, received_ns) = receipt_time(&state);
+    let received_ns = Some(received_ns);
+    let (url, overrides) = mock::split_overrides(&uri);
+    let (url, label) = match &state.label_param {
+        Some(param) => take_query_param(&url, param),
+        None => (url, None),
+    };
+    let endpoint = hook_endpoint(uri.path(), &state.hooks);
+    // Absolute-form request targets carry their own authority; otherwise use the Host header
+    let host = uri
+        .authority()
+        .map(|a| a.to_string())
+        .or_else(|| headers.get(header::HOST).and_then(|v| v.to_str().ok()).map(str::to_string));
 
     let headers_vec: Vec<(String, String)> = headers
         .iter()
-        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .map(|(k, v)| {
+            let value = v.to_str().unwrap_or("");
+            (k.to_string(), truncate_header_value(value, state.max_header_value_bytes))
+        })
         .collect();
     let headers_json = serde_json::to_string(&headers_vec).unwrap_or_else(|_| "[]".to_string());
+    let (raw_method, header_order) = if state.record_header_order {
+        let order: Vec<serde_json::Value> = headers
+            .iter()
+            .enumerate()
+            .map(|(index, (k, v))| {
+                json!({"index": index, "name": k.as_str(), "value": String::from_utf8_lossy(v.as_bytes())})
+            })
+            .collect();
+        (Some(method.as_str().to_string()), Some(json!(order).to_string()))
+    } else {
+        (None, None)
+    };
 
-    let body_size = body.len() as i64;
-    let body_b64 = BASE64.encode(&body);
+    // hyper sends the provisional `100 Continue` the first time the body is polled, which
+    // `bodies::receive` has done by now. Rejections before it answer without asking for the body
+    let expect_continue = headers
+        .get(header::EXPECT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"));
 
-    let req = LoggedRequest {
+    // Record contract violations without rejecting anything
+    let (spec_valid, spec_error) = match &state.spec {
+        Some(spec) => match spec.validate(method.as_str(), uri.path(), &content_type, &body) {
+            Ok(()) => (Some(true), None),
+            Err(e) => (Some(false), Some(e)),
+        },
+        None => (None, None),
+    };
+
+    // The hook is plain HTTP; upgrade attempts are recorded and then refused
+    let websocket_attempt = headers
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|p| p.trim().eq_ignore_ascii_case("websocket")));
+
+    let answer = match forward::refusal(invalid_json, websocket_attempt) {
+        Some(refused) => forward::Answer::buffer(refused, None).await,
+        None => forward::answer(&state, &method, &url, uri.path(), &headers, &body, overrides.status).await,
+    };
+    let response_status = Some(answer.parts.status.as_u16() as i64);
+
+    // Evaluated once the response is known so rules can match on its status
+    let capture = rules::Capture {
+        method: method.as_str(),
+        path: uri.path(),
+        headers: &headers,
+        body: &body,
+        status: answer.parts.status.as_u16(),
+    };
+    let tags = rules::tags_for(&state.tag_rules, &capture);
+    let pinned = rules::pins(&state.tag_rules, &capture);
+    let response_body = (!answer.body.is_empty()).then(|| {
+        let end = answer.body.len().min(MAX_STORED_RESPONSE_BYTES);
+        String::from_utf8_lossy(&answer.body[..end]).into_owned()
+    });
+
+    let what = format!("{method} {url}");
+    let inflated = decode::inflate(&state, content_encoding, &body, body_size, body_dropped, &what).await;
+    let shaped = bodies::shape(&state, &id, &content_type, body, body_dropped, staged.is_some()).await;
+
+    let duration_ms = Some(started.elapsed().as_millis() as i64);
+    let mut req = LoggedRequest {
         id,
         timestamp,
         method: method.to_string(),
         url,
         headers: headers_json,
-        body: body_b64,
+        // Spilled bodies travel by reference, as they do when read back from the database
+        body: if shaped.spilled { String::new() } else { BASE64.encode(&shaped.body) },
         body_size,
+        form: shaped.form,
+        expect_continue,
+        gap_ms,
+        body_dropped,
+        spec_valid,
+        spec_error,
+        tags: serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string()),
+        body_sha256: Some(body_sha256),
+        host,
+        pinned,
+        websocket_attempt,
+        chunks,
+        chain_result: None,
+        connection_seq: Some(connection_seq),
+        remote_addr: Some(remote_addr),
+        ndjson: shaped.ndjson,
+        ndjson_count: shaped.ndjson_count,
+        response_status,
+        body_text: shaped.text,
+        spilled: shaped.spilled,
+        body_file: shaped.file,
+        idempotency_key,
+        duplicate_of,
+        forward_error: answer.forward_error,
+        label,
+        body_redacted: shaped.redacted,
+        response_body,
+        duration_ms,
+        decoded_size: inflated.decoded_size,
+        compression_ratio: inflated.compression_ratio,
+        endpoint,
+        raw_method,
+        header_order,
+        repeat_count: 0,
+        received_ns,
+        decoded_body: inflated.decoded_body,
+        decoded_from: inflated.decoded_from,
+        replay_of: None,
+        preview: None,
     };
+    req.preview = preview::for_request(&req);
 
-    {
-        let db = state.db.lock().unwrap();
-        db.execute(
-            "INSERT INTO requests (id, timestamp, method, url, headers, body, body_size) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![req.id, req.timestamp, req.method, req.url, req.headers, req.body, req.body_size],
-        ).ok();
+    let streamed_head = staged.as_ref().map(|blob| blob.head().to_vec());
+    // A streamed body is already in `body_blobs`; the insert only adds the row
+    let stored_bytes = if staged.is_some() { axum::body::Bytes::new() } else { shaped.body.clone() };
+    let stored = match storage::store(&state, &req, staged, stored_bytes).await {
+        Ok(stored) => stored,
+        Err(response) => return response,
+    };
+    fanout::announce(&state, &req, &stored, &shaped.body, streamed_head.as_deref(), uri.path(), &headers);
 
-        // Enforce MAX_REQUESTS — delete oldest beyond limit
-        db.execute(
-            "DELETE FROM requests WHERE id IN (SELECT id FROM requests ORDER BY timestamp DESC LIMIT -1 OFFSET ?1)",
-            rusqlite::params![state.max_requests],
-        ).ok();
+    let mut response = Response::from_parts(answer.parts, axum::body::Body::from(answer.body));
+
+    if let Some(delay_ms) = overrides.delay_ms {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
     }
 
-    let msg = json!({"type": "new", "request": req}).to_string();
-    let _ = state.tx.send(msg);
+    if state.hook_response_headers {
+        let headers = response.headers_mut();
+        if !stored.sampled_out
+            && let Ok(id) = HeaderValue::from_str(&stored.repeat_of.as_ref().unwrap_or(&req).id)
+        {
+            headers.insert("x-capture-id", id);
+        }
+        if let Some(count) = stored.count {
+            headers.insert("x-capture-count", HeaderValue::from(count));
+        }
+    }
+    response
+}
+
+/// The capture's `timestamp` and `received_ns`. The timestamp is always derived from
+/// `received_ns`, so ordering by `(timestamp, received_ns)` never disagrees with either.
+fn receipt_time(state: &AppState) -> (String, i64) {
+    let now = chrono::Utc::now();
+    let received_ns = if state.nanosecond_timestamps {
+        let ns = now.timestamp_nanos_opt().unwrap_or_default();
+        // Clock steps and same-nanosecond arrivals are nudged forward to keep every value unique
+        let last = state
+            .last_received_ns
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| Some(ns.max(last + 1)))
+            .unwrap_or_default();
+        ns.max(last + 1)
+    } else {
+        now.timestamp_millis() * 1_000_000
+    };
+    let timestamp = chrono::DateTime::from_timestamp_nanos(received_ns)
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    (timestamp, received_ns)
+}
+
+/// With `HOOK_TOKEN` only `/hook/t/{token}/...` is accepted; returns the URI with the
+/// `/t/{token}` segment removed so the secret never reaches storage. The segment is
+/// percent-decoded first, so tokens with reserved characters can be sent encoded.
+fn strip_hook_token(uri: &Uri, token: &str) -> Option<Uri> {
+    let rest = uri.path().strip_prefix("/hook/t/")?;
+    let (given, rest) = rest.split_once('/').map_or((rest, ""), |(t, r)| (t, r));
+    let given: Vec<u8> = percent_encoding::percent_decode_str(given).collect();
+    if !constant_time_eq(&given, token.as_bytes()) {
+        return None;
+    }
+    let path = format!("/hook{}{rest}", if rest.is_empty() { "" } else { "/" });
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// The endpoint a hook path belongs to: its first segment after `/hook/`. With `HOOKS` set
+/// only those names count and anything else lands in the unlabeled bucket like bare `/hook`.
+fn hook_endpoint(path: &str, hooks: &[String]) -> Option<String> {
+    let rest = path.strip_prefix("/hook/")?;
+    let segment = rest.split('/').next().filter(|s| !s.is_empty())?;
+    (hooks.is_empty() || hooks.iter().any(|h| h == segment)).then(|| segment.to_string())
+}
+
+/// Removes every `name=` parameter from `url`, returning the URL without it and the first
+/// (decoded) value.
+fn take_query_param(url: &str, name: &str) -> (String, Option<String>) {
+    let Some((base, query)) = url.split_once('?') else {
+        return (url.to_string(), None);
+    };
+    let mut value = None;
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let (key, raw) = pair.split_once('=').unwrap_or((pair, ""));
+            if key != name {
+                return true;
+            }
+            if value.is_none() {
+                value = form_urlencoded::parse(format!("v={raw}").as_bytes())
+                    .next()
+                    .map(|(_, v)| v.into_owned())
+                    .filter(|v| !v.is_empty());
+            }
+            false
+        })
+        .collect();
+    let url = if kept.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}?{}", kept.join("&"))
+    };
+    (url, value)
+}
 
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn quota_reached(state: &AppState) -> Response {
     (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "application/json")],
-        r#"{"ok":1}"#,
+        StatusCode::INSUFFICIENT_STORAGE,
+        Json(json!({"error": QUOTA_REACHED, "quota": state.capture_quota})),
     )
+        .into_response()
 }
 
-async fn clear_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    {
-        let db = state.db.lock().unwrap();
-        db.execute("DELETE FROM requests", []).ok();
+/// Inserts the capture and applies `MAX_REQUESTS`, or with `QUOTA_POLICY=reject` inserts
+/// only while fewer than `CAPTURE_QUOTA` are stored; returns the stored count when
+/// `HOOK_RESPONSE_HEADERS` needs it.
+fn store_request(state: &AppState, req: &LoggedRequest, body: &[u8]) -> rusqlite::Result<Option<i64>> {
+    if let Some(rotation) = &state.rotation {
+        rotation.roll(&state.db);
     }
+    store_request_in(state, &*state.db.get()?, req, body)
+}
+
+/// `store_request` into the file `db` is open on, e.g. the one a streamed body was staged in.
+fn store_request_in(state: &AppState, db: &Connection, req: &LoggedRequest, body: &[u8]) -> rusqlite::Result<Option<i64>> {
+    // Streamed bodies are already in `body_blobs` and come with `body` empty
+    let blob = state.blob_bodies && req.body_file.is_none() && !body.is_empty();
+    let separate_blob = blob && state.body_store.spills(body.len());
+    let row_blob = blob && !separate_blob;
+    let inline_body = if req.body_file.is_some() || state.blob_bodies { "" } else { &req.body };
+    let quota = state.reject_over_quota.then_some(state.capture_quota);
+    // Takes the write lock up front: a deferred one that read first can't be upgraded once
+    // another writer commits (SQLITE_BUSY_SNAPSHOT), while this one waits out the busy timeout
+    let tx = Transaction::new_unchecked(db, TransactionBehavior::Immediate)?;
+    let inserted = tx.execute(
+        "INSERT INTO requests (id, timestamp, method, url, headers, body, body_size, form, expect_continue, gap_ms, body_dropped, spec_valid, spec_error, tags, body_sha256, host, websocket_attempt, chunks, connection_seq, remote_addr, ndjson, ndjson_count, response_status, body_text, body_file, idempotency_key, duplicate_of, forward_error, label, body_redacted, response_body, duration_ms, decoded_size, compression_ratio, endpoint, raw_method, header_order, repeat_count, pinned, received_ns, body_blob, decoded_body, decoded_from, replay_of) SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40, ?41, ?42, ?43, ?44
+         WHERE ?45 IS NULL OR (SELECT COUNT(*) FROM requests) < ?45",
+        rusqlite::params![req.id, req.timestamp, req.method, req.url, req.headers, inline_body, req.body_size, req.form, req.expect_continue, req.gap_ms, req.body_dropped, req.spec_valid, req.spec_error, req.tags, req.body_sha256, req.host, req.websocket_attempt, req.chunks, req.connection_seq, req.remote_addr, req.ndjson, req.ndjson_count, req.response_status, req.body_text, req.body_file, req.idempotency_key, req.duplicate_of, req.forward_error, req.label, req.body_redacted, req.response_body, req.duration_ms, req.decoded_size, req.compression_ratio, req.endpoint, req.raw_method, req.header_order, req.repeat_count, req.pinned, req.received_ns, row_blob.then_some(ZeroBlob(body.len() as i32)), req.decoded_body, req.decoded_from, req.replay_of, quota],
+    )?;
+    if inserted == 0 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FULL),
+            Some(QUOTA_REACHED.to_string()),
+        ));
+    }
+    if row_blob {
+        bodies::write_blob(&tx, "requests", "body_blob", tx.last_insert_rowid(), body)?;
+    }
+    if separate_blob {
+        bodies::insert_blob(&tx, &req.id, body)?;
+    }
+    tx.commit()?;
+
+    // Enforce MAX_REQUESTS — delete oldest unpinned beyond limit
+    if !state.reject_over_quota {
+        bodies::delete_requests(
+            db,
+            "WHERE id IN (SELECT id FROM requests WHERE pinned = 0
+                          ORDER BY timestamp DESC, received_ns DESC, id DESC LIMIT -1 OFFSET ?1)",
+            &[SqlValue::Integer(state.max_requests)],
+        ).ok();
+    }
+
+    if state.hook_response_headers {
+        return Ok(db.query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0)).ok());
+    }
+    Ok(None)
+}
+
+/// The limit of the longest matching `BODY_LIMITS` prefix, else the global one.
+fn body_limit(limits: &[(String, usize)], path: &str) -> usize {
+    limits
+        .iter()
+        .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, limit)| *limit)
+        .unwrap_or(MAX_BODY_BYTES)
+}
+
+/// Parses `/hook/uploads=104857600,/hook/events=4096` into `(prefix, bytes)` pairs.
+fn parse_body_limits(text: &str) -> Result<Vec<(String, usize)>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (prefix, bytes) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("{entry:?}: expected PATH_PREFIX=BYTES"))?;
+            let bytes = bytes
+                .trim()
+                .parse()
+                .map_err(|e| format!("{entry:?}: {e}"))?;
+            Ok((prefix.trim().to_string(), bytes))
+        })
+        .collect()
+}
+
+fn is_json_media(media: &str) -> bool {
+    media == "application/json" || media.ends_with("+json")
+}
+
+fn unsupported_json() -> Response {
     (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "application/json")],
-        r#"{"ok":1}"#,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        Json(json!({"error": "body is not valid JSON"})),
     )
+        .into_response()
+}
+
+/// Caps a header value at `max` bytes (0 = unlimited), marking how much was cut.
+fn truncate_header_value(value: &str, max: usize) -> String {
+    if max == 0 || value.len() <= max {
+        return value.to_string();
+    }
+    let mut end = max;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…[truncated {} bytes]", &value[..end], value.len() - end)
+}
+
+/// Lowercased media type of the request, without parameters like `charset`.
+fn media_type(headers: &HeaderMap) -> String {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Media types whose bodies are worth keeping as searchable text.
+fn is_text_media(media: &str) -> bool {
+    media.starts_with("text/")
+        || is_json_media(media)
+        || is_ndjson_media(media)
+        || media.ends_with("+xml")
+        || matches!(media, "application/xml" | "application/x-www-form-urlencoded")
+}
+
+fn is_ndjson_media(media: &str) -> bool {
+    matches!(
+        media,
+        "application/x-ndjson" | "application/ndjson" | "application/jsonl" | "application/x-jsonlines"
+    )
+}
+
+/// Decodes a form-urlencoded body into a JSON object; repeated keys become arrays.
+fn parse_form(body: &[u8]) -> String {
+    let mut fields = serde_json::Map::new();
+    for (key, value) in form_urlencoded::parse(body) {
+        let value = serde_json::Value::String(value.into_owned());
+        match fields.get_mut(key.as_ref()) {
+            Some(serde_json::Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = serde_json::Value::Array(vec![existing.take(), value]),
+            None => {
+                fields.insert(key.into_owned(), value);
+            }
+        }
+    }
+    serde_json::Value::Object(fields).to_string()
+}
+
+#[derive(Deserialize)]
+struct PurgeQuery {
+    body_sha256: Option<String>,
+}
+
+/// Clears everything, or only the requests whose body matches `body_sha256`.
+async fn clear_all(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PurgeQuery>,
+) -> Result<impl IntoResponse, pool::Unavailable> {
+    let deleted = state
+        .db
+        .run(move |db| {
+            match query.body_sha256 {
+                Some(hash) => bodies::delete_requests(
+                    db,
+                    "WHERE body_sha256 = ?1",
+                    &[SqlValue::Text(hash.to_ascii_lowercase())],
+                ),
+                None => bodies::delete_requests(db, "WHERE 1 = 1", &[]),
+            }
+            .unwrap_or(0)
+        })
+        .await?;
+    Ok(Json(json!({"ok": 1, "deleted": deleted})))
 }
 
 async fn delete_one(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-    {
-        let db = state.db.lock().unwrap();
-        db.execute("DELETE FROM requests WHERE id = ?1", rusqlite::params![id])
-            .ok();
-    }
-    (
+) -> Result<impl IntoResponse, pool::Unavailable> {
+    state
+        .db
+        .run(move |db| bodies::delete_requests(db, "WHERE id = ?1", &[SqlValue::Text(id)]).ok())
+        .await?;
+    Ok((
         StatusCode::OK,
         [(header::CONTENT_TYPE, "application/json")],
         r#"{"ok":1}"#,
-    )
+    ))
+}
+
+// Upper bound for `limit` on `GET /api/requests`
+const MAX_SEARCH_LIMIT: i64 = 1000;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    method: Option<String>,
+    url_contains: Option<String>,
+    body_contains: Option<String>,
+    label: Option<String>,
+    endpoint: Option<String>,
+    replay_of: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl SearchQuery {
+    /// Composes every given filter with AND. `since`/`until` are normalized to the stored
+    /// timestamp format so they compare correctly as text.
+    fn sql(&self) -> Result<(Vec<&'static str>, Vec<SqlValue>), String> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(method) = &self.method {
+            conditions.push("method = ?");
+            params.push(SqlValue::Text(method.to_ascii_uppercase()));
+        }
+        if let Some(needle) = &self.url_contains {
+            conditions.push(r"url LIKE ? ESCAPE '\'");
+            params.push(SqlValue::Text(format!("%{}%", escape_like(needle))));
+        }
+        // Only small text bodies have `body_text`; larger ones never match
+        if let Some(needle) = &self.body_contains {
+            conditions.push(r"body_text LIKE ? ESCAPE '\'");
+            params.push(SqlValue::Text(format!("%{}%", escape_like(needle))));
+        }
+        if let Some(label) = &self.label {
+            conditions.push("label = ?");
+            params.push(SqlValue::Text(label.clone()));
+        }
+        if let Some(endpoint) = &self.endpoint {
+            conditions.push("endpoint = ?");
+            params.push(SqlValue::Text(endpoint.clone()));
+        }
+        if let Some(source) = &self.replay_of {
+            conditions.push("replay_of = ?");
+            params.push(SqlValue::Text(source.clone()));
+        }
+        if let Some(since) = &self.since {
+            conditions.push("timestamp >= ?");
+            params.push(SqlValue::Text(normalize_timestamp(since)?));
+        }
+        if let Some(until) = &self.until {
+            conditions.push("timestamp <= ?");
+            params.push(SqlValue::Text(normalize_timestamp(until)?));
+        }
+        Ok((conditions, params))
+    }
+
+    fn where_clause(&self) -> Result<(String, Vec<SqlValue>), String> {
+        let (conditions, params) = self.sql()?;
+        if conditions.is_empty() {
+            return Ok((String::new(), params));
+        }
+        Ok((format!("WHERE {}", conditions.join(" AND ")), params))
+    }
+}
+
+/// Makes `%`, `_` and `\` match literally in a `LIKE ... ESCAPE '\'` pattern.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', r"\\").replace('%', r"\%").replace('_', r"\_")
+}
+
+fn normalize_timestamp(ts: &str) -> Result<String, String> {
+    chrono::DateTime::parse_from_rfc3339(ts)
+        .map(|t| {
+            t.with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        })
+        .map_err(|e| format!("{ts:?} is not an RFC 3339 timestamp: {e}"))
+}
+
+/// Stored requests matching the query, newest first, with the total number of matches.
+async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let (where_clause, mut params) = match query.where_clause() {
+        Ok(sql) => sql,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()),
+    };
+    let limit = query.limit.unwrap_or(PAGE_SIZE as i64).clamp(0, MAX_SEARCH_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (total, requests) = state
+        .db
+        .run(move |db| {
+            let total: i64 = db
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM requests {where_clause}"),
+                    rusqlite::params_from_iter(&params),
+                    |row| row.get(0),
+                )
+                .unwrap_or(0);
+            params.push(SqlValue::Integer(limit));
+            params.push(SqlValue::Integer(offset));
+            (total, select_requests(db, &where_clause, "LIMIT ? OFFSET ?", &params))
+        })
+        .await?;
+
+    Ok(Json(json!({
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+        "requests": requests,
+    }))
+    .into_response())
+}
+
+fn select_requests(db: &Connection, where_clause: &str, page: &str, params: &[SqlValue]) -> Vec<LoggedRequest> {
+    let mut stmt = db
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM requests {where_clause} ORDER BY timestamp DESC, received_ns DESC, id DESC {page}"
+        ))
+        .unwrap();
+    stmt.query_map(rusqlite::params_from_iter(params), map_row)
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+}
+
+async fn get_one(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, pool::Unavailable> {
+    let buffered = state.fallback.as_ref().and_then(|f| f.find(&id));
+    let found = state
+        .db
+        .run(move |db| {
+            let mut req = fetch_request(db, &id).or(buffered)?;
+            resolve_body(db, &mut req);
+            Some(req)
+        })
+        .await?;
+    Ok(match found {
+        Some(req) => Json(req).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response(),
+    })
+}
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    n: Option<i64>,
+}
+
+/// The `n`th newest request (`n=1` is the latest).
+async fn recent(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let n = query.n.unwrap_or(1);
+    if n < 1 {
+        return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": "n must be at least 1"}))).into_response());
+    }
+    let found = state
+        .db
+        .run(move |db| {
+            db.query_row(
+                &format!(
+                    "SELECT {SELECT_COLUMNS} FROM requests
+                     ORDER BY timestamp DESC, received_ns DESC, id DESC LIMIT 1 OFFSET ?1"
+                ),
+                rusqlite::params![n - 1],
+                map_row,
+            )
+            .ok()
+        })
+        .await?;
+    Ok(match found {
+        Some(req) => Json(req).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("fewer than {n} requests stored")})),
+        )
+            .into_response(),
+    })
+}
+
+/// Distinct client addresses with their request counts, busiest first.
+async fn senders(State(state): State<Arc<AppState>>) -> Result<Response, pool::Unavailable> {
+    let senders: Vec<serde_json::Value> = state
+        .db
+        .run(|db| {
+            let mut stmt = db
+                .prepare(
+                    "SELECT remote_addr, COUNT(*), MIN(timestamp), MAX(timestamp) FROM requests
+                     WHERE remote_addr IS NOT NULL
+                     GROUP BY remote_addr ORDER BY COUNT(*) DESC, remote_addr",
+                )
+                .unwrap();
+            stmt.query_map([], |row| {
+                Ok(json!({
+                    "remote_addr": row.get::<_, String>(0)?,
+                    "count": row.get::<_, i64>(1)?,
+                    "first_seen": row.get::<_, String>(2)?,
+                    "last_seen": row.get::<_, String>(3)?,
+                }))
+            })
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+        })
+        .await?;
+    Ok(Json(senders).into_response())
+}
+
+#[derive(Deserialize)]
+struct HistogramQuery {
+    field: Option<String>,
+    buckets: Option<usize>,
+}
+
+async fn histogram(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistogramQuery>,
+) -> Result<Response, pool::Unavailable> {
+    let field = query.field.unwrap_or_else(|| "body_size".to_string());
+    if !HISTOGRAM_FIELDS.contains(&field.as_str()) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("unknown field, expected one of: {}", HISTOGRAM_FIELDS.join(", "))})),
+        )
+            .into_response());
+    }
+    let buckets = query.buckets.unwrap_or(10).clamp(1, MAX_HISTOGRAM_BUCKETS);
+
+    // Read as floats so REAL columns like `compression_ratio` bucket the same way
+    let sql = format!("SELECT {field} FROM requests WHERE {field} IS NOT NULL");
+    let values: Vec<f64> = state
+        .db
+        .run(move |db| {
+            let mut stmt = db.prepare(&sql).unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .filter_map(|r| r.ok())
+                .collect()
+        })
+        .await?;
+
+    Ok(Json(bucketize(&field, &values, buckets)).into_response())
+}
+
+/// Splits `values` into `buckets` equal-width bins between their min and max.
+fn bucketize(field: &str, values: &[f64], buckets: usize) -> serde_json::Value {
+    let (Some(min), Some(max)) = (
+        values.iter().copied().reduce(f64::min),
+        values.iter().copied().reduce(f64::max),
+    ) else {
+        return json!({"field": field, "count": 0, "buckets": []});
+    };
+
+    // A single distinct value can't be split, so it gets one bucket
+    let buckets = if max == min { 1 } else { buckets };
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0i64; buckets];
+    for &v in values {
+        let idx = if width > 0.0 {
+            (((v - min) / width) as usize).min(buckets - 1)
+        } else {
+            0
+        };
+        counts[idx] += 1;
+    }
+
+    let bins: Vec<serde_json::Value> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let lower = min + width * i as f64;
+            let upper = if i == buckets - 1 { max } else { lower + width };
+            json!({"min": lower, "max": upper, "count": count})
+        })
+        .collect();
+
+    json!({
+        "field": field,
+        "count": values.len(),
+        "min": min,
+        "max": max,
+        "buckets": bins,
+    })
+}
+
+#[derive(Deserialize)]
+struct WsOptions {
+    /// Follow every `new` message with a `hexdump` frame of the body's first `HEXDUMP_BYTES`
+    hexdump: Option<String>,
 }
 
 async fn ws_handler(
     State(state): State<Arc<AppState>>,
+    Query(filter): Query<HistoryFilter>,
+    Query(options): Query<WsOptions>,
     ws: WebSocketUpgrade,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_ws(socket, state))
+    let hexdump = options.hexdump.as_deref().is_some_and(is_truthy);
+    // Oversized client frames are rejected while reading instead of being buffered
+    ws.max_message_size(state.ws_max_message_bytes)
+        .max_frame_size(state.ws_max_message_bytes)
+        .on_upgrade(move |socket| handle_ws(socket, state, filter, hexdump))
 }
 
-async fn handle_ws(mut socket: ws::WebSocket, state: Arc<AppState>) {
+async fn handle_ws(mut socket: ws::WebSocket, state: Arc<AppState>, filter: HistoryFilter, hexdump: bool) {
     // Subscribe to broadcast FIRST (race condition fix)
     let mut rx = state.tx.subscribe();
 
     // Load initial history
-    let (requests, total) = load_history(&state, None);
+    let (requests, total) = match history(&state, None, &filter).await {
+        Ok(page) => page,
+        Err(e) => {
+            let _ = socket.send(ws::Message::Text(unavailable(e).into())).await;
+            return;
+        }
+    };
     let history_msg = json!({
         "type": "history",
         "requests": requests,
         "total": total,
+        "protocol_version": PROTOCOL_VERSION,
+        "capabilities": CAPABILITIES,
     })
     .to_string();
 
     if socket.send(ws::Message::Text(history_msg.into())).await.is_err() {
         return;
     }
+    // Captures the database couldn't take yet follow the history as if they'd just arrived
+    if let Some(fallback) = &state.fallback {
+        let mut pending: Vec<String> = fallback
+            .captures()
+            .iter()
+            .filter(|req| filter.matches(req))
+            .map(|req| json!({"type": "new", "request": req}).to_string())
+            .collect();
+        if fallback.is_degraded() {
+            pending.push(fallback.notice());
+        }
+        for msg in pending {
+            if socket.send(ws::Message::Text(msg.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // Set by `subscribe_stats`; lives and dies with this connection
+    let mut stats_timer: Option<tokio::time::Interval> = None;
+    // With `?hexdump=1`, captures whose `new` message went out and whose frame hasn't yet
+    let mut awaiting_dump: HashSet<String> = HashSet::new();
 
     loop {
         tokio::select! {
+            _ = async { stats_timer.as_mut().unwrap().tick().await }, if stats_timer.is_some() => {
+                // A tick the database can't serve is skipped; the next one tries again
+                if let Ok(stats) = stats_for(&state, &filter).await
+                    && socket.send(ws::Message::Text(stats.to_string().into())).await.is_err()
+                {
+                    break;
+                }
+            }
             // Broadcast messages (new requests)
             result = rx.recv() => {
                 match result {
                     Ok(msg) => {
+                        // Frames follow their `new` message, which `filter` has already decided on
+                        if let Some(id) = hexdump::frame_id(&msg) {
+                            if awaiting_dump.remove(&id) && socket.send(ws::Message::Text(msg.into())).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                        if !broadcast_matches(&msg, &filter) {
+                            continue;
+                        }
+                        let shutting_down = msg == SHUTDOWN_MESSAGE;
+                        if hexdump && let Some(req) = new_request(&msg) {
+                            awaiting_dump.insert(req.id);
+                        }
                         if socket.send(ws::Message::Text(msg.into())).await.is_err() {
                             break;
                         }
+                        if shutting_down {
+                            let close = ws::CloseFrame {
+                                code: ws::close_code::AWAY,
+                                reason: "server shutting down".into(),
+                            };
+                            let _ = socket.send(ws::Message::Close(Some(close))).await;
+                            break;
+                        }
                     }
                     Err(broadcast::error::RecvError::Lagged(_)) => {
                         // Close connection, client will reconnect
@@ -232,23 +1698,30 @@ async fn handle_ws(mut socket: ws::WebSocket, state: Arc<AppState>) {
             result = socket.recv() => {
                 match result {
                     Some(Ok(ws::Message::Text(text))) => {
-                        if let Ok(cmd) = serde_json::from_str::<serde_json::Value>(&text) {
-                            if cmd.get("type").and_then(|t| t.as_str()) == Some("load_more") {
-                                if let Some(before) = cmd.get("before").and_then(|b| b.as_str()) {
-                                    let (requests, total) = load_history(&state, Some(before.to_string()));
-                                    let msg = json!({
-                                        "type": "history",
-                                        "requests": requests,
-                                        "total": total,
-                                    }).to_string();
-                                    if socket.send(ws::Message::Text(msg.into())).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }
+                        let mut stats_interval = None;
+                        if let Some(msg) = handle_command(&state, &filter, &text, &mut stats_interval).await
+                            && socket.send(ws::Message::Text(msg.into())).await.is_err()
+                        {
+                            break;
+                        }
+                        if let Some(every) = stats_interval {
+                            // The reply already carried fresh stats, so the first push is one interval out
+                            let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+                            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                            stats_timer = Some(timer);
                         }
                     }
                     Some(Ok(ws::Message::Close(_))) | None => break,
+                    Some(Err(e)) => {
+                        if message_too_long(e) {
+                            let close = ws::CloseFrame {
+                                code: ws::close_code::SIZE,
+                                reason: format!("message exceeds {} bytes", state.ws_max_message_bytes).into(),
+                            };
+                            let _ = socket.send(ws::Message::Close(Some(close))).await;
+                        }
+                        break;
+                    }
                     _ => {}
                 }
             }
@@ -256,40 +1729,391 @@ async fn handle_ws(mut socket: ws::WebSocket, state: Arc<AppState>) {
     }
 }
 
-fn load_history(state: &AppState, before: Option<String>) -> (Vec<LoggedRequest>, i64) {
-    let db = state.db.lock().unwrap();
+fn message_too_long(e: axum::Error) -> bool {
+    matches!(
+        e.into_inner().downcast_ref::<tungstenite::Error>(),
+        Some(tungstenite::Error::Capacity(_))
+    )
+}
 
-    let total: i64 = db
-        .query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0))
-        .unwrap_or(0);
+/// Runs a single client command, returning the reply to send back (if any).
+/// `stats_interval` is set when the command asks for periodic stats pushes.
+async fn handle_command(
+    state: &AppState,
+    filter: &HistoryFilter,
+    text: &str,
+    stats_interval: &mut Option<Duration>,
+) -> Option<String> {
+    let Ok(cmd) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Some(ws_error("invalid JSON"));
+    };
 
-    let requests = if let Some(before_ts) = before {
-        let mut stmt = db
-            .prepare(
-                "SELECT id, timestamp, method, url, headers, body, body_size FROM requests WHERE timestamp < ?1 ORDER BY timestamp DESC LIMIT ?2",
+    // Clients may state the protocol version they were written against; newer
+    // versions than ours may rely on semantics we don't have, so refuse them
+    if let Some(version) = cmd.get("protocol_version").and_then(|v| v.as_u64())
+        && version > PROTOCOL_VERSION as u64
+    {
+        return Some(ws_error(&format!(
+            "unsupported protocol_version {version}, server speaks {PROTOCOL_VERSION}"
+        )));
+    }
+
+    match cmd.get("type").and_then(|t| t.as_str()) {
+        Some("load_more") => {
+            let before = cmd.get("before").and_then(|b| b.as_str())?;
+            let cursor = HistoryCursor {
+                timestamp: before.to_string(),
+                received_ns: cmd.get("before_ns").and_then(|n| n.as_i64()),
+                id: cmd.get("before_id").and_then(|b| b.as_str()).map(str::to_string),
+            };
+            let (requests, total) = match history(state, Some(cursor), filter).await {
+                Ok(page) => page,
+                Err(e) => return Some(unavailable(e)),
+            };
+            Some(
+                json!({
+                    "type": "history",
+                    "requests": requests,
+                    "total": total,
+                })
+                .to_string(),
             )
-            .unwrap();
-        stmt.query_map(rusqlite::params![before_ts, PAGE_SIZE as i64], map_row)
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
+        }
+        Some("pin") => {
+            let id = cmd.get("id").and_then(|i| i.as_str())?;
+            let pinned = cmd.get("pinned").and_then(|p| p.as_bool()).unwrap_or(true);
+            match set_pinned(state, id, pinned).await {
+                // Every client, including this one, learns about it through the broadcast
+                Ok(Some(_)) => None,
+                Ok(None) => Some(ws_error(&format!("request not found: {id}"))),
+                Err(e) => Some(unavailable(e)),
+            }
+        }
+        Some("get_stats") => Some(stats_for(state, filter).await.map_or_else(unavailable, |s| s.to_string())),
+        Some("subscribe_stats") => {
+            let every = cmd
+                .get("interval_ms")
+                .and_then(|i| i.as_u64())
+                .unwrap_or(DEFAULT_STATS_INTERVAL_MS)
+                .max(MIN_STATS_INTERVAL_MS);
+            *stats_interval = Some(Duration::from_millis(every));
+            Some(stats_for(state, filter).await.map_or_else(unavailable, |s| s.to_string()))
+        }
+        Some(other) => Some(ws_error(&format!("unknown command: {other}"))),
+        None => Some(ws_error("missing command type")),
+    }
+}
+
+/// The request carried by a `{"type":"new"}` broadcast.
+fn new_request(msg: &str) -> Option<LoggedRequest> {
+    let value = serde_json::from_str::<serde_json::Value>(msg).ok()?;
+    if value.get("type")?.as_str()? != "new" {
+        return None;
+    }
+    serde_json::from_value(value.get("request")?.clone()).ok()
+}
+
+/// Whether a broadcast message should reach a connection with this filter.
+fn broadcast_matches(msg: &str, filter: &HistoryFilter) -> bool {
+    if filter.host.is_none() && filter.endpoint.is_none() {
+        return true;
+    }
+    match serde_json::from_str::<serde_json::Value>(msg)
+        .ok()
+        .and_then(|v| serde_json::from_value::<LoggedRequest>(v.get("request")?.clone()).ok())
+    {
+        Some(req) => filter.matches(&req),
+        // Not about a single request (e.g. notices), everyone gets it
+        None => true,
+    }
+}
+
+fn ws_error(message: &str) -> String {
+    json!({"type": "error", "error": message, "protocol_version": PROTOCOL_VERSION}).to_string()
+}
+
+/// The `error` reply for a command the database couldn't serve.
+fn unavailable(e: pool::Unavailable) -> String {
+    tracing::error!("{e}");
+    ws_error("database unavailable")
+}
+
+async fn log_ws_handler(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_log_ws(socket, state))
+}
+
+async fn handle_log_ws(mut socket: ws::WebSocket, state: Arc<AppState>) {
+    let Some(mut rx) = state.log_tx.as_ref().map(|tx| tx.subscribe()) else {
+        return;
+    };
+
+    loop {
+        tokio::select! {
+            result = rx.recv() => {
+                match result {
+                    Ok(line) => {
+                        if socket.send(ws::Message::Text(line.into())).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Dropping a few log lines is fine, keep streaming
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            result = socket.recv() => {
+                match result {
+                    Some(Ok(ws::Message::Close(_))) | None | Some(Err(_)) => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Optional narrowing of the history stream, shared by the initial page, `load_more` and live updates.
+#[derive(Clone, Default, Deserialize)]
+struct HistoryFilter {
+    host: Option<String>,
+    endpoint: Option<String>,
+}
+
+impl HistoryFilter {
+    fn sql(&self) -> (Vec<&'static str>, Vec<SqlValue>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(host) = &self.host {
+            conditions.push("host = ?");
+            params.push(SqlValue::Text(host.clone()));
+        }
+        if let Some(endpoint) = &self.endpoint {
+            conditions.push("endpoint = ?");
+            params.push(SqlValue::Text(endpoint.clone()));
+        }
+        (conditions, params)
+    }
+
+    fn matches(&self, req: &LoggedRequest) -> bool {
+        self.host.as_ref().is_none_or(|h| req.host.as_ref() == Some(h))
+            && self.endpoint.as_ref().is_none_or(|e| req.endpoint.as_ref() == Some(e))
+    }
+}
+
+/// Aggregate counts over the requests this connection's filter lets through.
+fn load_stats(db: &Connection, filter: &HistoryFilter) -> serde_json::Value {
+    let (conditions, params) = filter.sql();
+    let where_filter = if conditions.is_empty() {
+        String::new()
     } else {
-        let mut stmt = db
-            .prepare(
-                "SELECT id, timestamp, method, url, headers, body, body_size FROM requests ORDER BY timestamp DESC LIMIT ?1",
-            )
-            .unwrap();
-        stmt.query_map(rusqlite::params![PAGE_SIZE as i64], map_row)
-            .unwrap()
-            .filter_map(|r| r.ok())
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let (total, pinned, body_bytes, oldest, newest) = db
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(pinned), 0), COALESCE(SUM(body_size), 0), MIN(timestamp), MAX(timestamp)
+                 FROM requests {where_filter}"
+            ),
+            rusqlite::params_from_iter(&params),
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .unwrap_or_default();
+    let by_method: serde_json::Map<String, serde_json::Value> = db
+        .prepare(&format!(
+            "SELECT method, COUNT(*) FROM requests {where_filter} GROUP BY method ORDER BY COUNT(*) DESC"
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_map(rusqlite::params_from_iter(&params), |row| {
+                Ok((row.get::<_, String>(0)?, json!(row.get::<_, i64>(1)?)))
+            })?
             .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "type": "stats",
+        "total": total,
+        "pinned": pinned,
+        "body_bytes": body_bytes,
+        "by_method": by_method,
+        "oldest": oldest,
+        "newest": newest,
+    })
+}
+
+/// `GET /health`: always 200 while the server runs, so a restart doesn't throw away captures
+/// held in memory; `status` is `"degraded"` while `FALLBACK_BUFFER` is standing in for storage.
+async fn health(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    match &state.fallback {
+        Some(fallback) => Json(json!({
+            "status": if fallback.is_degraded() { "degraded" } else { "ok" },
+            "storage": fallback.status(),
+        })),
+        None => Json(json!({"status": "ok"})),
+    }
+}
+
+/// `GET /api/stats`: the `get_stats` numbers (taking the same filters) plus the effective
+/// `SAMPLE_ABOVE_RPS` sampling, whose `fraction` is 1 while everything is being stored.
+async fn stats(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<HistoryFilter>,
+) -> Result<Json<serde_json::Value>, pool::Unavailable> {
+    let mut stats = stats_for(&state, &filter).await?;
+    if let Some(stats) = stats.as_object_mut() {
+        stats.remove("type");
+        let sampling = match &state.sampler {
+            Some(sampler) => sampler.stats(),
+            None => json!({"threshold_rps": null, "fraction": 1.0}),
+        };
+        stats.insert("sampling".to_string(), sampling);
+    }
+    Ok(Json(stats))
+}
+
+/// The last row a client has of the history, which the next page starts after. Rows are
+/// ordered by `(timestamp, received_ns, id)`, so captures within one millisecond keep arrival
+/// order under `TIMESTAMP_PRECISION=ns`.
+struct HistoryCursor {
+    timestamp: String,
+    received_ns: Option<i64>,
+    id: Option<String>,
+}
+
+impl HistoryCursor {
+    fn after(req: &LoggedRequest) -> Self {
+        Self {
+            timestamp: req.timestamp.clone(),
+            received_ns: req.received_ns,
+            id: Some(req.id.clone()),
+        }
+    }
+
+    /// The condition selecting rows before (`<`) or after (`>`) the cursor. Clients that send
+    /// the row's id but not its `received_ns` have it looked up, which only works while that
+    /// row still exists; without the id the page starts strictly before the timestamp, which
+    /// skips rows that share the boundary millisecond.
+    fn condition(self, op: &str, params: &mut Vec<SqlValue>) -> String {
+        params.push(SqlValue::Text(self.timestamp));
+        match (self.received_ns, self.id) {
+            (Some(received_ns), Some(id)) => {
+                params.extend([SqlValue::Integer(received_ns), SqlValue::Text(id)]);
+                format!("(timestamp, received_ns, id) {op} (?, ?, ?)")
+            }
+            (None, Some(id)) => {
+                params.extend([SqlValue::Text(id.clone()), SqlValue::Text(id)]);
+                format!("(timestamp, received_ns, id) {op} (?, (SELECT received_ns FROM requests WHERE id = ?), ?)")
+            }
+            (_, None) => format!("timestamp {op} ?"),
+        }
+    }
+}
+
+fn load_history(
+    db: &Connection,
+    before: Option<HistoryCursor>,
+    filter: &HistoryFilter,
+) -> (Vec<LoggedRequest>, i64) {
+    let (conditions, mut params) = filter.sql();
+    let mut conditions: Vec<String> = conditions.into_iter().map(str::to_string).collect();
+    let where_filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let total: i64 = db
+        .query_row(
+            &format!("SELECT COUNT(*) FROM requests {where_filter}"),
+            rusqlite::params_from_iter(&params),
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    if let Some(before) = before {
+        conditions.push(before.condition("<", &mut params));
+    }
+    let where_page = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
     };
+    params.push(SqlValue::Integer(PAGE_SIZE as i64));
+
+    let mut stmt = db
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM requests {where_page} ORDER BY timestamp DESC, received_ns DESC, id DESC LIMIT ?"
+        ))
+        .unwrap();
+    let requests = stmt
+        .query_map(rusqlite::params_from_iter(&params), map_row)
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
 
     (requests, total)
 }
 
+/// Pinned requests are exempt from MAX_REQUESTS eviction. Broadcasts the updated request.
+async fn set_pinned(state: &AppState, id: &str, pinned: bool) -> Result<Option<LoggedRequest>, pool::Unavailable> {
+    let id = id.to_string();
+    let req = state
+        .db
+        .run(move |db| {
+            let updated = db
+                .execute(
+                    "UPDATE requests SET pinned = ?1 WHERE id = ?2",
+                    rusqlite::params![pinned, id],
+                )
+                .unwrap_or(0);
+            if updated == 0 {
+                return None;
+            }
+            fetch_request(db, &id)
+        })
+        .await?;
+    if let Some(req) = &req {
+        let _ = state.tx.send(json!({"type": "update", "request": req}).to_string());
+    }
+    Ok(req)
+}
+
+/// `load_history` on the blocking pool, for async callers.
+async fn history(
+    state: &AppState,
+    before: Option<HistoryCursor>,
+    filter: &HistoryFilter,
+) -> Result<(Vec<LoggedRequest>, i64), pool::Unavailable> {
+    let filter = filter.clone();
+    state.db.run(move |db| load_history(db, before, &filter)).await
+}
+
+/// `load_stats` on the blocking pool, for async callers.
+async fn stats_for(state: &AppState, filter: &HistoryFilter) -> Result<serde_json::Value, pool::Unavailable> {
+    let filter = filter.clone();
+    state.db.run(move |db| load_stats(db, &filter)).await
+}
+
+fn fetch_request(db: &Connection, id: &str) -> Option<LoggedRequest> {
+    db.query_row(
+        &format!("SELECT {SELECT_COLUMNS} FROM requests WHERE id = ?1"),
+        rusqlite::params![id],
+        map_row,
+    )
+    .ok()
+}
+
 fn map_row(row: &rusqlite::Row) -> rusqlite::Result<LoggedRequest> {
-    Ok(LoggedRequest {
+    let mut req = LoggedRequest {
         id: row.get(0)?,
         timestamp: row.get(1)?,
         method: row.get(2)?,
@@ -297,5 +2121,115 @@ fn map_row(row: &rusqlite::Row) -> rusqlite::Result<LoggedRequest> {
         headers: row.get(4)?,
         body: row.get(5)?,
         body_size: row.get(6)?,
-    })
+        form: row.get(7)?,
+        expect_continue: row.get(8)?,
+        gap_ms: row.get(9)?,
+        body_dropped: row.get(10)?,
+        spec_valid: row.get(11)?,
+        spec_error: row.get(12)?,
+        tags: row.get(13)?,
+        body_sha256: row.get(14)?,
+        host: row.get(15)?,
+        pinned: row.get(16)?,
+        websocket_attempt: row.get(17)?,
+        chunks: row.get(18)?,
+        chain_result: row.get(19)?,
+        connection_seq: row.get(20)?,
+        remote_addr: row.get(21)?,
+        ndjson: row.get(22)?,
+        ndjson_count: row.get(23)?,
+        response_status: row.get(24)?,
+        body_text: row.get(25)?,
+        body_file: row.get(26)?,
+        idempotency_key: row.get(27)?,
+        duplicate_of: row.get(28)?,
+        forward_error: row.get(29)?,
+        label: row.get(30)?,
+        body_redacted: row.get(31)?,
+        response_body: row.get(32)?,
+        duration_ms: row.get(33)?,
+        decoded_size: row.get(34)?,
+        compression_ratio: row.get(35)?,
+        endpoint: row.get(36)?,
+        raw_method: row.get(37)?,
+        header_order: row.get(38)?,
+        repeat_count: row.get(39)?,
+        received_ns: row.get(40)?,
+        decoded_body: row.get(42)?,
+        decoded_from: row.get(43)?,
+        replay_of: row.get(44)?,
+        spilled: false,
+        preview: None,
+    };
+    // BLOB bodies are in the row anyway; spilled ones stay on disk until `resolve_body`
+    if let Some(blob) = row.get::<_, Option<Vec<u8>>>(41)? {
+        req.body = BASE64.encode(blob);
+    }
+    req.spilled = req.body_file.is_some() || row.get(45)?;
+    req.preview = preview::for_request(&req);
+    Ok(req)
+}
+
+/// The capture's body bytes, read from its file or `body_blobs` row when it was spilled.
+fn stored_body(db: &Connection, req: &LoggedRequest) -> Vec<u8> {
+    if !req.spilled || !req.body.is_empty() {
+        return BASE64.decode(&req.body).unwrap_or_default();
+    }
+    match &req.body_file {
+        Some(path) => std::fs::read(path)
+            .inspect_err(|e| tracing::warn!("failed to read body file {path}: {e}"))
+            .unwrap_or_default(),
+        None => bodies::read_blob(db, &req.id)
+            .inspect_err(|e| tracing::warn!("failed to read body blob of {}: {e}", req.id))
+            .unwrap_or_default(),
+    }
+}
+
+/// Fills in a spilled body for the single-capture endpoints that return it whole.
+fn resolve_body(db: &Connection, req: &mut LoggedRequest) {
+    if req.spilled && req.body.is_empty() {
+        req.body = BASE64.encode(stored_body(db, req));
+        req.preview = preview::for_request(req);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(uri: &str, token: &str) -> Option<String> {
+        strip_hook_token(&uri.parse().unwrap(), token).map(|uri| uri.to_string())
+    }
+
+    #[test]
+    fn hook_token_is_cut_out_of_the_path_and_the_query_kept() {
+        assert_eq!(strip("/hook/t/secret/orders?a=1&b=two", "secret").as_deref(), Some("/hook/orders?a=1&b=two"));
+        assert_eq!(strip("/hook/t/secret/a/b", "secret").as_deref(), Some("/hook/a/b"));
+        assert_eq!(
+            strip("http://example.com/hook/t/secret/orders?a=1", "secret").as_deref(),
+            Some("http://example.com/hook/orders?a=1")
+        );
+    }
+
+    #[test]
+    fn hook_token_followed_only_by_a_query() {
+        assert_eq!(strip("/hook/t/secret?a=1", "secret").as_deref(), Some("/hook?a=1"));
+        assert_eq!(strip("/hook/t/secret", "secret").as_deref(), Some("/hook"));
+    }
+
+    #[test]
+    fn hook_token_is_compared_percent_decoded() {
+        assert_eq!(strip("/hook/t/s%65cret/x", "secret").as_deref(), Some("/hook/x"));
+        assert_eq!(strip("/hook/t/a%2Fb%20c/x?q=1", "a/b c").as_deref(), Some("/hook/x?q=1"));
+        assert_eq!(strip("/hook/t/a%2fb/x", "a/b").as_deref(), Some("/hook/x"));
+    }
+
+    #[test]
+    fn wrong_or_misplaced_hook_token_is_refused() {
+        assert_eq!(strip("/hook/t/wrong/x", "secret"), None);
+        assert_eq!(strip("/hook/t/secretx/x", "secret"), None);
+        assert_eq!(strip("/hook/t//x", "secret"), None);
+        assert_eq!(strip("/hook/x?t=secret", "secret"), None);
+        assert_eq!(strip("/hook/secret/x", "secret"), None);
+    }
 }
@@ -0,0 +1,170 @@
+use axum::{
+    http::{HeaderName, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A fixed response returned to the sender of a hook request.
+#[derive(Clone, Deserialize)]
+pub struct CannedResponse {
+    #[serde(default = "default_status")]
+    pub status: u16,
+    #[serde(default = "default_body")]
+    pub body: String,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    /// Extra response headers, e.g. `{"Retry-After": "30"}`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl Default for CannedResponse {
+    fn default() -> Self {
+        Self {
+            status: default_status(),
+            body: default_body(),
+            content_type: default_content_type(),
+            headers: HashMap::new(),
+        }
+    }
+}
+
+impl CannedResponse {
+    /// The response used when no rule or method response applies: `HOOK_STATUS`,
+    /// `HOOK_BODY`, `HOOK_CONTENT_TYPE` and `HOOK_HEADERS` (a JSON object), each optional.
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = Self::default();
+        let status = match std::env::var("HOOK_STATUS") {
+            Ok(v) => v
+                .parse::<u16>()
+                .ok()
+                .filter(|s| StatusCode::from_u16(*s).is_ok())
+                .ok_or_else(|| format!("HOOK_STATUS: {v:?} is not an HTTP status"))?,
+            Err(_) => defaults.status,
+        };
+        let headers = match std::env::var("HOOK_HEADERS") {
+            Ok(v) => serde_json::from_str(&v).map_err(|e| format!("HOOK_HEADERS: {e}"))?,
+            Err(_) => defaults.headers,
+        };
+        Ok(Self {
+            status,
+            body: std::env::var("HOOK_BODY").unwrap_or(defaults.body),
+            content_type: std::env::var("HOOK_CONTENT_TYPE").unwrap_or(defaults.content_type),
+            headers,
+        })
+    }
+
+    pub fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        let mut response = (status, [(header::CONTENT_TYPE, self.content_type)], self.body).into_response();
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+/// Per-request response overrides passed as `__`-prefixed query parameters on the hook URL.
+#[derive(Default)]
+pub struct Overrides {
+    pub status: Option<u16>,
+    pub delay_ms: Option<u64>,
+}
+
+// Longest `__delay` honoured, so a typo can't park a connection indefinitely
+const MAX_DELAY_MS: u64 = 60_000;
+
+/// Splits `__status` / `__delay` (and any other `__` parameter) out of `uri`, returning
+/// the URL to store without them.
+pub fn split_overrides(uri: &axum::http::Uri) -> (String, Overrides) {
+    let Some(query) = uri.query().filter(|q| q.contains("__")) else {
+        return (uri.to_string(), Overrides::default());
+    };
+
+    let mut overrides = Overrides::default();
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "__status" => overrides.status = value.parse().ok().filter(|s| StatusCode::from_u16(*s).is_ok()),
+                "__delay" => overrides.delay_ms = value.parse::<u64>().ok().map(|d| d.min(MAX_DELAY_MS)),
+                _ if key.starts_with("__") => {}
+                _ => return true,
+            }
+            false
+        })
+        .collect();
+
+    let url = uri.to_string();
+    let base = url.split_once('?').map_or(url.as_str(), |(base, _)| base);
+    let url = if kept.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}?{}", kept.join("&"))
+    };
+    (url, overrides)
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn default_body() -> String {
+    r#"{"ok":1}"#.to_string()
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
+
+#[derive(Deserialize)]
+struct RuleConfig {
+    path: String,
+    #[serde(flatten)]
+    response: CannedResponse,
+}
+
+/// A path regex mapped to a response whose body may reference capture groups (`$1`, `${name}`).
+pub struct ResponseRule {
+    path: Regex,
+    response: CannedResponse,
+}
+
+/// Loads response rules from a JSON file containing an array of
+/// `{"path": "<regex>", "status": 200, "body": "...", "content_type": "..."}` objects.
+pub fn load_rules(path: &str) -> Result<Vec<ResponseRule>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let configs: Vec<RuleConfig> = serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))?;
+    configs
+        .into_iter()
+        .map(|c| {
+            let regex = Regex::new(&c.path).map_err(|e| format!("{path}: {e}"))?;
+            Ok(ResponseRule { path: regex, response: c.response })
+        })
+        .collect()
+}
+
+/// Returns the response of the first rule whose regex matches `path`, with capture groups expanded.
+pub fn match_rules(rules: &[ResponseRule], path: &str) -> Option<CannedResponse> {
+    rules.iter().find_map(|rule| {
+        let caps = rule.path.captures(path)?;
+        let mut body = String::new();
+        caps.expand(&rule.response.body, &mut body);
+        Some(CannedResponse { body, ..rule.response.clone() })
+    })
+}
+
+/// Parses a JSON object mapping HTTP methods to responses, e.g.
+/// `{"POST": {"status": 201}, "DELETE": {"status": 204, "body": ""}}`.
+pub fn parse_method_responses(text: &str) -> Result<HashMap<String, CannedResponse>, String> {
+    let map: HashMap<String, CannedResponse> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(map
+        .into_iter()
+        .map(|(method, response)| (method.to_ascii_uppercase(), response))
+        .collect())
+}
@@ -0,0 +1,202 @@
+use regex::Regex;
+use serde_json::Value;
+
+/// An OpenAPI 3 document used to check captured requests against their documented contract.
+///
+/// Only the parts needed for passive validation are interpreted: path templates, operations,
+/// `requestBody` content types and a practical subset of JSON Schema for JSON bodies.
+pub struct Spec {
+    doc: Value,
+    paths: Vec<(Regex, String)>,
+    base_path: String,
+}
+
+impl Spec {
+    /// Loads a JSON or YAML (by `.yaml`/`.yml` extension) spec. `base_path` is stripped from
+    /// request paths before matching, since senders hit `/hook/...` rather than the API's own root.
+    pub fn load(path: &str, base_path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        let doc: Value = if path.ends_with(".yaml") || path.ends_with(".yml") {
+            serde_yaml::from_str(&text).map_err(|e| format!("{path}: {e}"))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))?
+        };
+
+        let paths = doc
+            .get("paths")
+            .and_then(|p| p.as_object())
+            .ok_or_else(|| format!("{path}: spec has no `paths` object"))?
+            .keys()
+            .map(|template| {
+                let pattern = regex::escape(template)
+                    .replace(r"\{", "{")
+                    .replace(r"\}", "}");
+                let pattern = Regex::new(r"\{[^}]+\}").unwrap().replace_all(&pattern, "[^/]+");
+                let regex = Regex::new(&format!("^{pattern}$")).map_err(|e| format!("{template}: {e}"))?;
+                Ok((regex, template.clone()))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            doc,
+            paths,
+            base_path: base_path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Returns `Err` with the first mismatch between the request and the spec.
+    pub fn validate(&self, method: &str, path: &str, content_type: &str, body: &[u8]) -> Result<(), String> {
+        let path = path.strip_prefix(&self.base_path).unwrap_or(path);
+        let path = if path.is_empty() { "/" } else { path };
+
+        // Prefer templates with fewer parameters so `/users/me` beats `/users/{id}`
+        let template = self
+            .paths
+            .iter()
+            .filter(|(regex, _)| regex.is_match(path))
+            .min_by_key(|(_, template)| template.matches('{').count())
+            .map(|(_, template)| template)
+            .ok_or_else(|| format!("path {path} is not in the spec"))?;
+
+        let operation = self.doc["paths"][template]
+            .get(method.to_ascii_lowercase())
+            .ok_or_else(|| format!("method {method} is not defined for {template}"))?;
+
+        let Some(request_body) = operation.get("requestBody").map(|b| self.resolve(b)) else {
+            return Ok(());
+        };
+
+        if body.is_empty() {
+            return if request_body.get("required").and_then(|r| r.as_bool()) == Some(true) {
+                Err("request body is required".to_string())
+            } else {
+                Ok(())
+            };
+        }
+
+        let Some(content) = request_body.get("content").and_then(|c| c.as_object()) else {
+            return Ok(());
+        };
+        let media = content
+            .iter()
+            .find(|(key, _)| media_matches(key, content_type))
+            .map(|(_, media)| media)
+            .ok_or_else(|| format!("content type {content_type:?} is not accepted by {method} {template}"))?;
+
+        let Some(schema) = media.get("schema") else {
+            return Ok(());
+        };
+        if !crate::is_json_media(content_type) {
+            return Ok(());
+        }
+
+        let value: Value =
+            serde_json::from_slice(body).map_err(|e| format!("body is not valid JSON: {e}"))?;
+        self.check(schema, &value, "$")
+    }
+
+    fn resolve<'a>(&'a self, value: &'a Value) -> &'a Value {
+        match value.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.doc.pointer(pointer))
+                .unwrap_or(value),
+            None => value,
+        }
+    }
+
+    fn check(&self, schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+        let schema = self.resolve(schema);
+
+        if value.is_null() && schema.get("nullable").and_then(|n| n.as_bool()) == Some(true) {
+            return Ok(());
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(|a| a.as_array()) {
+            for sub in all {
+                self.check(sub, value, at)?;
+            }
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(options) = schema.get(key).and_then(|a| a.as_array())
+                && !options.iter().any(|sub| self.check(sub, value, at).is_ok())
+            {
+                return Err(format!("{at}: does not match any {key} alternative"));
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array())
+            && !allowed.contains(value)
+        {
+            return Err(format!("{at}: {value} is not one of the allowed values"));
+        }
+
+        if let Some(expected) = schema.get("type").and_then(|t| t.as_str()) {
+            let ok = match expected {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "number" => value.is_number(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                _ => true,
+            };
+            if !ok {
+                return Err(format!("{at}: expected {expected}, got {}", type_name(value)));
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for name in required.iter().filter_map(|n| n.as_str()) {
+                    if !object.contains_key(name) {
+                        return Err(format!("{at}: missing required property {name:?}"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (name, field) in object {
+                match properties.and_then(|p| p.get(name)) {
+                    Some(sub) => self.check(sub, field, &format!("{at}.{name}"))?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{at}: unexpected property {name:?}"));
+                        }
+                        Some(sub @ Value::Object(_)) => self.check(sub, field, &format!("{at}.{name}"))?,
+                        _ => {}
+                    },
+                }
+            }
+        }
+
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                self.check(items, item, &format!("{at}[{i}]"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Matches a spec media range like `application/*` or `*/*` against a request media type.
+fn media_matches(range: &str, media: &str) -> bool {
+    let range = range.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    match range.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((kind, "*")) => media.split('/').next() == Some(kind),
+        _ => range == media,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+// Lines waiting for a slow or absent reader before new ones are dropped
+const QUEUE_SIZE: usize = 1000;
+
+/// Writes each capture as an NDJSON line to a file or named pipe, e.g. for `tail -f`.
+///
+/// Opening a FIFO waits for a reader, and a reader going away only closes the current
+/// handle; the next line reopens the path. Lines that don't fit the queue are dropped.
+pub struct CapturePipe {
+    tx: mpsc::Sender<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl CapturePipe {
+    pub fn spawn(path: String) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(run(path, rx, dropped.clone()));
+        Self { tx, dropped }
+    }
+
+    pub fn send(&self, line: String) {
+        if self.tx.try_send(line).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::warn!("capture pipe queue full, dropped capture ({dropped} dropped so far)");
+        }
+    }
+}
+
+async fn run(path: String, mut rx: mpsc::Receiver<String>, dropped: Arc<AtomicU64>) {
+    let mut file: Option<tokio::fs::File> = None;
+
+    while let Some(mut line) = rx.recv().await {
+        line.push('\n');
+
+        if file.is_none() {
+            match tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+            {
+                Ok(opened) => file = Some(opened),
+                Err(e) => {
+                    let dropped = dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    tracing::warn!("capture pipe {path}: {e} ({dropped} dropped so far)");
+                    continue;
+                }
+            }
+        }
+
+        if let Some(f) = file.as_mut()
+            && let Err(e) = write_line(f, &line).await
+        {
+            // Typically EPIPE after the reader exited; reopen for the next line
+            let dropped = dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::debug!("capture pipe {path}: {e} ({dropped} dropped so far)");
+            file = None;
+        }
+    }
+}
+
+async fn write_line(file: &mut tokio::fs::File, line: &str) -> std::io::Result<()> {
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await
+}
@@ -0,0 +1,145 @@
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::Connection;
+use serde_json::json;
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::open_db;
+
+// How long a writer waits for another connection's write to finish before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SQLite in WAL mode behind a small connection pool (`DB_POOL_SIZE`), so reads such as
+/// history loads don't wait on inserts and concurrent hooks don't queue on one lock.
+/// The pool sits behind a lock only so `DB_ROTATION` can swap in the next day's file.
+pub struct Db {
+    pool: RwLock<Pool<SqliteConnectionManager>>,
+    size: u32,
+}
+
+impl Db {
+    pub fn open(path: &str, size: u32) -> Result<Self, String> {
+        Ok(Self {
+            pool: RwLock::new(build(path, size)?),
+            size,
+        })
+    }
+
+    /// A connection from the pool, waiting for one to free up if all are busy.
+    pub fn get(&self) -> Result<PooledConnection<SqliteConnectionManager>, Unavailable> {
+        checkout(&self.pool())
+    }
+
+    /// The pool for the current file. Work that has to stay on one file while `DB_ROTATION`
+    /// moves on holds on to it instead of going through `Db` again.
+    pub fn pool(&self) -> Pool<SqliteConnectionManager> {
+        self.pool.read().unwrap().clone()
+    }
+
+    /// Runs `f` on a pooled connection on the blocking thread pool, so SQLite work never
+    /// holds up an async worker.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, Unavailable>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> T + Send + 'static,
+    {
+        run_on(self.pool(), f).await
+    }
+
+    /// Points new checkouts at `path`; connections already handed out finish on the old file.
+    pub fn reopen(&self, path: &str) -> Result<(), String> {
+        let pool = build(path, self.size)?;
+        *self.pool.write().unwrap() = pool;
+        Ok(())
+    }
+}
+
+/// A connection from `pool`, waiting for one to free up if all are busy.
+pub fn checkout(pool: &Pool<SqliteConnectionManager>) -> Result<PooledConnection<SqliteConnectionManager>, Unavailable> {
+    pool.get().map_err(|e| Unavailable(e.to_string()))
+}
+
+/// `Db::run` on a pool taken from `Db::pool`.
+pub async fn run_on<T, F>(pool: Pool<SqliteConnectionManager>, f: F) -> Result<T, Unavailable>
+where
+    T: Send + 'static,
+    F: FnOnce(&Connection) -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || Ok(f(&*checkout(&pool)?))).await?
+}
+
+/// A connection of its own to `path`, outside any pool, waiting on locks as pooled ones do.
+pub fn connect(path: &Path) -> rusqlite::Result<Connection> {
+    let db = open_db(&path.to_string_lossy())?;
+    db.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(db)
+}
+
+/// No connection could be checked out in time, or the database file couldn't be opened.
+/// Handlers answer it with `503` so the sender can retry.
+#[derive(Debug)]
+pub struct Unavailable(String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "database unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+// The blocking task running the work panicked or was cancelled at shutdown
+impl From<tokio::task::JoinError> for Unavailable {
+    fn from(e: tokio::task::JoinError) -> Self {
+        Unavailable(e.to_string())
+    }
+}
+
+impl IntoResponse for Unavailable {
+    fn into_response(self) -> Response {
+        tracing::error!("{self}");
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "1")],
+            Json(json!({"error": "database unavailable"})),
+        )
+            .into_response()
+    }
+}
+
+// Lets code already returning `rusqlite::Result` use `?` on a checkout; SQLite calls a
+// lock it can't get in time "busy" too
+impl From<Unavailable> for rusqlite::Error {
+    fn from(e: Unavailable) -> Self {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY), Some(e.to_string()))
+    }
+}
+
+fn build(path: &str, size: u32) -> Result<Pool<SqliteConnectionManager>, String> {
+    // Every `:memory:` connection is its own database, so it gets exactly one with the schema
+    let in_memory = path == ":memory:";
+    if !in_memory {
+        open_db(path).map_err(|e| e.to_string())?;
+    }
+    let manager = SqliteConnectionManager::file(path).with_init(move |conn| {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        if in_memory {
+            crate::init_schema(conn)?;
+        }
+        Ok(())
+    });
+    Pool::builder()
+        .max_size(if in_memory { 1 } else { size.max(1) })
+        // SQLite connections don't go stale; recycling one would lose a `:memory:` database
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .build(manager)
+        .map_err(|e| e.to_string())
+}
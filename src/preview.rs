@@ -0,0 +1,100 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Map, Value};
+
+use crate::LoggedRequest;
+
+// Roughly one line in the request list
+const PREVIEW_CHARS: usize = 120;
+const STRING_CHARS: usize = 40;
+const HEX_BYTES: usize = 16;
+// Bigger JSON bodies are previewed as text instead of being parsed for every list row
+const MAX_JSON_PARSE_BYTES: usize = 1024 * 1024;
+
+/// A short, readable summary of the body for list views, chosen by content type:
+/// JSON keeps whole top-level entries and stays valid JSON, text is a character prefix,
+/// anything else is a hex snippet.
+pub fn for_request(req: &LoggedRequest) -> Option<String> {
+    if req.body_dropped || req.body.is_empty() {
+        return None;
+    }
+    let body = BASE64.decode(&req.body).ok()?;
+    let headers: Vec<(String, String)> = serde_json::from_str(&req.headers).unwrap_or_default();
+    let content_type = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if (crate::is_json_media(&content_type) || content_type.is_empty())
+        && body.len() <= MAX_JSON_PARSE_BYTES
+        && let Ok(value) = serde_json::from_slice::<Value>(&body)
+    {
+        return Some(json_preview(&value));
+    }
+    match std::str::from_utf8(&body) {
+        Ok(text) => Some(text_preview(text)),
+        Err(_) => Some(hex_preview(&body)),
+    }
+}
+
+fn json_preview(value: &Value) -> String {
+    match value {
+        Value::Object(object) => {
+            let mut preview = Map::new();
+            for (key, field) in object {
+                preview.insert(key.clone(), summarize(field));
+                if Value::Object(preview.clone()).to_string().len() > PREVIEW_CHARS {
+                    preview.remove(key);
+                    preview.insert("…".to_string(), Value::from(object.len() - preview.len()));
+                    break;
+                }
+            }
+            Value::Object(preview).to_string()
+        }
+        Value::Array(items) => {
+            let mut preview = Vec::new();
+            for item in items {
+                preview.push(summarize(item));
+                if Value::Array(preview.clone()).to_string().len() > PREVIEW_CHARS {
+                    preview.pop();
+                    preview.push(Value::from(format!("… {} more", items.len() - preview.len())));
+                    break;
+                }
+            }
+            Value::Array(preview).to_string()
+        }
+        scalar => summarize(scalar).to_string(),
+    }
+}
+
+/// Nested containers collapse to a marker so a preview only ever shows one level.
+fn summarize(value: &Value) -> Value {
+    match value {
+        Value::Object(object) if object.is_empty() => Value::Object(Map::new()),
+        Value::Object(object) => Value::from(format!("{{…{} keys}}", object.len())),
+        Value::Array(items) if items.is_empty() => Value::Array(Vec::new()),
+        Value::Array(items) => Value::from(format!("[…{} items]", items.len())),
+        Value::String(text) if text.chars().count() > STRING_CHARS => {
+            Value::from(format!("{}…", text.chars().take(STRING_CHARS).collect::<String>()))
+        }
+        other => other.clone(),
+    }
+}
+
+fn text_preview(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= PREVIEW_CHARS {
+        return collapsed;
+    }
+    format!("{}…", collapsed.chars().take(PREVIEW_CHARS).collect::<String>())
+}
+
+fn hex_preview(body: &[u8]) -> String {
+    let hex: Vec<String> = body.iter().take(HEX_BYTES).map(|b| format!("{b:02x}")).collect();
+    let mut preview = hex.join(" ");
+    if body.len() > HEX_BYTES {
+        preview.push_str(" …");
+    }
+    preview
+}
@@ -0,0 +1,606 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use regex::Regex;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{AppState, LoggedRequest, fetch_request, is_truthy, normalize_timestamp, pool, select_requests};
+
+// Response bodies are only shown as a preview in results
+const MAX_RESPONSE_PREVIEW: usize = 4096;
+
+// Slower replays would stretch the schedule past what a `Duration` can hold
+const MIN_SPEED: f64 = 0.01;
+
+// Range replays remembered for `GET /api/replay/jobs/{id}`; finished ones go oldest first
+const KEPT_JOBS: usize = 64;
+
+// Marks a `CHAIN_TARGET` replay. A capture carrying it isn't chained again, so a target that
+// leads back to this instance's hook (directly or through other loggers) can't loop
+pub const CHAIN_HOP_HEADER: &str = "x-http-logger-chain";
+
+// Connection-level headers that describe the original hop and must be recomputed by the client
+const HOP_BY_HOP: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "expect",
+    "proxy-authorization",
+    "proxy-connection",
+];
+
+/// A request ready to be sent to a replay target.
+pub struct Outgoing {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Outgoing {
+    /// Rebuilds a stored capture for `target`. With `preserve_path` the original path and
+    /// query are appended to the target, otherwise the target URL is used as-is.
+    pub fn from_logged(db: &Connection, req: &LoggedRequest, target: &str, preserve_path: bool) -> Self {
+        let headers: Vec<(String, String)> = serde_json::from_str(&req.headers).unwrap_or_default();
+        let url = if preserve_path {
+            format!("{}{}", target.trim_end_matches('/'), path_and_query(&req.url))
+        } else {
+            target.to_string()
+        };
+        Self {
+            method: req.method.clone(),
+            url,
+            headers: headers
+                .into_iter()
+                .filter(|(k, _)| !is_hop_by_hop(k))
+                .collect(),
+            body: crate::stored_body(db, req),
+        }
+    }
+
+    /// The fully built request as JSON, for dry runs. The body is base64 like stored captures.
+    pub fn describe(&self) -> serde_json::Value {
+        json!({
+            "method": self.method,
+            "url": self.url,
+            "headers": self.headers,
+            "body": BASE64.encode(&self.body),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReplayQuery {
+    dry_run: Option<String>,
+    record: Option<String>,
+}
+
+impl ReplayQuery {
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.as_deref().is_some_and(is_truthy)
+    }
+
+    /// `?record=1`: each replay is also stored as a capture of its own (see `send_recorded`).
+    pub fn record(&self) -> bool {
+        self.record.as_deref().is_some_and(is_truthy)
+    }
+}
+
+pub fn is_hop_by_hop(name: &str) -> bool {
+    HOP_BY_HOP.contains(&name.to_ascii_lowercase().as_str())
+}
+
+/// Strips scheme and authority from absolute-form URLs.
+pub fn path_and_query(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or("/"),
+        None => url,
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReplayResult {
+    pub id: String,
+    pub status: Option<u16>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    /// Id of the capture recording this replay, with `?record=1`
+    pub recorded_id: Option<String>,
+}
+
+impl ReplayResult {
+    /// A replay that never got sent, e.g. because its capture couldn't be read back.
+    fn failed(id: &str, error: String) -> Self {
+        Self {
+            id: id.to_string(),
+            status: None,
+            response_body: None,
+            error: Some(error),
+            duration_ms: 0,
+            recorded_id: None,
+        }
+    }
+}
+
+pub async fn send(client: &reqwest::Client, id: &str, outgoing: Outgoing) -> ReplayResult {
+    let started = Instant::now();
+    let method = reqwest::Method::from_bytes(outgoing.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+
+    let mut builder = client.request(method, &outgoing.url).body(outgoing.body);
+    for (k, v) in &outgoing.headers {
+        builder = builder.header(k, v);
+    }
+
+    let result = match builder.send().await {
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            let body = resp.bytes().await.unwrap_or_default();
+            let end = body.len().min(MAX_RESPONSE_PREVIEW);
+            (Some(status), Some(String::from_utf8_lossy(&body[..end]).into_owned()), None)
+        }
+        Err(e) => (None, None, Some(e.to_string())),
+    };
+
+    ReplayResult {
+        id: id.to_string(),
+        status: result.0,
+        response_body: result.1,
+        error: result.2,
+        duration_ms: started.elapsed().as_millis() as i64,
+        recorded_id: None,
+    }
+}
+
+/// Sends like `send`, then stores what was sent and what came back as a new capture tagged
+/// `replay`, whose `replay_of` is the source's id, and announces it to dashboard clients.
+pub async fn send_recorded(state: &Arc<AppState>, source: &LoggedRequest, outgoing: Outgoing) -> ReplayResult {
+    let (timestamp, received_ns) = crate::receipt_time(state);
+    let id = uuid::Uuid::new_v4().to_string();
+    // Kept like a hook's body: large ones go to a file, or `body_blobs` in blob mode
+    let body_file = if state.blob_bodies { None } else { state.body_store.spill(&id, &outgoing.body).await };
+    let spilled = body_file.is_some() || state.blob_bodies && state.body_store.spills(outgoing.body.len());
+    let mut req = LoggedRequest {
+        id,
+        timestamp,
+        received_ns: Some(received_ns),
+        method: outgoing.method.clone(),
+        url: outgoing.url.clone(),
+        headers: serde_json::to_string(&outgoing.headers).unwrap_or_default(),
+        body: if spilled { String::new() } else { BASE64.encode(&outgoing.body) },
+        body_size: outgoing.body.len() as i64,
+        body_file,
+        spilled,
+        body_sha256: Some(format!("{:x}", Sha256::digest(&outgoing.body))),
+        host: reqwest::Url::parse(&outgoing.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string)),
+        endpoint: source.endpoint.clone(),
+        tags: json!(["replay"]).to_string(),
+        replay_of: Some(source.id.clone()),
+        ..Default::default()
+    };
+    let body = outgoing.body.clone();
+
+    let mut result = send(&state.http, &source.id, outgoing).await;
+    req.response_status = result.status.map(i64::from);
+    req.response_body = result.response_body.clone();
+    req.forward_error = result.error.clone();
+    req.duration_ms = Some(result.duration_ms);
+    req.preview = crate::preview::for_request(&req);
+
+    let stored = {
+        let (state, req, body) = (state.clone(), req.clone(), body.clone());
+        tokio::task::spawn_blocking(move || crate::store_request(&state, &req, &body).map_err(|e| e.to_string()))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()))
+    };
+    match stored {
+        Ok(_) => {
+            let _ = state.tx.send(json!({"type": "new", "request": req}).to_string());
+            let _ = state.tx.send(crate::hexdump::frame(&req.id, &body, req.body_size, state.hexdump_bytes));
+            result.recorded_id = Some(req.id);
+        }
+        Err(e) => {
+            tracing::warn!("failed to record replay of {}: {e}", source.id);
+            if let Some(path) = &req.body_file {
+                std::fs::remove_file(path).ok();
+            }
+        }
+    }
+    result
+}
+
+#[derive(Deserialize)]
+pub struct SingleReplay {
+    target: String,
+    #[serde(default)]
+    preserve_path: bool,
+}
+
+/// Re-sends one stored capture to `target` and reports the status and a response preview.
+/// With `?record=1` the replay is kept as a capture too, and its id is the `recorded_id`.
+pub async fn one(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+    Json(input): Json<SingleReplay>,
+) -> Result<Response, pool::Unavailable> {
+    let found = state
+        .db
+        .run(move |db| {
+            let req = fetch_request(db, &id)?;
+            let outgoing = Outgoing::from_logged(db, &req, &input.target, input.preserve_path);
+            Some((req, outgoing))
+        })
+        .await?;
+    let Some((req, outgoing)) = found else {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "request not found"}))).into_response());
+    };
+    if query.dry_run() {
+        let mut planned = outgoing.describe();
+        planned["id"] = json!(req.id);
+        return Ok(Json(json!({"dry_run": true, "request": planned})).into_response());
+    }
+    let result = if query.record() {
+        send_recorded(&state, &req, outgoing).await
+    } else {
+        send(&state.http, &req.id, outgoing).await
+    };
+    Ok(Json(result).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct TimedReplay {
+    ids: Vec<String>,
+    target: String,
+    #[serde(default = "default_speed")]
+    speed: f64,
+    #[serde(default)]
+    preserve_path: bool,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+fn valid_speed(speed: f64) -> bool {
+    speed.is_finite() && speed >= MIN_SPEED
+}
+
+/// Replays captures in chronological order, sleeping between them for the original
+/// inter-arrival gap divided by `speed`. With `?dry_run=1` the schedule and the requests
+/// that would be sent are returned without sending anything.
+pub async fn timed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ReplayQuery>,
+    Json(input): Json<TimedReplay>,
+) -> Result<Response, pool::Unavailable> {
+    if !valid_speed(input.speed) {
+        return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": format!("speed must be at least {MIN_SPEED}")})))
+            .into_response());
+    }
+
+    let ids = input.ids.clone();
+    let mut requests: Vec<LoggedRequest> = state
+        .db
+        .run(move |db| ids.iter().filter_map(|id| fetch_request(db, id)).collect())
+        .await?;
+    if requests.is_empty() {
+        return Ok((StatusCode::NOT_FOUND, Json(json!({"error": "no matching requests"}))).into_response());
+    }
+    // The same order history pages in, so captures within one millisecond keep theirs
+    requests.sort_by(|a, b| (&a.timestamp, a.received_ns, &a.id).cmp(&(&b.timestamp, b.received_ns, &b.id)));
+
+    if query.dry_run() {
+        let first = requests.first().and_then(|r| parse_time(&r.timestamp));
+        let (target, preserve_path) = (input.target.clone(), input.preserve_path);
+        let planned: Vec<serde_json::Value> = state
+            .db
+            .run(move |db| {
+                requests
+                    .iter()
+                    .map(|req| {
+                        let mut entry = Outgoing::from_logged(db, req, &target, preserve_path).describe();
+                        entry["id"] = json!(req.id);
+                        entry["offset_ms"] = json!(offset_ms(first, req));
+                        entry
+                    })
+                    .collect()
+            })
+            .await?;
+        return Ok(Json(json!({"dry_run": true, "requests": planned})).into_response());
+    }
+
+    let results = replay_timed(
+        &state,
+        &requests,
+        &input.target,
+        input.speed,
+        input.preserve_path,
+        query.record(),
+        |_| {},
+    )
+    .await;
+    Ok(Json(json!({"results": results})).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct RangeReplay {
+    from: String,
+    to: String,
+    target: String,
+    #[serde(default = "default_true")]
+    preserve_timing: bool,
+    #[serde(default = "default_speed")]
+    speed: f64,
+    #[serde(default)]
+    preserve_path: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A range replay's progress, as `GET /api/replay/jobs/{id}` reports it.
+#[derive(Serialize, Clone)]
+struct Job {
+    id: String,
+    done: bool,
+    total: usize,
+    sent: usize,
+    succeeded: usize,
+    failed: usize,
+    results: Vec<serde_json::Value>,
+}
+
+/// Range replays started in the background, running and recently finished.
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Mutex<VecDeque<Job>>,
+}
+
+impl Jobs {
+    fn start(&self, total: usize) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.len() >= KEPT_JOBS
+            && let Some(oldest) = jobs.iter().position(|job| job.done)
+        {
+            jobs.remove(oldest);
+        }
+        jobs.push_back(Job {
+            id: id.clone(),
+            done: total == 0,
+            total,
+            sent: 0,
+            succeeded: 0,
+            failed: 0,
+            results: Vec::new(),
+        });
+        id
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().iter_mut().find(|job| job.id == id) {
+            f(job);
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<Job> {
+        self.jobs.lock().unwrap().iter().find(|job| job.id == id).cloned()
+    }
+}
+
+/// Replays every capture with `from <= timestamp <= to`, in order, keeping the original
+/// spacing unless `preserve_timing` is false. That can take as long as the window did, so
+/// it answers `202` with a job right away; `GET /api/replay/jobs/{id}` follows it.
+pub async fn range(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ReplayQuery>,
+    Json(input): Json<RangeReplay>,
+) -> Result<Response, pool::Unavailable> {
+    if !valid_speed(input.speed) {
+        return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": format!("speed must be at least {MIN_SPEED}")})))
+            .into_response());
+    }
+    let bounds = normalize_timestamp(&input.from).and_then(|from| Ok((from, normalize_timestamp(&input.to)?)));
+    let (from, to) = match bounds {
+        Ok(bounds) => bounds,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()),
+    };
+
+    let mut requests = state
+        .db
+        .run(move |db| {
+            select_requests(
+                db,
+                "WHERE timestamp >= ? AND timestamp <= ?",
+                "",
+                &[rusqlite::types::Value::Text(from), rusqlite::types::Value::Text(to)],
+            )
+        })
+        .await?;
+    requests.reverse();
+
+    if query.dry_run() {
+        let (target, preserve_path) = (input.target.clone(), input.preserve_path);
+        let planned: Vec<serde_json::Value> = state
+            .db
+            .run(move |db| {
+                requests
+                    .iter()
+                    .map(|req| {
+                        let mut entry = Outgoing::from_logged(db, req, &target, preserve_path).describe();
+                        entry["id"] = json!(req.id);
+                        entry
+                    })
+                    .collect()
+            })
+            .await?;
+        return Ok(Json(json!({"dry_run": true, "total": planned.len(), "requests": planned})).into_response());
+    }
+
+    // Without timing, an infinite speed turns every scheduled delay into zero
+    let speed = if input.preserve_timing { input.speed } else { f64::INFINITY };
+    let total = requests.len();
+    let id = state.replay_jobs.start(total);
+    let job = id.clone();
+    tokio::spawn(async move {
+        let record = |result: &serde_json::Value| {
+            let succeeded = result["status"].as_u64().is_some_and(|s| (200..300).contains(&s));
+            state.replay_jobs.update(&job, |job| {
+                job.sent += 1;
+                if succeeded {
+                    job.succeeded += 1;
+                } else {
+                    job.failed += 1;
+                }
+                job.results.push(result.clone());
+                job.done = job.sent == job.total;
+            });
+        };
+        replay_timed(&state, &requests, &input.target, speed, input.preserve_path, query.record(), record).await;
+    });
+
+    let location = format!("/api/replay/jobs/{id}");
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, location.clone())],
+        Json(json!({"job": id, "total": total, "status": location})),
+    )
+        .into_response())
+}
+
+/// `GET /api/replay/jobs/{id}`: how far a range replay has got, with the results so far.
+pub async fn job(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match state.replay_jobs.get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": "replay job not found"}))).into_response(),
+    }
+}
+
+/// Sends `requests` (already in chronological order) on the original schedule scaled by
+/// `speed`, recording each replay as a capture when `record` is set. `sent` sees each
+/// result as soon as it's in.
+pub async fn replay_timed(
+    state: &Arc<AppState>,
+    requests: &[LoggedRequest],
+    target: &str,
+    speed: f64,
+    preserve_path: bool,
+    record: bool,
+    mut sent: impl FnMut(&serde_json::Value),
+) -> Vec<serde_json::Value> {
+    let first = requests.first().and_then(|r| parse_time(&r.timestamp));
+    let start = tokio::time::Instant::now();
+    let mut results = Vec::with_capacity(requests.len());
+
+    for req in requests {
+        let offset_ms = offset_ms(first, req);
+        let delay = Duration::from_secs_f64(offset_ms as f64 / 1000.0 / speed);
+        tokio::time::sleep_until(start + delay).await;
+
+        let (source, target_url) = (req.clone(), target.to_string());
+        let outgoing = state
+            .db
+            .run(move |db| Outgoing::from_logged(db, &source, &target_url, preserve_path))
+            .await;
+        let result = match outgoing {
+            Err(e) => ReplayResult::failed(&req.id, e.to_string()),
+            Ok(outgoing) if record => send_recorded(state, req, outgoing).await,
+            Ok(outgoing) => send(&state.http, &req.id, outgoing).await,
+        };
+        let mut entry = serde_json::to_value(&result).unwrap_or_default();
+        entry["offset_ms"] = json!(offset_ms);
+        sent(&entry);
+        results.push(entry);
+    }
+    results
+}
+
+/// Milliseconds between the first capture of a replay and `req`.
+fn offset_ms(first: Option<chrono::DateTime<chrono::FixedOffset>>, req: &LoggedRequest) -> i64 {
+    match (first, parse_time(&req.timestamp)) {
+        (Some(first), Some(at)) => (at - first).num_milliseconds().max(0),
+        _ => 0,
+    }
+}
+
+fn parse_time(ts: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::parse_from_rfc3339(ts).ok()
+}
+
+/// Replays matching captures to a downstream target after the sender has been answered.
+pub struct Chain {
+    pub target: String,
+    pub path: Option<Regex>,
+    pub preserve_path: bool,
+}
+
+impl Chain {
+    pub fn matches(&self, path: &str, headers: &HeaderMap) -> bool {
+        !headers.contains_key(CHAIN_HOP_HEADER) && self.path.as_ref().is_none_or(|p| p.is_match(path))
+    }
+
+    /// Sends the stored capture in the background and records the outcome in `chain_result`,
+    /// notifying clients with an `update` message.
+    pub fn spawn(state: Arc<AppState>, req: &LoggedRequest) {
+        let Some(chain) = &state.chain else {
+            return;
+        };
+        let (target, preserve_path) = (chain.target.clone(), chain.preserve_path);
+        let (req, id) = (req.clone(), req.id.clone());
+
+        tokio::spawn(async move {
+            let outgoing = state
+                .db
+                .run(move |db| {
+                    let mut outgoing = Outgoing::from_logged(db, &req, &target, preserve_path);
+                    outgoing.headers.push((CHAIN_HOP_HEADER.to_string(), "1".to_string()));
+                    outgoing
+                })
+                .await;
+            let result = match outgoing {
+                Ok(outgoing) => send(&state.http, &id, outgoing).await,
+                Err(e) => ReplayResult::failed(&id, e.to_string()),
+            };
+            if let Some(error) = &result.error {
+                tracing::warn!("chain replay of {id} failed: {error}");
+            }
+            let result = serde_json::to_string(&result).unwrap_or_default();
+            let updated = state
+                .db
+                .run(move |db| {
+                    db.execute(
+                        "UPDATE requests SET chain_result = ?1 WHERE id = ?2",
+                        rusqlite::params![result, id],
+                    )
+                    .ok();
+                    fetch_request(db, &id)
+                })
+                .await
+                .ok()
+                .flatten();
+            if let Some(req) = updated {
+                let _ = state.tx.send(json!({"type": "update", "request": req}).to_string());
+            }
+        });
+    }
+}
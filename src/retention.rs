@@ -0,0 +1,39 @@
+use chrono::SecondsFormat;
+use rusqlite::types::Value as SqlValue;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::AppState;
+
+/// `RETENTION_HOURS`: every `interval`, deletes unpinned requests older than `hours`, on top
+/// of the `MAX_REQUESTS` cap applied at insert time. Connected UIs get a `pruned` message
+/// with the cutoff so they can drop those rows and fix their totals.
+pub fn spawn(state: Arc<AppState>, hours: u64, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            let cutoff = (chrono::Utc::now() - chrono::Duration::hours(hours as i64))
+                .to_rfc3339_opts(SecondsFormat::Millis, true);
+            let params = [SqlValue::Text(cutoff.clone())];
+            let deleted = state
+                .db
+                .run(move |db| crate::bodies::delete_requests(db, "WHERE pinned = 0 AND timestamp < ?1", &params))
+                .await
+                .map_err(rusqlite::Error::from)
+                .and_then(|deleted| deleted);
+            match deleted {
+                Ok(0) => {}
+                Ok(deleted) => {
+                    tracing::info!("RETENTION_HOURS: pruned {deleted} requests older than {cutoff}");
+                    let _ = state
+                        .tx
+                        .send(json!({"type": "pruned", "before": cutoff, "deleted": deleted}).to_string());
+                }
+                Err(e) => tracing::warn!("RETENTION_HOURS: prune failed: {e}"),
+            }
+        }
+    });
+}
@@ -0,0 +1,194 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::pool::{self, Db};
+use crate::{AppState, LoggedRequest, SELECT_COLUMNS, map_row, open_db};
+
+/// `DB_ROTATION=daily`: captures go to `<stem>-YYYY-MM-DD.<ext>` next to `DB_PATH`,
+/// switching files at the first capture after UTC midnight.
+pub struct Rotation {
+    dir: PathBuf,
+    stem: String,
+    extension: String,
+    keep: usize,
+    day: Mutex<NaiveDate>,
+}
+
+impl Rotation {
+    /// `keep` is how many day files to retain (0 = all).
+    pub fn new(db_path: &str, keep: usize) -> Self {
+        let path = std::path::Path::new(db_path);
+        Self {
+            dir: path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| p.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from(".")),
+            stem: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "data".to_string()),
+            extension: path
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .unwrap_or_default(),
+            keep,
+            day: Mutex::new(today()),
+        }
+    }
+
+    fn path_for(&self, day: NaiveDate) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}{}", self.stem, day.format("%Y-%m-%d"), self.extension))
+    }
+
+    /// The file snapshots are kept in, `<stem>-snapshots.<ext>`, which is never rotated.
+    pub fn snapshots_path(&self) -> PathBuf {
+        self.dir.join(format!("{}-snapshots{}", self.stem, self.extension))
+    }
+
+    pub fn open_current(&self, pool_size: u32) -> Result<Db, String> {
+        let day = *self.day.lock().unwrap();
+        let db = Db::open(&self.path_for(day).to_string_lossy(), pool_size)?;
+        self.prune();
+        Ok(db)
+    }
+
+    /// Points `db` at today's file if the day has changed since it was opened.
+    pub fn roll(&self, db: &Db) {
+        let now = today();
+        let mut day = self.day.lock().unwrap();
+        if *day == now {
+            return;
+        }
+        match db.reopen(&self.path_for(now).to_string_lossy()) {
+            Ok(()) => {
+                tracing::info!("DB_ROTATION: switched to {}", self.path_for(now).display());
+                *day = now;
+            }
+            // Keep writing to the previous day rather than losing captures
+            Err(e) => {
+                tracing::error!("DB_ROTATION: failed to open {}: {e}", self.path_for(now).display());
+                return;
+            }
+        }
+        drop(day);
+        self.prune();
+    }
+
+    /// Day files present on disk, newest first.
+    fn days(&self) -> Vec<NaiveDate> {
+        let prefix = format!("{}-", self.stem);
+        let mut days: Vec<NaiveDate> = std::fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let date = name.strip_prefix(&prefix)?.strip_suffix(self.extension.as_str())?;
+                NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+            })
+            .collect();
+        days.sort_unstable_by(|a, b| b.cmp(a));
+        days
+    }
+
+    fn prune(&self) {
+        if self.keep == 0 {
+            return;
+        }
+        for day in self.days().into_iter().skip(self.keep) {
+            let path = self.path_for(day);
+            // Spilled bodies belong to the day file and go with it
+            if let Ok(conn) = Connection::open(&path) {
+                crate::bodies::delete_requests(&conn, "WHERE 1 = 1", &[]).ok();
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => tracing::info!("DB_ROTATION: removed {}", path.display()),
+                Err(e) => tracing::warn!("DB_ROTATION: failed to remove {}: {e}", path.display()),
+            }
+            for suffix in ["-wal", "-shm"] {
+                let mut sidecar = path.clone().into_os_string();
+                sidecar.push(suffix);
+                std::fs::remove_file(sidecar).ok();
+            }
+        }
+    }
+}
+
+fn today() -> NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({"error": message}))).into_response()
+}
+
+pub async fn list_days(State(state): State<Arc<AppState>>) -> Response {
+    let Some(rotation) = &state.rotation else {
+        return error(StatusCode::NOT_FOUND, "DB_ROTATION is not enabled");
+    };
+    let current = *rotation.day.lock().unwrap();
+    let days: Vec<serde_json::Value> = rotation
+        .days()
+        .into_iter()
+        .map(|day| json!({"day": day.to_string(), "current": day == current}))
+        .collect();
+    Json(days).into_response()
+}
+
+/// Requests stored in one day's file.
+pub async fn get_day(
+    State(state): State<Arc<AppState>>,
+    Path(day): Path<String>,
+) -> Result<Response, pool::Unavailable> {
+    let Some(rotation) = &state.rotation else {
+        return Ok(error(StatusCode::NOT_FOUND, "DB_ROTATION is not enabled"));
+    };
+    let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+        return Ok(error(StatusCode::BAD_REQUEST, "day must be YYYY-MM-DD"));
+    };
+
+    let requests: Vec<LoggedRequest> = if date == *rotation.day.lock().unwrap() {
+        state.db.run(query_all).await?
+    } else {
+        let path = rotation.path_for(date);
+        if !path.exists() {
+            return Ok(error(StatusCode::NOT_FOUND, "no database for that day"));
+        }
+        // Opening migrates files written by older versions to the current columns
+        let opened = tokio::task::spawn_blocking(move || open_db(&path.to_string_lossy()).map(|db| query_all(&db)))
+            .await
+            .expect("day export task panicked");
+        match opened {
+            Ok(requests) => requests,
+            Err(e) => return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())),
+        }
+    };
+
+    Ok(Json(json!({
+        "day": day,
+        "total": requests.len(),
+        "requests": requests,
+    }))
+    .into_response())
+}
+
+fn query_all(db: &Connection) -> Vec<LoggedRequest> {
+    let mut stmt = db
+        .prepare(&format!("SELECT {SELECT_COLUMNS} FROM requests ORDER BY timestamp DESC, received_ns DESC, id DESC"))
+        .unwrap();
+    stmt.query_map([], map_row)
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+}
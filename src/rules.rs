@@ -0,0 +1,141 @@
+use axum::http::HeaderMap;
+use regex::Regex;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct RuleConfig {
+    tag: Option<String>,
+    #[serde(default)]
+    pin: bool,
+    method: Option<String>,
+    path: Option<String>,
+    header: Option<String>,
+    header_contains: Option<String>,
+    body_contains: Option<String>,
+    status: Option<String>,
+}
+
+/// Conditions evaluated against each capture; every condition present must match.
+pub struct CaptureRule {
+    tag: Option<String>,
+    pin: bool,
+    method: Option<String>,
+    path: Option<Regex>,
+    header: Option<String>,
+    header_contains: Option<String>,
+    body_contains: Option<String>,
+    status: Option<(u16, u16)>,
+}
+
+/// What a rule gets to look at for a single capture.
+pub struct Capture<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub headers: &'a HeaderMap,
+    pub body: &'a [u8],
+    /// Status of the response the sender got (forwarded or canned)
+    pub status: u16,
+}
+
+/// Loads rules from a JSON file containing an array of
+/// `{"tag": "...", "pin": true, "method": "POST", "path": "<regex>", "header": "x-event-type",
+/// "header_contains": "...", "body_contains": "...", "status": "5xx"}` objects. A rule needs
+/// a `tag`, `pin` or both.
+pub fn load_rules(path: &str) -> Result<Vec<CaptureRule>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let configs: Vec<RuleConfig> = serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))?;
+    configs
+        .into_iter()
+        .map(|c| {
+            let regex = c
+                .path
+                .map(|p| Regex::new(&p))
+                .transpose()
+                .map_err(|e| format!("{path}: {e}"))?;
+            if c.tag.is_none() && !c.pin {
+                return Err(format!("{path}: every rule needs a \"tag\" or \"pin\": true"));
+            }
+            let status = c
+                .status
+                .map(|s| parse_status(&s).ok_or_else(|| format!("{path}: invalid status {s:?}: expected e.g. 503 or 5xx")))
+                .transpose()?;
+            Ok(CaptureRule {
+                tag: c.tag,
+                pin: c.pin,
+                method: c.method.map(|m| m.to_ascii_uppercase()),
+                path: regex,
+                header: c.header,
+                header_contains: c.header_contains,
+                body_contains: c.body_contains,
+                status,
+            })
+        })
+        .collect()
+}
+
+/// `503` or a class such as `5xx`, as an inclusive range.
+fn parse_status(status: &str) -> Option<(u16, u16)> {
+    if let Some(class) = status.strip_suffix("xx") {
+        let class: u16 = class.parse().ok().filter(|c| (1..=5).contains(c))?;
+        return Some((class * 100, class * 100 + 99));
+    }
+    let code: u16 = status.parse().ok().filter(|c| (100..=599).contains(c))?;
+    Some((code, code))
+}
+
+impl CaptureRule {
+    fn matches(&self, capture: &Capture) -> bool {
+        if self.method.as_deref().is_some_and(|m| m != capture.method) {
+            return false;
+        }
+        if self.path.as_ref().is_some_and(|p| !p.is_match(capture.path)) {
+            return false;
+        }
+        if let Some(name) = &self.header {
+            let Some(value) = capture.headers.get(name.as_str()) else {
+                return false;
+            };
+            if let Some(needle) = &self.header_contains
+                && !value.to_str().unwrap_or("").contains(needle.as_str())
+            {
+                return false;
+            }
+        }
+        if let Some(needle) = &self.body_contains
+            && !contains(capture.body, needle.as_bytes())
+        {
+            return false;
+        }
+        if self.status.is_some_and(|(low, high)| !(low..=high).contains(&capture.status)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Tags of every matching rule, deduplicated, in rule order.
+pub fn tags_for(rules: &[CaptureRule], capture: &Capture) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for rule in rules.iter().filter(|r| r.matches(capture)) {
+        if let Some(tag) = &rule.tag
+            && !tags.contains(tag)
+        {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// Whether any matching rule has `pin` set.
+pub fn pins(rules: &[CaptureRule], capture: &Capture) -> bool {
+    rules.iter().any(|r| r.pin && r.matches(capture))
+}
+
+/// Whether any rule has a `body_contains` condition and so needs each whole body.
+pub fn match_bodies(rules: &[CaptureRule]) -> bool {
+    rules.iter().any(|r| r.body_contains.is_some())
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+}
@@ -0,0 +1,88 @@
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// `SAMPLE_ABOVE_RPS`: stores every capture while arrivals stay under the threshold and,
+/// above it, only `threshold / rate` of them. Keeping is decided by accumulating that
+/// fraction rather than at random, so a steady firehose is thinned evenly.
+pub struct Sampler {
+    threshold: f64,
+    window: Mutex<Window>,
+}
+
+struct Window {
+    started: Instant,
+    arrivals: u64,
+    // Arrivals per second over the last completed window
+    rate: f64,
+    credit: f64,
+    sampled_out: u64,
+}
+
+impl Sampler {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            window: Mutex::new(Window {
+                started: Instant::now(),
+                arrivals: 0,
+                rate: 0.0,
+                credit: 0.0,
+                sampled_out: 0,
+            }),
+        }
+    }
+
+    /// Counts one arrival and says whether to store it.
+    pub fn admit(&self) -> bool {
+        let mut window = self.window.lock().unwrap();
+        let now = Instant::now();
+        let passed = (now.duration_since(window.started).as_secs_f64() / WINDOW.as_secs_f64()) as u32;
+        if passed > 0 {
+            window.rate = estimated_rate(&window, now);
+            window.started += WINDOW * passed;
+            window.arrivals = 0;
+        }
+        window.arrivals += 1;
+        window.credit = (window.credit + self.fraction_for(&window, now)).min(1.0);
+        if window.credit >= 1.0 {
+            window.credit -= 1.0;
+            true
+        } else {
+            window.sampled_out += 1;
+            false
+        }
+    }
+
+    /// The `sampling` object of `/api/stats`.
+    pub fn stats(&self) -> serde_json::Value {
+        let window = self.window.lock().unwrap();
+        let now = Instant::now();
+        json!({
+            "threshold_rps": self.threshold,
+            "rate_rps": estimated_rate(&window, now),
+            "fraction": self.fraction_for(&window, now),
+            "sampled_out": window.sampled_out,
+        })
+    }
+
+    fn fraction_for(&self, window: &Window, now: Instant) -> f64 {
+        let rate = estimated_rate(window, now);
+        if rate <= self.threshold { 1.0 } else { self.threshold / rate }
+    }
+}
+
+/// The last completed window's rate, or the current one's once it has already outrun it.
+/// A window that ended a whole window ago was followed by silence, so the rate is zero.
+fn estimated_rate(window: &Window, now: Instant) -> f64 {
+    let elapsed = now.duration_since(window.started);
+    if elapsed >= WINDOW * 2 {
+        0.0
+    } else if elapsed >= WINDOW {
+        window.arrivals as f64 / WINDOW.as_secs_f64()
+    } else {
+        window.rate.max(window.arrivals as f64)
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Ships serialized captures to a remote HTTP collector from a background task.
+///
+/// The queue is bounded so a slow or unreachable collector never blocks `log_request`;
+/// captures that don't fit, or that still fail after all retries, are dropped and counted.
+pub struct Sink {
+    tx: mpsc::Sender<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Sink {
+    pub fn spawn(url: String, queue_size: usize, retries: u32) -> Self {
+        let (tx, rx) = mpsc::channel(queue_size.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(run(url, rx, retries, dropped.clone()));
+        Self { tx, dropped }
+    }
+
+    pub fn send(&self, payload: String) {
+        if self.tx.try_send(payload).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::warn!("sink queue full, dropped capture ({dropped} dropped so far)");
+        }
+    }
+}
+
+async fn run(url: String, mut rx: mpsc::Receiver<String>, retries: u32, dropped: Arc<AtomicU64>) {
+    let client = reqwest::Client::new();
+
+    while let Some(payload) = rx.recv().await {
+        let mut attempt = 0;
+        loop {
+            let result = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.clone())
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status());
+
+            match result {
+                Ok(_) => break,
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    tracing::debug!("sink delivery failed (attempt {attempt}): {e}");
+                    tokio::time::sleep(Duration::from_millis(200 << attempt.min(6))).await;
+                }
+                Err(e) => {
+                    let dropped = dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    tracing::warn!("sink delivery failed, dropped capture ({dropped} dropped so far): {e}");
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::{AppState, INDEX_HTML, LoggedRequest, SELECT_COLUMNS, map_row, pool, static_asset};
+
+// Snapshot rows are stored as serialized `LoggedRequest`s so they survive later column migrations
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS snapshots (
+        name TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL,
+        request_count INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS snapshot_requests (
+        snapshot TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS snapshot_requests_snapshot ON snapshot_requests (snapshot, timestamp);
+";
+
+#[derive(Deserialize)]
+pub struct CreateSnapshot {
+    name: String,
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({"error": message}))).into_response()
+}
+
+/// The response, or `500` when SQLite failed partway through making it.
+fn answer(result: rusqlite::Result<Response>) -> Response {
+    result.unwrap_or_else(|e| error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()))
+}
+
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    Json(input): Json<CreateSnapshot>,
+) -> Result<Response, pool::Unavailable> {
+    if !valid_name(&input.name) {
+        return Ok(error(
+            StatusCode::BAD_REQUEST,
+            "name must be 1-64 characters of letters, digits, '-' or '_'",
+        ));
+    }
+
+    // With `DB_ROTATION` the snapshot file is opened on its own rather than checked out of
+    // `snapshot_db`; otherwise it's this same connection. Either way the copy holds only one
+    // pooled connection, so it can't wait on itself with `DB_POOL_SIZE=1`
+    let snapshots_path = state.rotation.as_ref().map(|rotation| rotation.snapshots_path());
+    let copied = state
+        .db
+        .run(move |db| {
+            let store = snapshots_path.map(|path| pool::connect(&path)).transpose()?;
+            copy_into_snapshot(db, store.as_ref().unwrap_or(db), input.name)
+        })
+        .await?;
+    Ok(answer(copied))
+}
+
+/// Where snapshots are kept. With `DB_ROTATION` that's a file of their own, so they don't
+/// go away when the day rolls over or `DB_ROTATION_KEEP` removes the day they were taken.
+fn store(state: &AppState) -> &pool::Db {
+    state.snapshot_db.as_ref().unwrap_or(&state.db)
+}
+
+fn copy_into_snapshot(db: &Connection, store: &Connection, name: String) -> rusqlite::Result<Response> {
+    let created_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let tx = store.unchecked_transaction()?;
+
+    let inserted = tx.execute(
+        "INSERT OR IGNORE INTO snapshots (name, created_at, request_count) VALUES (?1, ?2, 0)",
+        rusqlite::params![name, created_at],
+    )?;
+    if inserted == 0 {
+        return Ok(error(StatusCode::CONFLICT, "snapshot already exists"));
+    }
+
+    let requests: Vec<LoggedRequest> = {
+        let mut stmt =
+            db.prepare(&format!("SELECT {SELECT_COLUMNS} FROM requests ORDER BY timestamp DESC, received_ns DESC, id DESC"))?;
+        stmt.query_map([], map_row)?.filter_map(|r| r.ok()).collect()
+    };
+    let count = requests.len() as i64;
+    for mut req in requests {
+        // A snapshot outlives the body files and blobs, which rotation and retention remove,
+        // so it keeps its own inline copy, read one at a time
+        crate::resolve_body(db, &mut req);
+        req.spilled = false;
+        req.body_file = None;
+        tx.execute(
+            "INSERT INTO snapshot_requests (snapshot, timestamp, data) VALUES (?1, ?2, ?3)",
+            rusqlite::params![name, req.timestamp, serde_json::to_string(&req).unwrap_or_default()],
+        )?;
+    }
+    tx.execute(
+        "UPDATE snapshots SET request_count = ?1 WHERE name = ?2",
+        rusqlite::params![count, name],
+    )?;
+    tx.commit()?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "name": name,
+            "created_at": created_at,
+            "request_count": count,
+        })),
+    )
+        .into_response())
+}
+
+pub async fn list(State(state): State<Arc<AppState>>) -> Result<Response, pool::Unavailable> {
+    Ok(answer(store(&state).run(list_snapshots).await?))
+}
+
+fn list_snapshots(db: &Connection) -> rusqlite::Result<Response> {
+    let mut stmt = db.prepare("SELECT name, created_at, request_count FROM snapshots ORDER BY created_at DESC")?;
+    let snapshots: Vec<serde_json::Value> = stmt
+        .query_map([], |row| {
+            Ok(json!({
+                "name": row.get::<_, String>(0)?,
+                "created_at": row.get::<_, String>(1)?,
+                "request_count": row.get::<_, i64>(2)?,
+            }))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(Json(snapshots).into_response())
+}
+
+pub async fn get_one(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Response, pool::Unavailable> {
+    Ok(answer(store(&state).run(move |db| load_snapshot(db, name)).await?))
+}
+
+fn load_snapshot(db: &Connection, name: String) -> rusqlite::Result<Response> {
+    let Ok(created_at) = db.query_row(
+        "SELECT created_at FROM snapshots WHERE name = ?1",
+        rusqlite::params![name],
+        |row| row.get::<_, String>(0),
+    ) else {
+        return Ok(error(StatusCode::NOT_FOUND, "snapshot not found"));
+    };
+
+    let mut stmt = db.prepare("SELECT data FROM snapshot_requests WHERE snapshot = ?1 ORDER BY timestamp DESC")?;
+    let requests: Vec<LoggedRequest> = stmt
+        .query_map(rusqlite::params![name], |row| row.get::<_, String>(0))?
+        .filter_map(|r| r.ok())
+        .filter_map(|data| serde_json::from_str(&data).ok())
+        .collect();
+
+    Ok(Json(json!({
+        "name": name,
+        "created_at": created_at,
+        "total": requests.len(),
+        "requests": requests,
+    }))
+    .into_response())
+}
+
+pub async fn delete_one(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Response, pool::Unavailable> {
+    Ok(answer(store(&state).run(move |db| delete_snapshot(db, name)).await?))
+}
+
+fn delete_snapshot(db: &Connection, name: String) -> rusqlite::Result<Response> {
+    let deleted = db.execute("DELETE FROM snapshots WHERE name = ?1", rusqlite::params![name])?;
+    db.execute("DELETE FROM snapshot_requests WHERE snapshot = ?1", rusqlite::params![name])?;
+    if deleted == 0 {
+        return Ok(error(StatusCode::NOT_FOUND, "snapshot not found"));
+    }
+    Ok(Json(json!({"ok": 1})).into_response())
+}
+
+/// The regular UI; `app.js` switches to read-only snapshot mode based on the path.
+pub async fn serve_ui(State(state): State<Arc<AppState>>) -> Html<Cow<'static, str>> {
+    Html(static_asset(&state, "index.html", INDEX_HTML))
+}
@@ -0,0 +1,150 @@
+use axum::{
+    Json,
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rusqlite::Connection;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::bodies::StagedBlob;
+use crate::{
+    AppState, LoggedRequest, QUOTA_REACHED, fallback, fetch_request, pool, quota_reached, store_request, store_request_in,
+};
+
+/// What became of a capture on its way into the database.
+pub struct Stored {
+    /// `COLLAPSE_REPEATS_MS`: the earlier capture this one was folded into, count bumped
+    pub repeat_of: Option<LoggedRequest>,
+    /// `SAMPLE_ABOVE_RPS` left it out
+    pub sampled_out: bool,
+    /// The stored count, when `HOOK_RESPONSE_HEADERS` needs it
+    pub count: Option<i64>,
+}
+
+/// Collapses, samples or inserts the capture. A failed insert is buffered by
+/// `FALLBACK_BUFFER` if set; `Err` is the answer when the sender must not take the capture as
+/// accepted: the quota filled up meanwhile, or `CONFIRM_STORAGE` and the write failed.
+/// `body` is what the insert writes, empty when `staged` already holds it.
+pub async fn store(
+    state: &Arc<AppState>,
+    req: &LoggedRequest,
+    staged: Option<StagedBlob>,
+    body: Bytes,
+) -> Result<Stored, Response> {
+    let repeat_of = match state.collapse_repeats_ms {
+        0 => None,
+        window_ms => {
+            let req = req.clone();
+            state.db.run(move |db| collapse_repeat(db, &req, window_ms)).await.ok().flatten()
+        }
+    };
+    // Every arrival counts toward the rate, but pinned captures are always kept
+    let sampled_out = state.sampler.as_ref().is_some_and(|s| !s.admit()) && !req.pinned && repeat_of.is_none();
+    let stored = if let Some(original) = &repeat_of {
+        remove_body_file(req);
+        tracing::info!("collapsed repeat of {} {} into {}", req.method, req.url, original.id);
+        Ok(None)
+    } else if sampled_out {
+        remove_body_file(req);
+        tracing::debug!("sampled out {} {}", req.method, req.url);
+        Ok(None)
+    } else {
+        let (state, req, body) = (state.clone(), req.clone(), body.clone());
+        // The row goes in with its streamed body, even if `DB_ROTATION` has rolled since
+        let staged_in = staged.as_ref().map(|blob| blob.pool().clone());
+        tokio::task::spawn_blocking(move || {
+            match staged_in {
+                Some(pool) => pool::checkout(&pool)
+                    .map_err(rusqlite::Error::from)
+                    .and_then(|db| store_request_in(&state, &db, &req, &body)),
+                None => store_request(&state, &req, &body),
+            }
+            .map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+    };
+    let count = match stored {
+        Ok(count) => {
+            // Collapsed and sampled-out captures store nothing, so their streamed body goes too
+            if repeat_of.is_none()
+                && !sampled_out
+                && let Some(blob) = staged
+            {
+                blob.keep();
+            }
+            if let Some(fallback) = &state.fallback
+                && fallback.succeeded()
+            {
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || fallback::flush(&state));
+            }
+            count
+        }
+        // Another capture took the last slot after the arrival check
+        Err(e) if e == QUOTA_REACHED => {
+            remove_body_file(req);
+            return Err(quota_reached(state));
+        }
+        // The sender must not take this capture as accepted, so nothing else sees it either
+        Err(e) if state.confirm_storage => {
+            tracing::error!("failed to store {} {}: {e}", req.method, req.url);
+            remove_body_file(req);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "failed to store request"})))
+                .into_response());
+        }
+        Err(e) => {
+            tracing::warn!("failed to store {} {}: {e}", req.method, req.url);
+            if let Some(fallback) = &state.fallback {
+                // The buffered capture is inserted on its own later; one it outlives is swept on restart
+                if let Some(blob) = staged {
+                    blob.keep();
+                }
+                if fallback.push(req.clone(), body, &e) {
+                    tracing::error!("storage degraded; keeping captures in memory until writes recover");
+                    let _ = state.tx.send(fallback.notice());
+                }
+            }
+            None
+        }
+    };
+    Ok(Stored {
+        repeat_of,
+        sampled_out,
+        count,
+    })
+}
+
+fn remove_body_file(req: &LoggedRequest) {
+    if let Some(path) = &req.body_file {
+        std::fs::remove_file(path).ok();
+    }
+}
+
+/// `COLLAPSE_REPEATS_MS`: when the latest capture from the same address is this same
+/// request (method, URL and body hash) and was stored within the window, bumps its
+/// `repeat_count` instead and returns it updated.
+fn collapse_repeat(db: &Connection, req: &LoggedRequest, window_ms: u64) -> Option<LoggedRequest> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::milliseconds(window_ms as i64))
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let (id, method, url, body_sha256): (String, String, String, Option<String>) = db
+        .query_row(
+            "SELECT id, method, url, body_sha256 FROM requests
+             WHERE remote_addr = ?1 AND timestamp >= ?2
+             ORDER BY timestamp DESC, received_ns DESC, id DESC LIMIT 1",
+            rusqlite::params![req.remote_addr, cutoff],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .ok()?;
+    if method != req.method || url != req.url || body_sha256 != req.body_sha256 {
+        return None;
+    }
+    db.execute(
+        "UPDATE requests SET repeat_count = repeat_count + 1 WHERE id = ?1",
+        rusqlite::params![id],
+    )
+    .ok()?;
+    fetch_request(db, &id)
+}
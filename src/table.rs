@@ -0,0 +1,151 @@
+use axum::{
+    extract::{Query, State},
+    response::Html,
+};
+use rusqlite::Connection;
+use rusqlite::types::Value as SqlValue;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{AppState, HistoryCursor, HistoryFilter, LoggedRequest, PAGE_SIZE, load_history, pool};
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    before: Option<String>,
+    before_ns: Option<i64>,
+    before_id: Option<String>,
+}
+
+/// `GET /api/requests/html`: the history as a plain HTML table with newer/older links, for
+/// browsers and text-mode clients that can't run the app. Takes the `/ws` filters.
+pub async fn page(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<HistoryFilter>,
+    Query(query): Query<PageQuery>,
+) -> Result<Html<String>, pool::Unavailable> {
+    let cursor = query.before.clone().map(|timestamp| HistoryCursor {
+        timestamp,
+        received_ns: query.before_ns,
+        id: query.before_id.clone(),
+    });
+    let (requests, total, newer_cursor) = {
+        let (filter, newer) = (filter.clone(), query.before.is_some());
+        state
+            .db
+            .run(move |db| {
+                let (requests, total) = load_history(db, cursor, &filter);
+                let newer_cursor = requests
+                    .first()
+                    .filter(|_| newer)
+                    .and_then(|first| newer_page(db, &filter, first));
+                (requests, total, newer_cursor)
+            })
+            .await?
+    };
+
+    let mut filter_params = String::new();
+    for (name, value) in [("host", &filter.host), ("endpoint", &filter.endpoint)] {
+        if let Some(value) = value {
+            filter_params.push_str(&format!("&{name}={}", encode(value)));
+        }
+    }
+    let link = |cursor: Option<&HistoryCursor>| match cursor {
+        Some(cursor) => {
+            let mut href = format!("?before={}", encode(&cursor.timestamp));
+            if let Some(received_ns) = cursor.received_ns {
+                href.push_str(&format!("&before_ns={received_ns}"));
+            }
+            if let Some(id) = &cursor.id {
+                href.push_str(&format!("&before_id={}", encode(id)));
+            }
+            format!("{href}{filter_params}")
+        }
+        None => format!("?{}", filter_params.trim_start_matches('&')),
+    };
+
+    // Past the last page there's no row to start from, so "newer" goes back to the first page
+    let newer = query
+        .before
+        .is_some()
+        .then(|| link(newer_cursor.as_ref()));
+    let older = (requests.len() == PAGE_SIZE)
+        .then(|| requests.last())
+        .flatten()
+        .map(|last| link(Some(&HistoryCursor::after(last))));
+
+    let mut rows = String::new();
+    for req in &requests {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><a href=\"/api/requests/{}\">{}</a></td></tr>\n",
+            escape(&req.timestamp),
+            escape(&req.method),
+            escape(&req.url),
+            escape(req.endpoint.as_deref().unwrap_or("")),
+            req.body_size,
+            req.response_status.map(|s| s.to_string()).unwrap_or_default(),
+            encode(&req.id),
+            escape(&req.id[..req.id.len().min(8)]),
+        ));
+    }
+
+    let nav = |label: &str, href: Option<String>| match href {
+        Some(href) => format!("<a href=\"{}\">{label}</a>", escape(&href)),
+        None => format!("<span>{label}</span>"),
+    };
+    Ok(Html(format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head><meta charset=\"UTF-8\"><title>Neo HTTP Logger</title>
+<style>body{{font-family:monospace}}table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:2px 6px;text-align:left}}</style>
+</head>
+<body>
+<h1>Neo HTTP Logger</h1>
+<p>{total} requests</p>
+<p>{} | {}</p>
+<table>
+<tr><th>Time</th><th>Method</th><th>URL</th><th>Endpoint</th><th>Bytes</th><th>Status</th><th>ID</th></tr>
+{rows}</table>
+</body>
+</html>
+",
+        nav("&larr; Newer", newer),
+        nav("Older &rarr;", older),
+    )))
+}
+
+/// The cursor whose page holds the `PAGE_SIZE` rows just newer than `first`, or `None`
+/// when that's the first page.
+fn newer_page(db: &Connection, filter: &HistoryFilter, first: &LoggedRequest) -> Option<HistoryCursor> {
+    let (conditions, mut params) = filter.sql();
+    let mut conditions: Vec<String> = conditions.into_iter().map(str::to_string).collect();
+    conditions.push(HistoryCursor::after(first).condition(">", &mut params));
+    params.push(SqlValue::Integer(PAGE_SIZE as i64));
+    db.query_row(
+            &format!(
+                "SELECT timestamp, received_ns, id FROM requests WHERE {}
+                 ORDER BY timestamp ASC, received_ns ASC, id ASC LIMIT 1 OFFSET ?",
+                conditions.join(" AND ")
+            ),
+            rusqlite::params_from_iter(&params),
+            |row| {
+                Ok(HistoryCursor {
+                    timestamp: row.get(0)?,
+                    received_ns: row.get(1)?,
+                    id: Some(row.get(2)?),
+                })
+            },
+        )
+        .ok()
+}
+
+fn encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
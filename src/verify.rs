@@ -0,0 +1,177 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rusqlite::{Connection, DatabaseName};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::{AppState, pool};
+
+// Rows hashed per connection checkout, so inserts can interleave with a long verify
+const BATCH_SIZE: i64 = 500;
+
+#[derive(Serialize)]
+struct Mismatch {
+    id: String,
+    timestamp: String,
+    reason: String,
+    stored_sha256: String,
+    computed_sha256: Option<String>,
+}
+
+struct Row {
+    rowid: i64,
+    id: String,
+    timestamp: String,
+    body: String,
+    body_file: Option<String>,
+    // Set when the body is the row's `body_blob`; `blob_rowid` is its `body_blobs` row otherwise
+    row_blob: bool,
+    blob_rowid: Option<i64>,
+    body_sha256: Option<String>,
+    body_dropped: bool,
+    body_redacted: bool,
+}
+
+impl Row {
+    fn hashed(&self) -> bool {
+        self.body_sha256.is_some() && !self.body_dropped && !self.body_redacted
+    }
+}
+
+/// Recomputes every stored body's SHA-256 and reports those that no longer match
+/// `body_sha256`. Dropped and redacted bodies were hashed before they were changed and
+/// rows from before hashing existed have no hash, so those are counted but not checked.
+pub async fn verify(State(state): State<Arc<AppState>>) -> Result<Response, pool::Unavailable> {
+    let mut after = 0i64;
+    let (mut checked, mut verified, mut dropped, mut redacted, mut unhashed) = (0, 0, 0, 0, 0);
+    let mut mismatches = Vec::new();
+
+    loop {
+        // Hashed on the blocking pool along with the query that found the rows
+        let batch = state.db.run(move |db| hash_batch(db, after)).await?;
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => {
+                return Ok((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))).into_response());
+            }
+        };
+        let Some((last, _)) = batch.last() else {
+            break;
+        };
+        after = last.rowid;
+
+        for (row, computed) in batch {
+            checked += 1;
+            let Some(stored) = row.body_sha256 else {
+                unhashed += 1;
+                continue;
+            };
+            if row.body_dropped {
+                dropped += 1;
+                continue;
+            }
+            if row.body_redacted {
+                redacted += 1;
+                continue;
+            }
+            let mismatch = match computed {
+                Some(Ok(computed)) => {
+                    (computed != stored).then(|| ("body does not match body_sha256".to_string(), Some(computed)))
+                }
+                Some(Err(reason)) => Some((reason, None)),
+                None => None,
+            };
+            match mismatch {
+                None => verified += 1,
+                Some((reason, computed_sha256)) => mismatches.push(Mismatch {
+                    id: row.id,
+                    timestamp: row.timestamp,
+                    reason,
+                    stored_sha256: stored,
+                    computed_sha256,
+                }),
+            }
+        }
+        tokio::task::yield_now().await;
+    }
+
+    Ok(Json(json!({
+        "ok": mismatches.is_empty(),
+        "checked": checked,
+        "verified": verified,
+        "skipped": {"dropped": dropped, "redacted": redacted, "unhashed": unhashed},
+        "mismatches": mismatches,
+    }))
+    .into_response())
+}
+
+// A row and, when it has one to check, its body's SHA-256 or why it couldn't be read
+type Hashed = (Row, Option<Result<String, String>>);
+
+/// The next `BATCH_SIZE` rows after `after`, hashed.
+fn hash_batch(db: &Connection, after: i64) -> rusqlite::Result<Vec<Hashed>> {
+    let mut stmt = db.prepare(
+        "SELECT rowid, id, timestamp, body, body_file, body_sha256, body_dropped, body_redacted, body_blob IS NOT NULL,
+                (SELECT rowid FROM body_blobs WHERE body_blobs.id = requests.id)
+         FROM requests WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+    )?;
+    let rows: Vec<Row> = stmt
+        .query_map(rusqlite::params![after, BATCH_SIZE], |row| {
+            Ok(Row {
+                rowid: row.get(0)?,
+                id: row.get(1)?,
+                timestamp: row.get(2)?,
+                body: row.get(3)?,
+                body_file: row.get(4)?,
+                body_sha256: row.get(5)?,
+                body_dropped: row.get(6)?,
+                body_redacted: row.get(7)?,
+                row_blob: row.get(8)?,
+                blob_rowid: row.get(9)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let computed = row.hashed().then(|| hash(db, &row));
+            (row, computed)
+        })
+        .collect())
+}
+
+/// Streams the body into the hasher from wherever it's kept, so no file or BLOB is read whole.
+fn hash(db: &Connection, row: &Row) -> Result<String, String> {
+    let mut sha256 = Sha256::new();
+    let blob = if row.row_blob {
+        Some(("requests", "body_blob", row.rowid))
+    } else {
+        row.blob_rowid.map(|rowid| ("body_blobs", "body", rowid))
+    };
+    match (blob, &row.body_file) {
+        (Some((table, column, rowid)), _) => db
+            .blob_open(DatabaseName::Main, table, column, rowid, true)
+            .map_err(|e| e.to_string())
+            .and_then(|mut blob| std::io::copy(&mut blob, &mut sha256).map_err(|e| e.to_string()))
+            .map_err(|e| format!("body blob: {e}"))?,
+        (None, Some(path)) => File::open(path)
+            .and_then(|mut file| std::io::copy(&mut file, &mut sha256))
+            .map_err(|e| format!("body file {path}: {e}"))?,
+        (None, None) => {
+            let body = BASE64.decode(&row.body).map_err(|e| format!("body is not valid base64: {e}"))?;
+            sha256.update(&body);
+            body.len() as u64
+        }
+    };
+    Ok(format!("{:x}", sha256.finalize()))
+}
@@ -6,6 +6,9 @@ let loadedCount = 0;
 let ws = null;
 let reconnectDelay = 1000;
 let loading = false;
+let endpointFilter = '';
+// Live hexdump frames by request id; older requests are fetched when the tab is opened
+const hexdumps = new Map();
 
 // === DOM ===
 const $ = (s) => document.querySelector(s);
@@ -19,6 +22,13 @@ const placeholderCurl = $('#placeholder-curl');
 const copyBtn = $('#copy-curl-btn');
 const clearBtn = $('#clear-btn');
 const sidebar = $('#sidebar');
+const snapshotBtn = $('#snapshot-btn');
+const endpointSelect = $('#endpoint-filter');
+
+// Read-only view of a frozen snapshot, served at /snapshot/{name}
+const snapshotName = location.pathname.startsWith('/snapshot/')
+  ? decodeURIComponent(location.pathname.slice('/snapshot/'.length))
+  : null;
 
 // === Init ===
 const curlText = `curl -X POST ${location.origin}/hook \\
@@ -44,6 +54,22 @@ clearBtn.addEventListener('click', async () => {
   updateCounter();
 });
 
+snapshotBtn.addEventListener('click', async () => {
+  const name = prompt('Snapshot name (letters, digits, - or _)');
+  if (!name) return;
+  const res = await fetch('/api/snapshots', {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ name }),
+  });
+  if (res.ok) {
+    window.open(`/snapshot/${encodeURIComponent(name)}`, '_blank');
+  } else {
+    const err = await res.json().catch(() => ({}));
+    alert(err.error || `Failed to create snapshot (${res.status})`);
+  }
+});
+
 // === Infinite scroll ===
 const scrollObserver = new IntersectionObserver((entries) => {
   if (entries[0].isIntersecting && !loading && loadedCount < totalCount) {
@@ -57,13 +83,45 @@ function loadMore() {
   const oldest = requests[requests.length - 1];
   if (!oldest) return;
   loading = true;
-  ws?.send(JSON.stringify({ type: 'load_more', before: oldest.timestamp }));
+  ws?.send(JSON.stringify({
+    type: 'load_more',
+    before: oldest.timestamp,
+    before_ns: oldest.received_ns,
+    before_id: oldest.id,
+  }));
+}
+
+// === Endpoint filter ===
+function noteEndpoint(endpoint) {
+  if (!endpoint || [...endpointSelect.options].some(o => o.value === endpoint)) return;
+  const option = document.createElement('option');
+  option.value = endpoint;
+  option.textContent = `/hook/${endpoint}`;
+  endpointSelect.appendChild(option);
 }
 
+endpointSelect.addEventListener('change', () => {
+  endpointFilter = endpointSelect.value;
+  requests = [];
+  totalCount = 0;
+  loadedCount = 0;
+  selectedId = null;
+  renderList();
+  showPlaceholder();
+  updateCounter();
+  // Reconnect right away with the new filter instead of through the backoff in onclose
+  if (ws) {
+    ws.onclose = null;
+    ws.close();
+  }
+  connect();
+});
+
 // === WebSocket ===
 function connect() {
   const proto = location.protocol === 'https:' ? 'wss:' : 'ws:';
-  ws = new WebSocket(`${proto}//${location.host}/ws`);
+  const query = endpointFilter ? `&endpoint=${encodeURIComponent(endpointFilter)}` : '';
+  ws = new WebSocket(`${proto}//${location.host}/ws?hexdump=1${query}`);
 
   ws.onopen = () => {
     reconnectDelay = 1000;
@@ -76,6 +134,17 @@ function connect() {
       handleHistory(msg);
     } else if (msg.type === 'new') {
       handleNew(msg.request);
+    } else if (msg.type === 'update') {
+      handleUpdate(msg.request);
+    } else if (msg.type === 'pruned') {
+      handlePruned(msg);
+    } else if (msg.type === 'hexdump') {
+      hexdumps.set(msg.id, msg);
+      if (msg.id === selectedId) fillHexdump(msg);
+    } else if (msg.type === 'shutdown') {
+      setStatus('reconnecting');
+    } else if (msg.type === 'storage') {
+      setStatus(msg.storage.degraded ? 'degraded' : 'connected');
     }
   };
 
@@ -99,12 +168,30 @@ function setStatus(state) {
   } else if (state === 'reconnecting') {
     dot.className = 'w-2 h-2 rounded-full bg-yellow-400';
     text.textContent = 'Reconnecting...';
+  } else if (state === 'degraded') {
+    dot.className = 'w-2 h-2 rounded-full bg-orange-400';
+    text.textContent = 'Storage degraded: captures held in memory';
+  } else if (state === 'snapshot') {
+    dot.className = 'w-2 h-2 rounded-full bg-blue-400';
+    text.textContent = `Snapshot: ${snapshotName}`;
   } else {
     dot.className = 'w-2 h-2 rounded-full bg-red-400';
     text.textContent = 'Disconnected';
   }
 }
 
+function handlePruned(msg) {
+  requests = requests.filter(r => r.pinned || r.timestamp >= msg.before);
+  totalCount = Math.max(0, totalCount - msg.deleted);
+  loadedCount = requests.length;
+  if (selectedId && !requests.some(r => r.id === selectedId)) {
+    selectedId = null;
+    showPlaceholder();
+  }
+  renderList();
+  updateCounter();
+}
+
 function handleHistory(msg) {
   const isInitial = requests.length === 0;
   totalCount = msg.total;
@@ -113,6 +200,7 @@ function handleHistory(msg) {
   const existingIds = new Set(requests.map(r => r.id));
   const newReqs = msg.requests.filter(r => !existingIds.has(r.id));
   requests.push(...newReqs);
+  newReqs.forEach(r => noteEndpoint(r.endpoint));
   loadedCount = requests.length;
 
   if (isInitial) {
@@ -128,12 +216,22 @@ function handleNew(req) {
   // Deduplicate
   if (requests.some(r => r.id === req.id)) return;
   requests.unshift(req);
+  noteEndpoint(req.endpoint);
   totalCount++;
   loadedCount++;
   prependListItem(req);
   updateCounter();
 }
 
+function handleUpdate(req) {
+  const idx = requests.findIndex(r => r.id === req.id);
+  if (idx === -1) return;
+  requests[idx] = req;
+  const el = requestList.querySelector(`[data-id="${req.id}"]`);
+  if (el) el.replaceWith(createListItem(req));
+  if (selectedId === req.id) renderDetail();
+}
+
 function updateCounter() {
   counter.textContent = `${loadedCount} / ${totalCount}`;
 }
@@ -160,14 +258,24 @@ function createListItem(req) {
   const time = new Date(req.timestamp);
   const ts = time.toTimeString().slice(0, 8);
   const shortId = req.id.slice(0, 8);
+  const tags = JSON.parse(req.tags || '[]');
+  const tagBadges = tags.map(t => `<span class="px-1 rounded bg-slate-800 text-slate-400">${escapeHtml(t)}</span>`).join(' ');
 
   div.innerHTML = `
     <div class="flex items-center gap-2">
       <span class="px-1.5 py-0.5 text-xs font-bold rounded ${methodColor}">${req.method}</span>
+      ${req.pinned ? '<i class="bx bxs-pin text-xs text-amber-400"></i>' : ''}
+      ${req.endpoint ? `<span class="px-1 text-xs rounded bg-teal-900/60 text-teal-300">${escapeHtml(req.endpoint)}</span>` : ''}
+      ${req.label ? `<span class="px-1 text-xs rounded bg-indigo-900/60 text-indigo-300">${escapeHtml(req.label)}</span>` : ''}
+      ${req.repeat_count ? `<span class="px-1 text-xs rounded bg-slate-800 text-slate-300" title="Repeated ${req.repeat_count} more time(s)">×${req.repeat_count + 1}</span>` : ''}
+      ${req.duplicate_of ? `<span class="px-1 text-xs rounded bg-amber-900/60 text-amber-300" title="Duplicate of ${req.duplicate_of}">DUP</span>` : ''}
+      ${req.replay_of ? `<span class="px-1 text-xs rounded bg-cyan-900/60 text-cyan-300" title="Replay of ${req.replay_of}">REPLAY</span>` : ''}
+      ${req.websocket_attempt ? '<span class="px-1 text-xs rounded bg-red-900/60 text-red-300">WS</span>' : ''}
       <span class="text-xs text-slate-500 ml-auto">${ts}</span>
     </div>
     <div class="mt-1 text-xs text-slate-400 truncate">${escapeHtml(req.url)}</div>
-    <div class="mt-0.5 text-xs text-slate-600">${shortId}</div>
+    ${req.preview ? `<div class="mt-0.5 text-xs text-slate-500 truncate font-mono">${escapeHtml(req.preview)}</div>` : ''}
+    <div class="mt-0.5 text-xs text-slate-600">${shortId} ${tagBadges}</div>
   `;
 
   div.addEventListener('click', () => selectRequest(req.id));
@@ -196,6 +304,36 @@ function selectRequest(id) {
   renderDetail();
 }
 
+// === Hexdump ===
+async function loadHexdump(id) {
+  if (hexdumps.has(id)) {
+    fillHexdump(hexdumps.get(id));
+    return;
+  }
+  const res = await fetch(`/api/requests/${id}/hexdump`);
+  if (res.ok && id === selectedId) fillHexdump({ dump: await res.text() });
+}
+
+function fillHexdump(msg) {
+  const hexDiv = $('#body-hex');
+  if (!hexDiv) return;
+  const more = msg.total > msg.shown ? `\n… first ${formatSize(msg.shown)} of ${formatSize(msg.total)}` : '';
+  hexDiv.textContent = msg.dump + more;
+}
+
+// Lists only reference bodies spilled to files; the detail view fetches the whole capture
+const spilledLoads = new Set();
+async function loadSpilledBody(id) {
+  if (spilledLoads.has(id)) return;
+  spilledLoads.add(id);
+  try {
+    const res = await fetch(`/api/requests/${id}`);
+    if (res.ok) handleUpdate(unwrapEnvelope(await res.json()));
+  } finally {
+    spilledLoads.delete(id);
+  }
+}
+
 // === Detail view ===
 function showPlaceholder() {
   placeholder.classList.remove('hidden');
@@ -208,6 +346,7 @@ function renderDetail() {
 
   placeholder.classList.add('hidden');
   detailPanel.classList.remove('hidden');
+  if (req.spilled && !req.body) loadSpilledBody(req.id);
 
   const time = new Date(req.timestamp);
   const fullTime = `${time.getFullYear()}-${pad(time.getMonth()+1)}-${pad(time.getDate())} ${pad(time.getHours())}:${pad(time.getMinutes())}:${pad(time.getSeconds())}`;
@@ -222,18 +361,29 @@ function renderDetail() {
 
   const headers = JSON.parse(req.headers);
   const contentType = (headers.find(([k]) => k.toLowerCase() === 'content-type') || [])[1] || '';
+  const hasBody = req.body_size > 0 && !req.body_dropped && !(req.spilled && !req.body);
+  const chunkSizes = req.chunks ? JSON.parse(req.chunks) : null;
+  const chunkInfo = chunkSizes
+    ? ` <span class="text-slate-600" title="${chunkSizes.join(', ')}">· ${chunkSizes.length} chunk${chunkSizes.length === 1 ? '' : 's'}</span>`
+    : '';
 
   let bodySection = '';
   if (req.body_size === 0) {
     bodySection = `<div class="text-slate-500 italic">Empty</div>`;
+  } else if (req.body_dropped) {
+    bodySection = `<div class="text-slate-500 italic">Body not stored for this path</div>`;
+  } else if (req.spilled && !req.body) {
+    bodySection = `<div class="text-slate-500 italic">Loading body…</div>`;
   } else {
     bodySection = `
       <div class="flex gap-1 mb-3" id="body-tabs">
         <button class="tab-btn px-3 py-1 text-xs rounded" data-tab="parsed">Parsed</button>
         <button class="tab-btn px-3 py-1 text-xs rounded" data-tab="raw">Raw</button>
+        ${snapshotName ? '' : '<button class="tab-btn px-3 py-1 text-xs rounded" data-tab="hex">Hex</button>'}
       </div>
-      <div id="body-parsed">${renderParsedBody(req.body, contentType, headers)}</div>
+      <div id="body-parsed">${req.ndjson ? renderNdjson(req.ndjson) : renderParsedBody(req.body, contentType, headers)}</div>
       <div id="body-raw" class="hidden">${renderRawBody(req.body, headers)}</div>
+      <pre id="body-hex" class="hidden bg-slate-900 rounded px-3 py-2 text-xs overflow-x-auto"></pre>
     `;
   }
 
@@ -244,12 +394,22 @@ function renderDetail() {
         <div class="flex items-center gap-3">
           <span class="px-2 py-1 text-sm font-bold rounded ${methodColor}">${req.method}</span>
           <span class="text-slate-400 text-xs">${fullTime}</span>
+          ${req.response_status != null ? `<span class="text-slate-500 text-xs" title="${escapeHtml(req.response_body || '')}">→ ${req.response_status}${req.duration_ms != null ? ` · ${req.duration_ms} ms` : ''}</span>` : ''}
         </div>
         <div class="flex items-center gap-2">
-          <button id="download-btn" class="px-2.5 py-1.5 text-xs bg-slate-800 hover:bg-slate-700 rounded text-slate-300 transition-colors ${req.body_size === 0 ? 'opacity-50 cursor-not-allowed' : ''}" ${req.body_size === 0 ? 'disabled' : ''}>
+          <button id="download-btn" class="px-2.5 py-1.5 text-xs bg-slate-800 hover:bg-slate-700 rounded text-slate-300 transition-colors ${hasBody ? '' : 'opacity-50 cursor-not-allowed'}" ${hasBody ? '' : 'disabled'}>
             <i class="bx bx-download mr-1"></i>Download
           </button>
-          <button id="delete-btn" class="px-2.5 py-1.5 text-xs bg-red-900/50 hover:bg-red-900 text-red-300 rounded border border-red-800/50 transition-colors">
+          <button id="curl-btn" ${snapshotName ? 'hidden' : ''} class="px-2.5 py-1.5 text-xs bg-slate-800 hover:bg-slate-700 rounded text-slate-300 transition-colors">
+            <i class="bx bx-terminal mr-1"></i>Copy curl
+          </button>
+          <button id="replay-btn" ${snapshotName ? 'hidden' : ''} class="px-2.5 py-1.5 text-xs bg-slate-800 hover:bg-slate-700 rounded text-slate-300 transition-colors">
+            <i class="bx bx-redo mr-1"></i>Replay
+          </button>
+          <button id="pin-btn" ${snapshotName ? 'hidden' : ''} class="px-2.5 py-1.5 text-xs bg-slate-800 hover:bg-slate-700 rounded ${req.pinned ? 'text-amber-300' : 'text-slate-300'} transition-colors">
+            <i class="bx ${req.pinned ? 'bxs-pin' : 'bx-pin'} mr-1"></i>${req.pinned ? 'Unpin' : 'Pin'}
+          </button>
+          <button id="delete-btn" ${snapshotName ? 'hidden' : ''} class="px-2.5 py-1.5 text-xs bg-red-900/50 hover:bg-red-900 text-red-300 rounded border border-red-800/50 transition-colors">
             <i class="bx bx-trash mr-1"></i>Delete
           </button>
         </div>
@@ -257,6 +417,7 @@ function renderDetail() {
 
       <!-- UUID -->
       <div class="text-xs text-slate-500">${req.id}</div>
+      ${req.replay_of ? `<div class="text-xs text-slate-500">Replay of <a href="#" id="replay-of-link" class="text-sky-400 hover:underline">${escapeHtml(req.replay_of)}</a></div>` : ''}
 
       <!-- URL -->
       <div>
@@ -276,7 +437,7 @@ function renderDetail() {
 
       <!-- Body -->
       <div>
-        <div class="text-xs text-slate-500 mb-1">Body <span class="text-slate-600">(${formatSize(req.body_size)})</span></div>
+        <div class="text-xs text-slate-500 mb-1">Body <span class="text-slate-600">(${formatSize(req.body_size)})</span>${chunkInfo}</div>
         ${bodySection}
       </div>
     </div>
@@ -299,8 +460,56 @@ function renderDetail() {
     });
     if (parsedDiv) parsedDiv.classList.toggle('hidden', tab !== 'parsed');
     if (rawDiv) rawDiv.classList.toggle('hidden', tab !== 'raw');
+    const hexDiv = $('#body-hex');
+    if (hexDiv) {
+      hexDiv.classList.toggle('hidden', tab !== 'hex');
+      if (tab === 'hex' && !hexDiv.textContent) loadHexdump(req.id);
+    }
+  }
+
+  // Copy curl button
+  $('#curl-btn')?.addEventListener('click', async () => {
+    const res = await fetch(`/api/requests/${req.id}/curl`);
+    if (!res.ok) return;
+    await navigator.clipboard.writeText(await res.text());
+    const btn = $('#curl-btn');
+    if (btn) btn.lastChild.textContent = 'Copied';
+  });
+
+  // Replay button
+  $('#replay-btn')?.addEventListener('click', async () => {
+    const target = prompt('Replay to URL', localStorage.getItem('replayTarget') || 'http://localhost:8080/hook');
+    if (!target) return;
+    localStorage.setItem('replayTarget', target);
+    const res = await fetch(`/api/requests/${req.id}/replay?record=1`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ target }),
+    });
+    const body = await res.json();
+    const result = unwrapEnvelope(body) || {};
+    const error = body.error || result.error;
+    alert(error
+      ? `Replay failed: ${error}`
+      : `${result.status} in ${result.duration_ms} ms\n\n${result.response_body || ''}`);
+  });
+
+  $('#replay-of-link')?.addEventListener('click', (e) => {
+    e.preventDefault();
+    if (requests.some(r => r.id === req.replay_of)) selectRequest(req.replay_of);
+  });
+
+  // Compressed bodies are decoded server-side for the parsed view
+  const encoding = ((headers.find(([k]) => k.toLowerCase() === 'content-encoding') || [])[1] || '').toLowerCase();
+  if (hasBody && parsedDiv && /gzip|deflate/.test(encoding)) {
+    renderDecodedBody(req.id, contentType, headers, parsedDiv);
   }
 
+  // Pin button
+  $('#pin-btn')?.addEventListener('click', () => {
+    ws?.send(JSON.stringify({ type: 'pin', id: req.id, pinned: !req.pinned }));
+  });
+
   // Delete button
   $('#delete-btn')?.addEventListener('click', async () => {
     await fetch(`/api/requests/${req.id}`, { method: 'DELETE' });
@@ -314,7 +523,7 @@ function renderDetail() {
   });
 
   // Download button
-  if (req.body_size > 0) {
+  if (hasBody) {
     $('#download-btn')?.addEventListener('click', () => downloadBody(req));
   }
 }
@@ -333,6 +542,24 @@ function renderRawBody(bodyB64, headers) {
 }
 
 // === Parsed body ===
+async function renderDecodedBody(id, contentType, headers, target) {
+  const res = await fetch(`/api/requests/${id}/body?decode=1`);
+  if (!res.ok) return;
+  const decoded = unwrapEnvelope(await res.json());
+  if (selectedId !== id) return;
+  const warning = decoded.warning
+    ? `<div class="mb-2 text-xs text-amber-400">${escapeHtml(decoded.warning)}</div>`
+    : '';
+  let content;
+  if (decoded.format === 'base64') {
+    content = renderParsedBody(decoded.body, contentType, headers);
+  } else {
+    const text = decoded.format === 'form' ? JSON.stringify(decoded.body, null, 2) : decoded.body;
+    content = `<div class="bg-slate-900 rounded p-3"><pre class="text-xs text-slate-300 whitespace-pre-wrap">${escapeHtml(text)}</pre></div>`;
+  }
+  target.innerHTML = warning + content;
+}
+
 function renderParsedBody(bodyB64, contentType, headers) {
   const ct = contentType.toLowerCase();
 
@@ -376,6 +603,18 @@ function renderAsText(bodyB64) {
   }
 }
 
+function renderNdjson(recordsJson) {
+  const records = JSON.parse(recordsJson);
+  const items = records.map((record, i) => `
+    <div class="border-b border-slate-800/50 py-1.5">
+      <div class="text-slate-500 mb-0.5">#${i + 1}</div>
+      ${jsonTree(record, records.length === 1)}
+    </div>`).join('');
+  return `<div class="bg-slate-900 rounded p-3 text-xs">
+    <div class="text-slate-500 mb-1">${records.length} record${records.length === 1 ? '' : 's'}</div>${items}
+  </div>`;
+}
+
 function renderJson(bodyB64) {
   try {
     const text = atob(bodyB64);
@@ -561,5 +800,28 @@ function base64ToBytes(b64) {
   return bytes;
 }
 
+// === Snapshot ===
+async function loadSnapshot() {
+  clearBtn.classList.add('hidden');
+  snapshotBtn.classList.add('hidden');
+  endpointSelect.classList.add('hidden');
+  const res = await fetch(`/api/snapshots/${encodeURIComponent(snapshotName)}`);
+  if (!res.ok) {
+    setStatus('disconnected');
+    return;
+  }
+  setStatus('snapshot');
+  handleHistory(unwrapEnvelope(await res.json()));
+}
+
+// Accepts both raw and API_ENVELOPE-wrapped responses
+function unwrapEnvelope(body) {
+  return body && typeof body === 'object' && 'data' in body && 'error' in body ? body.data : body;
+}
+
 // === Start ===
-connect();
+if (snapshotName) {
+  loadSnapshot();
+} else {
+  connect();
+}
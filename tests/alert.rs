@@ -0,0 +1,28 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, post_json, wait_for};
+
+#[test]
+fn crossing_the_count_threshold_posts_one_alert() {
+    let receiver = Server::start(&[]);
+    let server = Server::start(&[
+        ("ALERT_URL", &receiver.url("/hook/alerts")),
+        ("ALERT_COUNT_THRESHOLD", "2"),
+        ("ALERT_CHECK_SECONDS", "1"),
+    ]);
+    post_json(&server, "/hook/a", "{}");
+    post_json(&server, "/hook/b", "{}");
+
+    wait_for("the alert", || !receiver.captures().is_empty());
+    let alert = &receiver.captures()[0];
+    let payload: serde_json::Value = serde_json::from_slice(&BASE64.decode(alert["body"].as_str().unwrap()).unwrap()).unwrap();
+    assert_eq!(payload["type"], "count_threshold");
+    assert_eq!(payload["threshold"], 2);
+
+    // Still above the threshold on the next checks, which don't alert again
+    post_json(&server, "/hook/c", "{}");
+    std::thread::sleep(std::time::Duration::from_millis(2200));
+    assert_eq!(receiver.captures().len(), 1);
+}
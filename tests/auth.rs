@@ -0,0 +1,45 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, get, post_json, request};
+
+const UPGRADE: [(&str, &str); 4] = [
+    ("Connection", "Upgrade"),
+    ("Upgrade", "websocket"),
+    ("Sec-WebSocket-Version", "13"),
+    ("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="),
+];
+
+#[test]
+fn everything_but_the_hook_needs_the_token() {
+    let server = Server::start(&[("AUTH_TOKEN", "s3cret")]);
+    assert_eq!(post_json(&server, "/hook/open", "{}").0, 200);
+
+    for path in ["/", "/api/requests", "/app.js"] {
+        assert_eq!(get(&server, path).0, 401, "{path}");
+    }
+    let (status, head, _) = request(&server, "GET", "/api/requests", &[("Authorization", "Bearer wrong")], b"");
+    assert_eq!(status, 401);
+    assert!(head.contains("Basic realm=\"http_logger\""), "{head}");
+
+    let bearer = ("Authorization", "Bearer s3cret");
+    let (status, _, body) = request(&server, "GET", "/api/requests", &[bearer], b"");
+    assert_eq!(status, 200);
+    assert!(body.contains("/hook/open"), "{body}");
+    let basic = format!("Basic {}", BASE64.encode("anyone:s3cret"));
+    assert_eq!(request(&server, "GET", "/", &[("Authorization", &basic)], b"").0, 200);
+
+    // The WebSocket upgrade carries the credential too
+    assert_eq!(request(&server, "GET", "/ws", &UPGRADE, b"").0, 401);
+    let mut authorized = UPGRADE.to_vec();
+    authorized.push(bearer);
+    let mut stream = server.connect();
+    let mut head = "GET /ws HTTP/1.1\r\nHost: localhost\r\n".to_string();
+    for (name, value) in authorized {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    std::io::Write::write_all(&mut stream, head.as_bytes()).unwrap();
+    assert_eq!(common::read_head(&mut stream).0, 101);
+}
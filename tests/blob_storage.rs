@@ -0,0 +1,59 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, get, read_head};
+use std::io::Write;
+
+const ENV: &[(&str, &str)] = &[("BODY_STORAGE", "blob"), ("BODY_INLINE_LIMIT", "1024")];
+
+fn staged_bodies(server: &Server) -> i64 {
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    db.query_row("SELECT COUNT(*) FROM body_blobs", [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn body_with_content_length_is_streamed_into_its_blob() {
+    let server = Server::start(ENV);
+    let body: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+    let mut stream = server.connect();
+    write!(
+        stream,
+        "POST /hook/upload HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    )
+    .unwrap();
+    for frame in body.chunks(7_000) {
+        stream.write_all(frame).unwrap();
+    }
+    assert_eq!(read_head(&mut stream).0, 200);
+
+    let captures = server.captures();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0]["spilled"], true);
+    assert_eq!(captures[0]["body"], "", "lists don't carry the body");
+    assert_eq!(captures[0]["body_size"], body.len());
+
+    let id = captures[0]["id"].as_str().unwrap();
+    let (_, one) = get(&server, &format!("/api/requests/{id}"));
+    let one: serde_json::Value = serde_json::from_str(&one).unwrap();
+    assert_eq!(BASE64.decode(one["body"].as_str().unwrap()).unwrap(), body);
+    assert_eq!(staged_bodies(&server), 1);
+}
+
+#[test]
+fn upload_cut_off_midway_leaves_no_body_behind() {
+    let server = Server::start(ENV);
+
+    let mut stream = server.connect();
+    stream
+        .write_all(b"POST /hook/upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 100000\r\n\r\n")
+        .unwrap();
+    stream.write_all(&[b'a'; 40_000]).unwrap();
+    drop(stream);
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    assert!(server.captures().is_empty());
+    assert_eq!(staged_bodies(&server), 0);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::io::Write;
+
+use common::{Server, get, request};
+
+fn decoded(server: &Server, url: &str) -> serde_json::Value {
+    let id = server.captures().iter().find(|r| r["url"] == url).unwrap()["id"].as_str().unwrap().to_string();
+    let (status, body) = get(server, &format!("/api/requests/{id}/body?decode=1"));
+    assert_eq!(status, 200, "{body}");
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn bodies_are_served_raw_or_decoded() {
+    let server = Server::start(&[]);
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(br#"{"event":"paid"}"#).unwrap();
+    let gzipped = gzip.finish().unwrap();
+    let json_gzip = [("Content-Type", "application/json"), ("Content-Encoding", "gzip")];
+    request(&server, "POST", "/hook/gzip", &json_gzip, &gzipped);
+    request(&server, "POST", "/hook/corrupt", &json_gzip, b"not gzip at all");
+    request(&server, "POST", "/hook/form", &[("Content-Type", "application/x-www-form-urlencoded")], b"a=1&b=2&a=3");
+    request(&server, "POST", "/hook/text", &[("Content-Type", "text/plain; charset=utf-8")], b"hello");
+
+    let gzip = decoded(&server, "/hook/gzip");
+    assert_eq!((gzip["decompressed"].clone(), gzip["format"].clone()), (true.into(), "json".into()));
+    assert_eq!(gzip["body"], "{\n  \"event\": \"paid\"\n}");
+    let corrupt = decoded(&server, "/hook/corrupt");
+    assert_eq!(corrupt["decompressed"], false);
+    assert!(corrupt["warning"].as_str().unwrap().starts_with("could not decode gzip body"), "{corrupt}");
+    assert_eq!(corrupt["body"], "not gzip at all");
+    assert_eq!(decoded(&server, "/hook/form")["body"], serde_json::json!({"a": ["1", "3"], "b": "2"}));
+
+    let text_id = server.captures().iter().find(|r| r["url"] == "/hook/text").unwrap()["id"].as_str().unwrap().to_string();
+    let (status, head, body) = request(&server, "GET", &format!("/api/requests/{text_id}/body"), &[], b"");
+    assert_eq!((status, body.as_str()), (200, "hello"));
+    assert!(head.to_ascii_lowercase().contains("content-type: text/plain; charset=utf-8"), "{head}");
+    assert_eq!(get(&server, "/api/requests/missing/body").0, 404);
+}
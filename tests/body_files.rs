@@ -0,0 +1,38 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, config_dir, get, post_json, request};
+
+#[test]
+fn large_bodies_live_in_files_that_go_with_their_requests() {
+    let bodies = config_dir("body_files");
+    let server = Server::start(&[("BODY_INLINE_LIMIT", "16"), ("BODIES_DIR", bodies.to_str().unwrap()), ("MAX_REQUESTS", "2")]);
+    let big = format!(r#"{{"pad":"{}"}}"#, "x".repeat(100));
+    post_json(&server, "/hook/big", &big);
+    post_json(&server, "/hook/small", "{}");
+
+    let captures = server.captures();
+    let listed = captures.iter().find(|r| r["url"] == "/hook/big").unwrap();
+    let id = listed["id"].as_str().unwrap().to_string();
+    assert_eq!((listed["spilled"].clone(), listed["body"].clone()), (true.into(), "".into()));
+    assert_eq!(std::fs::read(bodies.join(&id)).unwrap(), big.as_bytes());
+    let small = captures.iter().find(|r| r["url"] == "/hook/small").unwrap();
+    assert_eq!(small["spilled"], false);
+    assert_eq!(std::fs::read_dir(&bodies).unwrap().count(), 1);
+
+    let (_, one) = get(&server, &format!("/api/requests/{id}"));
+    let one: serde_json::Value = serde_json::from_str(&one).unwrap();
+    assert_eq!(BASE64.decode(one["body"].as_str().unwrap()).unwrap(), big.as_bytes());
+
+    assert_eq!(request(&server, "DELETE", &format!("/api/requests/{id}"), &[], b"").0, 200);
+    assert!(!bodies.join(&id).exists());
+
+    // Evicted by MAX_REQUESTS
+    post_json(&server, "/hook/big-again", &big);
+    let evicted = server.captures().iter().find(|r| r["url"] == "/hook/big-again").unwrap()["id"].as_str().unwrap().to_string();
+    assert!(bodies.join(&evicted).exists());
+    post_json(&server, "/hook/a", "{}");
+    post_json(&server, "/hook/b", "{}");
+    assert!(!bodies.join(&evicted).exists());
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use std::io::{Read, Write};
+
+use common::{Server, read_head, request};
+
+#[test]
+fn the_longest_matching_prefix_sets_the_body_cap() {
+    let server = Server::start(&[("BODY_LIMITS", "/hook/events=8, /hook/events/big=64")]);
+    let post = |path: &str, len: usize| request(&server, "POST", path, &[], &vec![b'x'; len]).0;
+    assert_eq!(post("/hook/events/a", 8), 200);
+    assert_eq!(post("/hook/events/a", 9), 413);
+    assert_eq!(post("/hook/events/big/b", 64), 200);
+    assert_eq!(post("/hook/events/big/b", 65), 413);
+    assert_eq!(post("/hook/other", 1000), 200);
+
+    // A chunked body has no Content-Length to check up front
+    let mut stream = server.connect();
+    write!(stream, "POST /hook/events/c HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n").unwrap();
+    stream.write_all(b"6\r\nabcdef\r\n6\r\nghijkl\r\n0\r\n\r\n").unwrap();
+    assert_eq!(read_head(&mut stream).0, 413);
+    stream.read_to_string(&mut String::new()).ok();
+
+    let mut sizes: Vec<_> = server.captures().iter().map(|r| r["body_size"].as_i64().unwrap()).collect();
+    sizes.sort();
+    assert_eq!(sizes, [8, 64, 1000]);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{Server, get, request};
+
+fn matching(server: &Server, needle: &str) -> Vec<String> {
+    let (status, body) = get(server, &format!("/api/requests?body_contains={needle}"));
+    assert_eq!(status, 200, "{body}");
+    let found: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let mut urls: Vec<String> = found["requests"].as_array().unwrap().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    urls.sort();
+    urls
+}
+
+#[test]
+fn only_small_text_bodies_are_searchable() {
+    let server = Server::start(&[("BODY_TEXT_MAX_BYTES", "64")]);
+    let post = |path: &str, content_type: &str, body: &[u8]| {
+        request(&server, "POST", path, &[("Content-Type", content_type)], body);
+    };
+    post("/hook/json", "application/json", br#"{"kind":"refund","note":"50% off"}"#);
+    post("/hook/text", "text/plain", b"refund requested");
+    post("/hook/big", "application/json", format!(r#"{{"kind":"refund","pad":"{}"}}"#, "x".repeat(64)).as_bytes());
+    post("/hook/binary", "application/octet-stream", b"refund");
+    post("/hook/other", "text/plain", b"500 off");
+
+    assert_eq!(matching(&server, "refund"), ["/hook/json", "/hook/text"]);
+    // `%` and `_` are literal, not LIKE wildcards
+    assert_eq!(matching(&server, "50%25%20off"), ["/hook/json"]);
+    assert_eq!(matching(&server, "5_0"), Vec::<String>::new());
+
+    let captures = server.captures();
+    let text_of = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap()["body_text"].clone();
+    assert_eq!(text_of("/hook/text"), "refund requested");
+    assert!(text_of("/hook/big").is_null());
+}
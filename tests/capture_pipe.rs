@@ -0,0 +1,26 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, config_file, post_json, wait_for};
+
+#[test]
+fn every_capture_is_appended_as_one_ndjson_line_with_its_whole_body() {
+    let pipe = config_file("capture_pipe.ndjson", "");
+    let server = Server::start(&[("CAPTURE_PIPE", pipe.to_str().unwrap()), ("BODY_INLINE_LIMIT", "16")]);
+    let big = format!("{{\"padding\":\"{}\"}}", "b".repeat(100));
+    post_json(&server, "/hook/small", "{}");
+    post_json(&server, "/hook/big", &big);
+
+    let lines = || std::fs::read_to_string(&pipe).unwrap().lines().map(str::to_string).collect::<Vec<_>>();
+    wait_for("both pipe lines", || lines().len() == 2);
+    let lines: Vec<serde_json::Value> = lines().iter().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines[0]["url"], "/hook/small");
+    assert_eq!(lines[1]["url"], "/hook/big");
+    // The API leaves a spilled body out; the pipe carries it
+    assert_eq!(lines[1]["spilled"], true);
+    assert_eq!(BASE64.decode(lines[1]["body"].as_str().unwrap()).unwrap(), big.as_bytes());
+    let listed = &server.captures()[0];
+    assert_eq!(lines[1]["id"], listed["id"]);
+    assert_eq!(listed["body"], "");
+}
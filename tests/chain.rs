@@ -0,0 +1,25 @@
+mod common;
+
+use common::{Server, free_port, post_json};
+use std::time::{Duration, Instant};
+
+#[test]
+fn chain_target_pointing_back_at_the_hook_does_not_loop() {
+    let port = free_port();
+    let target = format!("http://127.0.0.1:{port}/hook/chained");
+    let server = Server::start_on(port, &[("CHAIN_TARGET", &target)]);
+    assert_eq!(post_json(&server, "/hook/original", "{}").0, 200);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.captures().len() < 2 {
+        assert!(Instant::now() < deadline, "the chained replay never arrived");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    // Long enough for a loop to have gone round several more times
+    std::thread::sleep(Duration::from_millis(500));
+    let captures = server.captures();
+    assert_eq!(captures.len(), 2, "the chained capture was chained again");
+    let chained = captures.iter().find(|r| r["url"] == "/hook/chained").unwrap();
+    assert!(chained["headers"].as_str().unwrap().contains("x-http-logger-chain"), "{chained}");
+    assert!(chained["chain_result"].is_null());
+}
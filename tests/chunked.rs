@@ -0,0 +1,32 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, post_json, read_head};
+
+#[test]
+fn chunked_uploads_record_each_chunk_size() {
+    let server = Server::start(&[]);
+    let mut stream = server.connect();
+    write!(stream, "POST /hook/stream HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n").unwrap();
+    for chunk in ["hello", "big", " world!"] {
+        write!(stream, "{:x}\r\n{chunk}\r\n", chunk.len()).unwrap();
+        stream.flush().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    stream.write_all(b"0\r\n\r\n").unwrap();
+    assert_eq!(read_head(&mut stream).0, 200);
+    stream.read_to_string(&mut String::new()).ok();
+    post_json(&server, "/hook/sized", "{}");
+
+    let captures = server.captures();
+    let streamed = captures.iter().find(|r| r["url"] == "/hook/stream").unwrap();
+    let chunks: Vec<u64> = serde_json::from_str(streamed["chunks"].as_str().unwrap()).unwrap();
+    assert_eq!(chunks, [5, 3, 7]);
+    assert_eq!(BASE64.decode(streamed["body"].as_str().unwrap()).unwrap(), b"hellobig world!");
+    let sized = captures.iter().find(|r| r["url"] == "/hook/sized").unwrap();
+    assert!(sized["chunks"].is_null(), "{sized}");
+}
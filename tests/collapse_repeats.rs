@@ -0,0 +1,40 @@
+mod common;
+
+use common::{Server, post_json, read_message, request, websocket};
+
+#[test]
+fn repeats_within_the_window_bump_the_stored_row() {
+    let server = Server::start(&[("COLLAPSE_REPEATS_MS", "5000"), ("TRUST_FORWARDED_FOR", "1")]);
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+
+    for _ in 0..3 {
+        post_json(&server, "/hook/poll", "{\"n\":1}");
+    }
+    let first = read_message(&mut ws);
+    assert_eq!(first["type"], "new");
+    for count in [1, 2] {
+        let update = read_message(&mut ws);
+        assert_eq!(update["type"], "update");
+        assert_eq!(update["request"]["id"], first["request"]["id"]);
+        assert_eq!(update["request"]["repeat_count"], count);
+    }
+
+    // A different body, or the same request from another sender, is stored
+    post_json(&server, "/hook/poll", "{\"n\":2}");
+    request(&server, "POST", "/hook/poll", &[("X-Forwarded-For", "203.0.113.9"), ("Content-Type", "application/json")], b"{\"n\":2}");
+    // The same as two requests ago, but no longer this sender's latest
+    post_json(&server, "/hook/poll", "{\"n\":1}");
+
+    let counts: Vec<i64> = server.captures().iter().map(|r| r["repeat_count"].as_i64().unwrap()).collect();
+    assert_eq!(counts, [0, 0, 0, 2]);
+}
+
+#[test]
+fn repeats_outside_the_window_are_stored() {
+    let server = Server::start(&[("COLLAPSE_REPEATS_MS", "200")]);
+    post_json(&server, "/hook/poll", "{}");
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    post_json(&server, "/hook/poll", "{}");
+    assert_eq!(server.captures().len(), 2);
+}
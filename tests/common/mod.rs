@@ -0,0 +1,240 @@
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The logger binary running on a free port with its own database, killed on drop.
+pub struct Server {
+    pub port: u16,
+    pub dir: PathBuf,
+    child: Child,
+}
+
+/// A port nothing is listening on, for a server that has to know its own address up front.
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+impl Server {
+    pub fn start(env: &[(&str, &str)]) -> Self {
+        Self::start_on(free_port(), env)
+    }
+
+    pub fn start_on(port: u16, env: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("neo_http_logger_test_{}_{port}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_neo_http_logger"))
+            .current_dir(&dir)
+            .env("PORT", port.to_string())
+            .env("DB_PATH", dir.join("test.db"))
+            .envs(env.iter().copied())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Self { port, dir, child };
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(server.addr()).is_err() {
+            assert!(Instant::now() < deadline, "server did not start on port {port}");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        server
+    }
+
+    pub fn addr(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr())
+    }
+
+    pub fn connect(&self) -> TcpStream {
+        let stream = TcpStream::connect(self.addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream
+    }
+
+    /// Sends SIGTERM, as a supervisor stopping the logger would, and waits for it to exit.
+    pub fn terminate(&mut self) -> std::process::ExitStatus {
+        let pid = self.child.id().to_string();
+        assert!(Command::new("kill").args(["-TERM", &pid]).status().unwrap().success());
+        self.child.wait().unwrap()
+    }
+
+    /// Every stored capture, newest first, as `GET /api/requests` returns them.
+    pub fn captures(&self) -> Vec<serde_json::Value> {
+        let (_, body) = get(self, "/api/requests");
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        value["requests"].as_array().cloned().unwrap_or_default()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Reads one response head (status line and headers) off `stream`; returns the status code.
+pub fn read_head(stream: &mut TcpStream) -> (u16, String) {
+    let mut head = Vec::new();
+    let mut byte = [0u8];
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut byte) {
+            Ok(1) => head.push(byte[0]),
+            _ => panic!("connection ended inside a response head: {:?}", String::from_utf8_lossy(&head)),
+        }
+    }
+    let head = String::from_utf8(head).unwrap();
+    let status = head.split(' ').nth(1).and_then(|s| s.parse().ok()).unwrap();
+    (status, head)
+}
+
+/// A plain `GET` on its own connection; returns the status and body.
+pub fn get(server: &Server, path: &str) -> (u16, String) {
+    let mut stream = server.connect();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    let (status, _) = read_head(&mut stream);
+    let mut body = String::new();
+    stream.read_to_string(&mut body).ok();
+    (status, body)
+}
+
+/// A JSON `POST` on its own connection; returns the status and body.
+pub fn post_json(server: &Server, path: &str, json: &str) -> (u16, String) {
+    let mut stream = server.connect();
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{json}",
+        json.len()
+    )
+    .unwrap();
+    let (status, _) = read_head(&mut stream);
+    let mut body = String::new();
+    stream.read_to_string(&mut body).ok();
+    (status, body)
+}
+
+/// Opens a WebSocket on `path`; read what the server sends with `read_message`.
+pub fn websocket(server: &Server, path: &str) -> TcpStream {
+    let mut stream = server.connect();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+         Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+    )
+    .unwrap();
+    let (status, head) = read_head(&mut stream);
+    assert_eq!(status, 101, "{head}");
+    stream
+}
+
+/// The next frame off a WebSocket from `websocket`, as its opcode and payload.
+fn read_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).unwrap();
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len).unwrap();
+            u16::from_be_bytes(len) as usize
+        }
+        127 => {
+            let mut len = [0u8; 8];
+            stream.read_exact(&mut len).unwrap();
+            u64::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).unwrap();
+    (head[0] & 0x0f, payload)
+}
+
+/// The next text message off a WebSocket from `websocket`, skipping control frames.
+pub fn read_message(stream: &mut TcpStream) -> serde_json::Value {
+    loop {
+        // Servers don't fragment these small messages; 0x1 is a text frame
+        if let (0x1, payload) = read_frame(stream) {
+            return serde_json::from_slice(&payload).unwrap();
+        }
+    }
+}
+
+/// Skips to the server's close frame and returns its code and reason.
+pub fn read_close(stream: &mut TcpStream) -> (u16, String) {
+    loop {
+        if let (0x8, payload) = read_frame(stream) {
+            let code = u16::from_be_bytes([payload[0], payload[1]]);
+            return (code, String::from_utf8(payload[2..].to_vec()).unwrap());
+        }
+    }
+}
+
+/// Any request on its own connection, with `headers` added to `Host` and `Content-Length`;
+/// returns the status, the response head and the body.
+pub fn request(server: &Server, method: &str, path: &str, headers: &[(&str, &str)], body: &[u8]) -> (u16, String, String) {
+    let mut stream = server.connect();
+    let mut head = format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n", body.len());
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).unwrap();
+    stream.write_all(body).unwrap();
+    let (status, head) = read_head(&mut stream);
+    let mut body = String::new();
+    stream.read_to_string(&mut body).ok();
+    (status, head, body)
+}
+
+/// A directory for files a test sets up before starting a server; `name` must be unique per test.
+pub fn config_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neo_http_logger_config_{}", std::process::id())).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a config file (rules, specs) for a server to load; `name` must be unique per test.
+pub fn config_file(name: &str, contents: &str) -> PathBuf {
+    let path = config_dir("").join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Polls `done` until it holds, failing the test with `what` after five seconds.
+pub fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out waiting for {what}");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Sends `text` as one masked text frame, as a client must.
+pub fn send_message(stream: &mut TcpStream, text: &str) {
+    let payload = text.as_bytes();
+    let mut frame = vec![0x81];
+    match payload.len() {
+        len if len < 126 => frame.push(0x80 | len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    let mask = [0x12, 0x34, 0x56, 0x78];
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    stream.write_all(&frame).unwrap();
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::{Server, request};
+
+fn content_encoding(server: &Server) -> Option<String> {
+    let (status, head, _) = request(server, "POST", "/hook/big", &[("Accept-Encoding", "gzip")], b"{}");
+    assert_eq!(status, 200);
+    head.lines()
+        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-encoding: ").map(str::to_string))
+}
+
+#[test]
+fn hook_responses_are_compressed_only_above_the_minimum_size() {
+    let big = format!("{{\"padding\":\"{}\"}}", "a".repeat(2000));
+    let default = Server::start(&[("HOOK_BODY", &big)]);
+    assert_eq!(content_encoding(&default).as_deref(), Some("gzip"));
+
+    let raised = Server::start(&[("HOOK_BODY", &big), ("RESPONSE_COMPRESS_MIN_BYTES", "4096")]);
+    assert_eq!(content_encoding(&raised), None);
+
+    // The `{"ok":1}` ack is under the default minimum
+    let small = Server::start(&[]);
+    assert_eq!(content_encoding(&small), None);
+}
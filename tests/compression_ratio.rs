@@ -0,0 +1,35 @@
+mod common;
+
+use std::io::Write;
+
+use common::{Server, request};
+
+#[test]
+fn compressed_bodies_record_their_inflated_size() {
+    let server = Server::start(&[("MEASURE_COMPRESSION", "1")]);
+    let plain = format!(r#"{{"pad":"{}"}}"#, "z".repeat(10_000));
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(plain.as_bytes()).unwrap();
+    let gzipped = gzip.finish().unwrap();
+    let mut deflate = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    deflate.write_all(plain.as_bytes()).unwrap();
+    let deflated = deflate.finish().unwrap();
+
+    request(&server, "POST", "/hook/gzip", &[("Content-Encoding", "gzip")], &gzipped);
+    request(&server, "POST", "/hook/deflate", &[("Content-Encoding", "deflate")], &deflated);
+    request(&server, "POST", "/hook/corrupt", &[("Content-Encoding", "gzip")], b"not gzip");
+    request(&server, "POST", "/hook/plain", &[], plain.as_bytes());
+
+    let captures = server.captures();
+    let by_url = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap().clone();
+    for (url, sent) in [("/hook/gzip", gzipped.len()), ("/hook/deflate", deflated.len())] {
+        let capture = by_url(url);
+        assert_eq!(capture["body_size"], sent, "{url}");
+        assert_eq!(capture["decoded_size"], plain.len(), "{url}");
+        let ratio = capture["compression_ratio"].as_f64().unwrap();
+        assert!((ratio - plain.len() as f64 / sent as f64).abs() < 1e-9, "{url}: {ratio}");
+    }
+    for url in ["/hook/corrupt", "/hook/plain"] {
+        assert!(by_url(url)["decoded_size"].is_null() && by_url(url)["compression_ratio"].is_null(), "{url}");
+    }
+}
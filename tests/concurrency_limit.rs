@@ -0,0 +1,30 @@
+mod common;
+
+use std::io::{Read, Write};
+
+use common::{Server, get, post_json, read_head, request};
+
+#[test]
+fn hooks_beyond_the_limit_are_shed_while_the_api_keeps_answering() {
+    let server = Server::start(&[("MAX_CONCURRENT_REQUESTS", "1")]);
+
+    // Holds the only slot: the handler is waiting on the rest of the body
+    let mut slow = server.connect();
+    write!(slow, "POST /hook/slow HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\nConnection: close\r\n\r\nhello").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    let (status, head, body) = request(&server, "POST", "/hook/extra", &[], b"{}");
+    assert_eq!(status, 503, "{body}");
+    assert!(head.to_ascii_lowercase().contains("retry-after: 1"), "{head}");
+    assert!(body.contains("too many concurrent requests"), "{body}");
+    assert_eq!(get(&server, "/api/requests").0, 200);
+
+    slow.write_all(b"world").unwrap();
+    let (status, _) = read_head(&mut slow);
+    assert_eq!(status, 200);
+    slow.read_to_string(&mut String::new()).ok();
+
+    assert_eq!(post_json(&server, "/hook/after", "{}").0, 200);
+    let urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(urls, ["/hook/after", "/hook/slow"]);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{Server, post_json, read_message, websocket};
+
+fn fail_inserts(server: &Server) -> rusqlite::Connection {
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    db.execute_batch("CREATE TRIGGER fail_inserts BEFORE INSERT ON requests BEGIN SELECT RAISE(ABORT, 'disk full'); END;")
+        .unwrap();
+    db
+}
+
+#[test]
+fn failed_writes_answer_500_and_are_never_broadcast() {
+    let server = Server::start(&[("CONFIRM_STORAGE", "1")]);
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+
+    let db = fail_inserts(&server);
+    let (status, body) = post_json(&server, "/hook/lost", "{}");
+    assert_eq!(status, 500);
+    assert!(body.contains("failed to store request"), "{body}");
+
+    db.execute_batch("DROP TRIGGER fail_inserts").unwrap();
+    assert_eq!(post_json(&server, "/hook/kept", "{}").0, 200);
+    // The failed capture's broadcast would have come first
+    assert_eq!(read_message(&mut ws)["request"]["url"], "/hook/kept");
+    assert_eq!(server.captures().len(), 1);
+}
+
+#[test]
+fn without_it_the_sender_is_answered_regardless() {
+    let server = Server::start(&[]);
+    let _db = fail_inserts(&server);
+    assert_eq!(post_json(&server, "/hook/lost", "{}").0, 200);
+    assert!(server.captures().is_empty());
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use std::io::{Read, Write};
+
+use common::{Server, post_json, read_head};
+
+#[test]
+fn requests_are_numbered_by_their_position_on_the_connection() {
+    let server = Server::start(&[]);
+    let mut stream = server.connect();
+    for n in 1..=3 {
+        write!(stream, "POST /hook/kept/{n} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{{}}").unwrap();
+        let (status, head) = read_head(&mut stream);
+        assert_eq!(status, 200);
+        let len: usize = head
+            .lines()
+            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length: ").map(|v| v.parse().unwrap()))
+            .unwrap();
+        stream.read_exact(&mut vec![0; len]).unwrap();
+    }
+    post_json(&server, "/hook/fresh", "{}");
+
+    let captures = server.captures();
+    let seq = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap()["connection_seq"].clone();
+    assert_eq!(seq("/hook/kept/1"), 1);
+    assert_eq!(seq("/hook/kept/2"), 2);
+    assert_eq!(seq("/hook/kept/3"), 3);
+    assert_eq!(seq("/hook/fresh"), 1);
+}
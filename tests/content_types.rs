@@ -0,0 +1,21 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn only_listed_content_types_are_stored() {
+    let server = Server::start(&[("CAPTURE_CONTENT_TYPES", "application/json,application/xml")]);
+    for (path, content_type) in [
+        ("/hook/json", "application/json; charset=utf-8"),
+        ("/hook/xml", "Application/XML"),
+        ("/hook/text", "text/plain"),
+        ("/hook/form", "application/x-www-form-urlencoded"),
+    ] {
+        let (status, _, body) = request(&server, "POST", path, &[("Content-Type", content_type)], b"{}");
+        // Skipped requests still get the normal answer
+        assert_eq!((status, body.as_str()), (200, "{\"ok\":1}"), "{path}");
+    }
+    let mut urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    urls.sort();
+    assert_eq!(urls, ["/hook/json", "/hook/xml"]);
+}
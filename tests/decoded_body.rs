@@ -0,0 +1,43 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use common::{Server, request};
+use flate2::{Compression, write::GzEncoder};
+use std::io::Write;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn inflated_bodies_are_stored_next_to_the_received_ones() {
+    let server = Server::start(&[("STORE_DECODED_BODY", "true")]);
+    let plain = br#"{"event":"push","padding":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#;
+    let compressed = gzip(plain);
+    request(&server, "POST", "/hook/gz", &[("Content-Encoding", "gzip")], &compressed);
+    request(&server, "POST", "/hook/bad", &[("Content-Encoding", "gzip")], b"not gzip");
+    request(&server, "POST", "/hook/plain", &[], plain);
+
+    let captures = server.captures();
+    let by_url = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap();
+    let gz = by_url("/hook/gz");
+    assert_eq!(STANDARD.decode(gz["body"].as_str().unwrap()).unwrap(), compressed);
+    assert_eq!(STANDARD.decode(gz["decoded_body"].as_str().unwrap()).unwrap(), plain);
+    assert_eq!(gz["decoded_from"], "gzip");
+
+    // A body that fails to inflate is kept as received, without a decoded copy
+    assert_eq!(by_url("/hook/bad")["decoded_body"], serde_json::Value::Null);
+    assert_eq!(by_url("/hook/plain")["decoded_from"], serde_json::Value::Null);
+}
+
+#[test]
+fn decoded_bodies_are_off_by_default() {
+    let server = Server::start(&[]);
+    request(&server, "POST", "/hook/gz", &[("Content-Encoding", "gzip")], &gzip(b"{}"));
+    let captures = server.captures();
+    assert_eq!(captures[0]["decoded_body"], serde_json::Value::Null);
+    assert_eq!(captures[0]["decoded_from"], serde_json::Value::Null);
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use common::{Server, post_json};
+
+#[test]
+fn bodies_on_drop_body_paths_keep_only_their_size() {
+    let server = Server::start(&[("DROP_BODY_PATHS", "/hook/secret")]);
+    post_json(&server, "/hook/secret/cards", r#"{"pan":"4111111111111111"}"#);
+    post_json(&server, "/hook/public", r#"{"ok":true}"#);
+
+    let captures = server.captures();
+    let dropped = captures.iter().find(|r| r["url"] == "/hook/secret/cards").unwrap();
+    assert_eq!(dropped["body"], "");
+    assert_eq!(dropped["body_dropped"], true);
+    assert_eq!(dropped["body_size"], 26);
+    let kept = captures.iter().find(|r| r["url"] == "/hook/public").unwrap();
+    assert_eq!(kept["body_dropped"], false);
+    assert_ne!(kept["body"], "");
+}
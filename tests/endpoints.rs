@@ -0,0 +1,43 @@
+mod common;
+
+use common::{Server, get, post_json, read_message, send_message, websocket};
+
+fn endpoint_of(server: &Server, url: &str) -> serde_json::Value {
+    server.captures().iter().find(|r| r["url"] == url).unwrap()["endpoint"].clone()
+}
+
+#[test]
+fn listed_endpoints_are_recorded_and_filter_every_view() {
+    let server = Server::start(&[("HOOKS", "stripe,github")]);
+    let mut github_only = websocket(&server, "/ws?endpoint=github");
+    assert_eq!(read_message(&mut github_only)["type"], "history");
+    for path in ["/hook/stripe/evt", "/hook/github", "/hook/other/x", "/hook", "/hook/stripe"] {
+        post_json(&server, path, "{}");
+    }
+
+    assert_eq!(endpoint_of(&server, "/hook/stripe/evt"), "stripe");
+    assert_eq!(endpoint_of(&server, "/hook/github"), "github");
+    assert!(endpoint_of(&server, "/hook/other/x").is_null());
+    assert!(endpoint_of(&server, "/hook").is_null());
+
+    let (_, found) = get(&server, "/api/requests?endpoint=stripe");
+    let found: serde_json::Value = serde_json::from_str(&found).unwrap();
+    assert_eq!(found["total"], 2);
+    let (_, stats) = get(&server, "/api/stats?endpoint=github");
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&stats).unwrap()["total"], 1);
+
+    assert_eq!(read_message(&mut github_only)["request"]["url"], "/hook/github");
+    // Nothing else was sent to this socket before the stats it asks for
+    send_message(&mut github_only, r#"{"type":"get_stats"}"#);
+    let next = read_message(&mut github_only);
+    assert_eq!((next["type"].as_str(), next["total"].as_i64()), (Some("stats"), Some(1)));
+}
+
+#[test]
+fn without_hooks_any_first_segment_is_an_endpoint() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/other/x", "{}");
+    post_json(&server, "/hook", "{}");
+    assert_eq!(endpoint_of(&server, "/hook/other/x"), "other");
+    assert!(endpoint_of(&server, "/hook").is_null());
+}
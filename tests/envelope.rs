@@ -0,0 +1,31 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+fn enveloped(server: &Server, path: &str) -> (u16, serde_json::Value) {
+    let (status, body) = get(server, path);
+    (status, serde_json::from_str(&body).unwrap_or_else(|e| panic!("{path}: {e}: {body}")))
+}
+
+#[test]
+fn api_responses_share_one_shape_and_hooks_are_untouched() {
+    let server = Server::start(&[("API_ENVELOPE", "1")]);
+    assert_eq!(post_json(&server, "/hook/a", "{}").1, "{\"ok\":1}");
+    post_json(&server, "/hook/b", "{}");
+
+    let (status, list) = enveloped(&server, "/api/requests");
+    assert_eq!(status, 200);
+    assert_eq!(list["error"], serde_json::Value::Null);
+    assert_eq!(list["total"], 2);
+    assert_eq!(list["data"]["requests"].as_array().unwrap().len(), 2);
+
+    let (status, missing) = enveloped(&server, "/api/requests/recent?n=5");
+    assert_eq!(status, 404);
+    assert_eq!(missing, serde_json::json!({"data": null, "total": null, "error": "fewer than 5 requests stored"}));
+
+    // Extractor rejections are plain text underneath
+    let (status, rejected) = enveloped(&server, "/api/requests/recent?n=lots");
+    assert_eq!(status, 400);
+    assert!(rejected["error"].as_str().unwrap().starts_with("Failed to deserialize query string"), "{rejected}");
+    assert_eq!(rejected["data"], serde_json::Value::Null);
+}
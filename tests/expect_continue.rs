@@ -0,0 +1,59 @@
+mod common;
+
+use common::{Server, read_head};
+use std::io::Write;
+
+const BODY: &str = "hello world";
+
+/// Sends the head of an `Expect: 100-continue` upload and returns the first response head.
+fn start_upload(server: &Server, path: &str) -> (std::net::TcpStream, u16) {
+    let mut stream = server.connect();
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nExpect: 100-continue\r\n\r\n",
+        BODY.len()
+    )
+    .unwrap();
+    let (status, _) = read_head(&mut stream);
+    (stream, status)
+}
+
+#[test]
+fn body_is_sent_after_100_continue_and_stored() {
+    let server = Server::start(&[]);
+
+    let (mut stream, status) = start_upload(&server, "/hook/upload");
+    assert_eq!(status, 100, "the client only sends the body after 100 Continue");
+    stream.write_all(BODY.as_bytes()).unwrap();
+    let (status, _) = read_head(&mut stream);
+    assert_eq!(status, 200);
+
+    let captures = server.captures();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0]["url"], "/hook/upload");
+    assert_eq!(captures[0]["expect_continue"], true);
+    assert_eq!(captures[0]["body_text"], BODY);
+}
+
+#[test]
+fn bad_hook_token_is_rejected_before_the_body_is_requested() {
+    let server = Server::start(&[("HOOK_TOKEN", "secret")]);
+
+    let (_, status) = start_upload(&server, "/hook/t/wrong/upload");
+    assert_eq!(status, 401, "the final answer comes instead of 100 Continue");
+    assert!(server.captures().is_empty());
+}
+
+#[test]
+fn full_quota_is_rejected_before_the_body_is_requested() {
+    let server = Server::start(&[("CAPTURE_QUOTA", "1"), ("QUOTA_POLICY", "reject")]);
+
+    let (mut stream, status) = start_upload(&server, "/hook/first");
+    assert_eq!(status, 100);
+    stream.write_all(BODY.as_bytes()).unwrap();
+    assert_eq!(read_head(&mut stream).0, 200);
+
+    let (_, status) = start_upload(&server, "/hook/second");
+    assert_eq!(status, 507, "the final answer comes instead of 100 Continue");
+    assert_eq!(server.captures().len(), 1);
+}
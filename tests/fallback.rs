@@ -0,0 +1,62 @@
+mod common;
+
+use common::{Server, get, post_json, read_message, wait_for, websocket};
+
+fn health(server: &Server) -> serde_json::Value {
+    serde_json::from_str(&get(server, "/health").1).unwrap()
+}
+
+#[test]
+fn failed_inserts_are_buffered_until_the_database_recovers() {
+    let server = Server::start(&[("FALLBACK_BUFFER", "10"), ("FALLBACK_AFTER_FAILURES", "2")]);
+    let mut live = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut live)["type"], "history");
+    assert_eq!(health(&server)["status"], "ok");
+
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    db.execute_batch("CREATE TRIGGER fail_inserts BEFORE INSERT ON requests BEGIN SELECT RAISE(ABORT, 'disk full'); END;")
+        .unwrap();
+    assert_eq!(post_json(&server, "/hook/first", "{}").0, 200);
+    assert_eq!(read_message(&mut live)["request"]["url"], "/hook/first");
+    assert_eq!(health(&server)["status"], "ok", "one failure is under FALLBACK_AFTER_FAILURES");
+    assert_eq!(post_json(&server, "/hook/second", "{}").0, 200);
+    // The failure that degrades storage is announced before its capture is broadcast
+    let notice = read_message(&mut live);
+    assert_eq!(notice["type"], "storage");
+    assert_eq!(notice["storage"]["degraded"], true);
+    assert_eq!(read_message(&mut live)["request"]["url"], "/hook/second");
+
+    let health_now = health(&server);
+    assert_eq!(health_now["status"], "degraded");
+    assert_eq!(health_now["storage"]["buffered"], 2);
+    assert!(health_now["storage"]["last_error"].as_str().unwrap().contains("disk full"));
+    assert!(server.captures().is_empty());
+
+    // A dashboard opened now gets the buffered captures after the (empty) history
+    let mut late = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut late)["requests"], serde_json::json!([]));
+    let first = read_message(&mut late);
+    assert_eq!(first["request"]["url"], "/hook/first");
+    assert_eq!(read_message(&mut late)["request"]["url"], "/hook/second");
+    assert_eq!(read_message(&mut late)["storage"]["degraded"], true);
+    let id = first["request"]["id"].as_str().unwrap();
+    assert_eq!(get(&server, &format!("/api/requests/{id}")).0, 200);
+
+    db.execute_batch("DROP TRIGGER fail_inserts").unwrap();
+    assert_eq!(post_json(&server, "/hook/third", "{}").0, 200);
+    let mut types: Vec<_> = (0..2).map(|_| read_message(&mut live)).collect();
+    types.sort_by_key(|m| m["type"].as_str().unwrap().to_string());
+    assert_eq!(types[0]["request"]["url"], "/hook/third");
+    assert_eq!(types[1]["type"], "storage");
+    assert_eq!(types[1]["storage"]["degraded"], false);
+
+    wait_for("buffered captures to be written back", || server.captures().len() == 3);
+    assert_eq!(health(&server)["status"], "ok");
+    assert_eq!(health(&server)["storage"]["buffered"], 0);
+}
+
+#[test]
+fn health_has_no_storage_object_without_a_buffer() {
+    let server = Server::start(&[]);
+    assert_eq!(health(&server), serde_json::json!({"status": "ok"}));
+}
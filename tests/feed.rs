@@ -0,0 +1,37 @@
+mod common;
+
+use common::{Server, post_json, request};
+
+fn header(head: &str, name: &str) -> String {
+    head.lines()
+        .find_map(|l| l.split_once(": ").filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.to_string()))
+        .unwrap_or_else(|| panic!("no {name} in {head}"))
+}
+
+#[test]
+fn pollers_get_304_until_something_new_arrives() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/orders?id=1", "{\"n\":1}");
+
+    let (status, head, body) = request(&server, "GET", "/api/feed", &[], b"");
+    assert_eq!(status, 200);
+    let feed: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let item = &feed["items"][0];
+    assert_eq!((item["method"].as_str(), item["path"].as_str(), item["size"].as_i64()), (Some("POST"), Some("/hook/orders"), Some(7)));
+    let etag = header(&head, "etag");
+    let last_modified = header(&head, "last-modified");
+
+    let (status, _, body) = request(&server, "GET", "/api/feed", &[("If-None-Match", &etag)], b"");
+    assert_eq!((status, body.as_str()), (304, ""));
+    assert_eq!(request(&server, "GET", "/api/feed", &[("If-None-Match", &format!("\"other\", {etag}"))], b"").0, 304);
+    assert_eq!(request(&server, "GET", "/api/feed", &[("If-Modified-Since", &last_modified)], b"").0, 304);
+
+    // The tag covers the items, not the query: a larger limit lists the same single item
+    assert_eq!(request(&server, "GET", "/api/feed?limit=2", &[("If-None-Match", &etag)], b"").0, 304);
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    post_json(&server, "/hook/orders?id=2", "{}");
+    let (status, head, _) = request(&server, "GET", "/api/feed", &[("If-None-Match", &etag)], b"");
+    assert_eq!(status, 200);
+    assert_ne!(header(&head, "etag"), etag);
+    assert_eq!(request(&server, "GET", "/api/feed", &[("If-Modified-Since", &last_modified)], b"").0, 200);
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::{Server, request};
+
+const FORM: &[(&str, &str)] = &[("Content-Type", "application/x-www-form-urlencoded")];
+
+#[test]
+fn form_bodies_are_parsed_with_repeated_keys_as_arrays() {
+    let server = Server::start(&[]);
+    let (status, _, _) = request(&server, "POST", "/hook", FORM, b"name=J%C3%BCrgen+M&tag=a&tag=b");
+    assert_eq!(status, 200);
+
+    let req = &server.captures()[0];
+    let form: serde_json::Value = serde_json::from_str(req["form"].as_str().unwrap()).unwrap();
+    assert_eq!(form, serde_json::json!({"name": "Jürgen M", "tag": ["a", "b"]}));
+    assert!(!req["body"].as_str().unwrap().is_empty(), "the raw body is kept too");
+}
+
+#[test]
+fn parse_form_off_leaves_the_body_alone() {
+    let server = Server::start(&[("PARSE_FORM", "false")]);
+    request(&server, "POST", "/hook", FORM, b"a=1");
+    assert!(server.captures()[0]["form"].is_null());
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, free_port, request};
+
+#[test]
+fn captures_are_relayed_and_the_upstream_answer_recorded() {
+    let upstream = Server::start(&[("HOOK_STATUS", "201"), ("HOOK_BODY", "created"), ("HOOK_HEADERS", r#"{"X-Upstream":"yes"}"#)]);
+    let proxy = Server::start(&[("FORWARD_URL", &upstream.url(""))]);
+    let (status, head, body) = request(&proxy, "POST", "/hook/orders?x=1", &[("X-Signature", "abc")], b"{\"n\":1}");
+    assert_eq!((status, body.as_str()), (201, "created"));
+    assert!(head.to_ascii_lowercase().contains("x-upstream: yes"), "{head}");
+
+    let relayed = &upstream.captures()[0];
+    assert_eq!(relayed["url"], "/hook/orders?x=1");
+    assert_eq!(BASE64.decode(relayed["body"].as_str().unwrap()).unwrap(), b"{\"n\":1}");
+    assert!(relayed["headers"].as_str().unwrap().contains("x-signature"), "{relayed}");
+
+    let recorded = &proxy.captures()[0];
+    assert_eq!(recorded["response_status"], 201);
+    assert_eq!(recorded["response_body"], "created");
+    assert!(recorded["forward_error"].is_null());
+}
+
+#[test]
+fn an_unreachable_upstream_is_a_recorded_502() {
+    let proxy = Server::start(&[("FORWARD_URL", &format!("http://127.0.0.1:{}", free_port()))]);
+    assert_eq!(request(&proxy, "POST", "/hook/orders", &[], b"{}").0, 502);
+    let recorded = &proxy.captures()[0];
+    assert_eq!(recorded["response_status"], 502);
+    assert!(recorded["forward_error"].as_str().unwrap().contains("/hook/orders"), "{recorded}");
+}
+
+#[test]
+fn a_slow_upstream_times_out_with_504() {
+    // Accepts the connection and never answers
+    let upstream = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy = Server::start(&[
+        ("FORWARD_URL", &format!("http://{}", upstream.local_addr().unwrap())),
+        ("FORWARD_TIMEOUT_MS", "300"),
+    ]);
+    let started = std::time::Instant::now();
+    let (status, _, body) = request(&proxy, "POST", "/hook/slow", &[], b"{}");
+    assert_eq!(status, 504, "{body}");
+    assert!(started.elapsed() < std::time::Duration::from_secs(3));
+    assert!(body.contains("upstream request failed: timeout"), "{body}");
+    let recorded = &proxy.captures()[0];
+    assert_eq!(recorded["forward_error"], "timeout");
+    assert_eq!(recorded["response_status"], 504);
+}
+
+#[test]
+fn paths_are_rewritten_on_the_way_upstream_only() {
+    let upstream = Server::start(&[]);
+    let proxy = Server::start(&[
+        ("FORWARD_URL", &upstream.url("/hook/up")),
+        ("FORWARD_PATH_REWRITE", "/hook/stripe=/payments,/hook="),
+    ]);
+    for path in ["/hook/stripe/evt?x=1", "/hook/stripes", "/hook/github", "/hook"] {
+        assert_eq!(request(&proxy, "POST", path, &[], b"{}").0, 200, "{path}");
+    }
+
+    let mut relayed: Vec<String> = upstream.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    relayed.sort();
+    // First match wins, on whole segments: `/hook/stripes` isn't under `/hook/stripe`
+    assert_eq!(relayed, ["/hook/up", "/hook/up/github", "/hook/up/payments/evt?x=1", "/hook/up/stripes"]);
+    let mut stored: Vec<String> = proxy.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    stored.sort();
+    assert_eq!(stored, ["/hook", "/hook/github", "/hook/stripe/evt?x=1", "/hook/stripes"]);
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn har_entries_carry_the_recorded_response() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/missing?__status=404", "{}");
+
+    let (status, har) = get(&server, "/api/export.har");
+    assert_eq!(status, 200);
+    let har: serde_json::Value = serde_json::from_str(&har).unwrap();
+    let response = &har["log"]["entries"][0]["response"];
+    assert_eq!(response["status"], 404);
+    assert_eq!(response["statusText"], "Not Found");
+    assert_eq!(response["content"]["text"], r#"{"ok":1}"#);
+    assert_eq!(response["content"]["size"], 8);
+    assert_eq!(response["bodySize"], 8);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn the_exact_method_and_header_order_are_kept_alongside_the_headers() {
+    let server = Server::start(&[("RECORD_HEADER_ORDER", "1"), ("MAX_HEADER_VALUE_BYTES", "8")]);
+    let long = "v".repeat(100);
+    request(&server, "PURGE", "/hook/cache", &[("X-B", "1"), ("X-Long", &long), ("X-A", "2"), ("X-B", "3")], b"");
+
+    let capture = &server.captures()[0];
+    assert_eq!(capture["raw_method"], "PURGE");
+    let order: Vec<serde_json::Value> = serde_json::from_str(capture["header_order"].as_str().unwrap()).unwrap();
+    let names: Vec<&str> = order.iter().map(|h| h["name"].as_str().unwrap()).collect();
+    // `request` sends Host, Content-Length and Connection first; repeats are grouped at the first
+    assert_eq!(names, ["host", "content-length", "connection", "x-b", "x-b", "x-long", "x-a"]);
+    assert_eq!(order.iter().map(|h| h["index"].as_i64().unwrap()).collect::<Vec<_>>(), (0..7).collect::<Vec<_>>());
+    assert_eq!((order[3]["value"].as_str(), order[4]["value"].as_str()), (Some("1"), Some("3")));
+    // Untruncated, unlike `headers`
+    assert_eq!(order[5]["value"], long.as_str());
+    assert!(!capture["headers"].as_str().unwrap().contains(&long));
+}
+
+#[test]
+fn nothing_extra_is_stored_by_default() {
+    let server = Server::start(&[]);
+    request(&server, "POST", "/hook/a", &[], b"");
+    let capture = &server.captures()[0];
+    assert!(capture["raw_method"].is_null() && capture["header_order"].is_null(), "{capture}");
+}
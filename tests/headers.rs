@@ -0,0 +1,19 @@
+mod common;
+
+use common::{Server, request};
+
+fn stored_headers(capture: &serde_json::Value) -> Vec<(String, String)> {
+    serde_json::from_str(capture["headers"].as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn oversized_header_values_are_truncated_with_a_marker() {
+    let server = Server::start(&[("MAX_HEADER_VALUE_BYTES", "8")]);
+    let long = "a".repeat(100);
+    request(&server, "GET", "/hook", &[("X-Huge", &long), ("X-Small", "short")], b"");
+
+    let headers = stored_headers(&server.captures()[0]);
+    let value = |name: &str| headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone()).unwrap();
+    assert_eq!(value("x-huge"), "aaaaaaaa…[truncated 92 bytes]");
+    assert_eq!(value("x-small"), "short");
+}
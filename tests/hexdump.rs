@@ -0,0 +1,35 @@
+mod common;
+
+use common::{Server, post_json, read_message, websocket};
+
+/// Posts a body over the inline limit and returns the `hexdump` frame that follows its `new` message.
+fn live_dump(server: &Server) -> serde_json::Value {
+    let mut ws = websocket(server, "/ws?hexdump=1");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+    post_json(server, "/hook", r#"{"spilled":"past the inline limit"}"#);
+
+    let new = read_message(&mut ws);
+    assert_eq!(new["type"], "new");
+    assert_eq!(new["request"]["spilled"], true);
+    let frame = read_message(&mut ws);
+    assert_eq!(frame["type"], "hexdump");
+    assert_eq!(frame["id"], new["request"]["id"]);
+    frame
+}
+
+#[test]
+fn spilled_body_is_dumped_live() {
+    let server = Server::start(&[("BODY_INLINE_LIMIT", "8"), ("HEXDUMP_BYTES", "16")]);
+    let frame = live_dump(&server);
+    assert_eq!(frame["shown"], 16);
+    assert_eq!(frame["total"], 35);
+    assert!(frame["dump"].as_str().unwrap().starts_with("00000000  7b 22 73 70"), "{frame}");
+}
+
+#[test]
+fn streamed_blob_body_is_dumped_live() {
+    let server = Server::start(&[("BODY_STORAGE", "blob"), ("BODY_INLINE_LIMIT", "8")]);
+    let frame = live_dump(&server);
+    assert_eq!(frame["shown"], 35);
+    assert!(frame["dump"].as_str().unwrap().contains(r#"{"spilled":"past"#), "{frame}");
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{Server, get, request};
+
+fn histogram(server: &Server, query: &str) -> serde_json::Value {
+    let (status, body) = get(server, &format!("/api/histogram?{query}"));
+    assert_eq!(status, 200, "{body}");
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn body_sizes_are_bucketed_between_min_and_max() {
+    let server = Server::start(&[]);
+    for size in [10, 20, 30, 100] {
+        request(&server, "POST", "/hook", &[], &vec![b'x'; size]);
+    }
+
+    let histogram = histogram(&server, "field=body_size&buckets=3");
+    assert_eq!(histogram["count"], 4);
+    let buckets = histogram["buckets"].as_array().unwrap();
+    assert_eq!(buckets.len(), 3);
+    assert_eq!(buckets[0]["min"], 10.0);
+    assert_eq!(buckets[2]["max"], 100.0);
+    let counts: Vec<i64> = buckets.iter().map(|b| b["count"].as_i64().unwrap()).collect();
+    assert_eq!(counts, [3, 0, 1]);
+
+    assert_eq!(get(&server, "/api/histogram?field=url").0, 400);
+}
+
+#[test]
+fn gap_ms_is_the_time_since_the_previous_capture() {
+    let server = Server::start(&[]);
+    request(&server, "POST", "/hook/first", &[], b"");
+    std::thread::sleep(std::time::Duration::from_millis(150));
+    request(&server, "POST", "/hook/second", &[], b"");
+
+    let captures = server.captures();
+    assert!(captures[1]["gap_ms"].is_null(), "nothing came before the first");
+    let gap = captures[0]["gap_ms"].as_i64().unwrap();
+    assert!((150..5000).contains(&gap), "{gap}");
+    assert_eq!(histogram(&server, "field=gap_ms")["count"], 1);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Server, get, post_json, read_head, read_message, send_message, websocket};
+use std::io::Write;
+
+#[test]
+fn older_link_still_pages_after_its_row_is_deleted() {
+    let server = Server::start(&[]);
+    for i in 0..105 {
+        post_json(&server, &format!("/hook/{i}"), "{}");
+    }
+
+    let (_, first_page) = get(&server, "/api/requests/html");
+    let older = first_page
+        .split("<a href=\"")
+        .filter_map(|a| a.split_once("\">Older"))
+        .map(|(href, _)| href.replace("&amp;", "&"))
+        .next()
+        .expect("first page links to older rows");
+    assert!(older.contains("before_ns="), "{older}");
+
+    // The cursor row is the oldest one on the first page
+    let cursor_row = server.captures()[99]["id"].as_str().unwrap().to_string();
+    let mut stream = server.connect();
+    write!(stream, "DELETE /api/requests/{cursor_row} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    assert_eq!(read_head(&mut stream).0, 200);
+
+    let (status, second_page) = get(&server, &format!("/api/requests/html{older}"));
+    assert_eq!(status, 200);
+    for i in 0..5 {
+        assert!(second_page.contains(&format!(">/hook/{i}<")), "/hook/{i} missing from {second_page}");
+    }
+    assert!(!second_page.contains(">/hook/5<"));
+}
+
+#[test]
+fn load_more_pages_through_rows_that_share_a_timestamp() {
+    let server = Server::start(&[]);
+    for i in 0..130 {
+        post_json(&server, &format!("/hook/{i}"), "{}");
+    }
+    // Every row ties on the timestamp and on received_ns, leaving only the id to order by
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    db.execute("UPDATE requests SET timestamp = '2026-01-01T00:00:00.000Z', received_ns = 1", []).unwrap();
+
+    let mut ws = websocket(&server, "/ws");
+    let first = read_message(&mut ws);
+    let mut ids: Vec<String> = first["requests"].as_array().unwrap().iter().map(|r| r["id"].as_str().unwrap().to_string()).collect();
+    assert_eq!((ids.len(), first["total"].as_i64()), (100, Some(130)));
+
+    let last = ids.last().unwrap().clone();
+    send_message(&mut ws, &format!(r#"{{"type":"load_more","before":"2026-01-01T00:00:00.000Z","before_ns":1,"before_id":"{last}"}}"#));
+    let second = read_message(&mut ws);
+    ids.extend(second["requests"].as_array().unwrap().iter().map(|r| r["id"].as_str().unwrap().to_string()));
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!((ids.len(), unique.len()), (130, 130));
+    assert!(ids.windows(2).all(|w| w[0] > w[1]), "pages are newest first by id");
+
+    // A bare timestamp starts strictly before it, past every row sharing it
+    send_message(&mut ws, r#"{"type":"load_more","before":"2026-01-01T00:00:00.000Z"}"#);
+    assert_eq!(read_message(&mut ws)["requests"], serde_json::json!([]));
+}
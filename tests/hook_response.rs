@@ -0,0 +1,30 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{Server, request};
+
+#[test]
+fn the_default_response_and_per_request_overrides() {
+    let server = Server::start(&[
+        ("HOOK_STATUS", "202"),
+        ("HOOK_BODY", "accepted"),
+        ("HOOK_CONTENT_TYPE", "text/plain"),
+        ("HOOK_HEADERS", r#"{"Retry-After":"30"}"#),
+    ]);
+    let (status, head, body) = request(&server, "POST", "/hook/plain", &[], b"{}");
+    assert_eq!((status, body.as_str()), (202, "accepted"));
+    let head = head.to_ascii_lowercase();
+    assert!(head.contains("content-type: text/plain"), "{head}");
+    assert!(head.contains("retry-after: 30"), "{head}");
+
+    let started = Instant::now();
+    let (status, _, body) = request(&server, "POST", "/hook/slow?__status=404&keep=1&__delay=300", &[], b"{}");
+    assert_eq!((status, body.as_str()), (404, "accepted"));
+    assert!(started.elapsed() >= Duration::from_millis(300));
+
+    let captures = server.captures();
+    assert_eq!(captures[0]["url"], "/hook/slow?keep=1");
+    assert_eq!(captures[0]["response_status"], 404);
+    assert_eq!(captures[1]["response_status"], 202);
+}
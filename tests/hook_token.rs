@@ -0,0 +1,25 @@
+mod common;
+
+use common::{Server, post_json};
+
+#[test]
+fn stored_url_never_contains_the_hook_token() {
+    let server = Server::start(&[("HOOK_TOKEN", "s3cret token")]);
+    for path in [
+        "/hook/t/s3cret%20token/orders?a=1&b=2",
+        "/hook/t/s3cret%20token?only=query",
+        "/hook/t/s3cret%20token",
+        "/hook/t/%73%33cret%20token/encoded",
+    ] {
+        assert_eq!(post_json(&server, path, "{}").0, 200, "{path}");
+    }
+    assert_eq!(post_json(&server, "/hook/t/wrong/orders", "{}").0, 401);
+
+    let mut urls: Vec<String> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    urls.sort();
+    assert_eq!(urls, ["/hook", "/hook/encoded", "/hook/orders?a=1&b=2", "/hook?only=query"]);
+    for capture in server.captures() {
+        let stored = capture.to_string();
+        assert!(!stored.contains("s3cret") && !stored.contains("%73%33cret"), "{stored}");
+    }
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{Server, get, read_head};
+use std::io::Write;
+
+fn send_raw(server: &Server, head: &str) {
+    let mut stream = server.connect();
+    stream.write_all(head.as_bytes()).unwrap();
+    assert_eq!(read_head(&mut stream).0, 200);
+}
+
+#[test]
+fn host_comes_from_the_host_header_or_an_absolute_target() {
+    let server = Server::start(&[]);
+    send_raw(&server, "GET /hook/a HTTP/1.1\r\nHost: one.example\r\nConnection: close\r\n\r\n");
+    send_raw(&server, "GET http://two.example:8080/hook/b HTTP/1.1\r\nHost: ignored.example\r\nConnection: close\r\n\r\n");
+
+    let captures = server.captures();
+    let host_of = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap()["host"].clone();
+    assert_eq!(host_of("/hook/a"), "one.example");
+    assert_eq!(host_of("http://two.example:8080/hook/b"), "two.example:8080");
+
+    let (_, stats) = get(&server, "/api/stats?host=one.example");
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    assert_eq!(stats["total"], 1);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+fn links(page: &str) -> (Option<String>, Option<String>) {
+    let href = |label: &str| {
+        page.split("<a href=\"")
+            .filter_map(|a| a.split_once(&format!("\">{label}")))
+            .map(|(href, _)| href.replace("&amp;", "&"))
+            .next()
+    };
+    (href("&larr; Newer"), href("Older &rarr;"))
+}
+
+fn rows(page: &str) -> usize {
+    page.matches("<tr><td>").count()
+}
+
+#[test]
+fn filtered_pages_link_to_each_other_and_keep_the_filter() {
+    let server = Server::start(&[]);
+    for i in 0..101 {
+        post_json(&server, &format!("/hook/a/{i}"), "{}");
+    }
+    for i in 0..4 {
+        post_json(&server, &format!("/hook/b/{i}"), "{}");
+    }
+
+    let (status, first) = get(&server, "/api/requests/html?endpoint=a");
+    assert_eq!(status, 200);
+    assert!(first.contains("<p>101 requests</p>"), "{first}");
+    assert_eq!(rows(&first), 100);
+    assert!(!first.contains("/hook/b/"));
+    let (newer, older) = links(&first);
+    assert_eq!(newer, None);
+    let older = older.expect("a second page");
+    assert!(older.ends_with("&endpoint=a"), "{older}");
+
+    let (_, second) = get(&server, &format!("/api/requests/html{older}"));
+    assert_eq!(rows(&second), 1);
+    assert!(second.contains("<td>/hook/a/0</td>"), "{second}");
+    let (newer, older) = links(&second);
+    assert_eq!(older, None);
+    // Back to the first page, still filtered
+    assert_eq!(newer.as_deref(), Some("?endpoint=a"));
+
+    let (_, unfiltered) = get(&server, "/api/requests/html");
+    assert!(unfiltered.contains("<p>105 requests</p>") && unfiltered.contains("<td>/hook/b/3</td>"));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn retries_are_linked_to_the_first_capture_with_their_key() {
+    let server = Server::start(&[("IDEMPOTENCY_HEADER", "Idempotency-Key")]);
+    for (path, key) in [("/hook/first", "k1"), ("/hook/retry", "k1"), ("/hook/other", "k2"), ("/hook/again", "k1")] {
+        assert_eq!(request(&server, "POST", path, &[("Idempotency-Key", key)], b"{}").0, 200);
+    }
+    request(&server, "POST", "/hook/unkeyed", &[], b"{}");
+
+    let captures = server.captures();
+    let by_url = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap().clone();
+    let first = by_url("/hook/first")["id"].clone();
+    assert_eq!(by_url("/hook/retry")["duplicate_of"], first);
+    assert_eq!(by_url("/hook/again")["duplicate_of"], first);
+    assert_eq!(by_url("/hook/retry")["idempotency_key"], "k1");
+    for url in ["/hook/first", "/hook/other", "/hook/unkeyed"] {
+        assert!(by_url(url)["duplicate_of"].is_null(), "{url}");
+    }
+}
+
+#[test]
+fn skipped_retries_get_the_default_response_and_are_not_stored() {
+    let server = Server::start(&[
+        ("IDEMPOTENCY_HEADER", "Idempotency-Key"),
+        ("IDEMPOTENCY_DUPLICATES", "skip"),
+        ("HOOK_STATUS", "202"),
+    ]);
+    assert_eq!(request(&server, "POST", "/hook/first", &[("Idempotency-Key", "k1")], b"{}").0, 202);
+    let (status, _, body) = request(&server, "POST", "/hook/retry", &[("Idempotency-Key", "k1")], b"{}");
+    assert_eq!((status, body.as_str()), (202, "{\"ok\":1}"));
+    let urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(urls, ["/hook/first"]);
+}
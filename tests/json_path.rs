@@ -0,0 +1,38 @@
+mod common;
+
+use common::{Server, get, post_json};
+use serde_json::{Value, json};
+
+#[test]
+fn json_paths_select_values_from_the_body() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/orders", r#"{"order":{"id":7,"items":[{"sku":"a"},{"sku":"b"},{"sku":"c"}]}}"#);
+    let id = server.captures()[0]["id"].as_str().unwrap().to_string();
+    let select = |path: &str| {
+        let (status, body) = get(&server, &format!("/api/requests/{id}/json?path={path}"));
+        (status, serde_json::from_str::<Value>(&body).unwrap())
+    };
+
+    assert_eq!(select("$.order.id").1["matches"], json!([7]));
+    assert_eq!(select("$.order.items[-1].sku").1["matches"], json!(["c"]));
+    assert_eq!(select("$..sku").1["matches"], json!(["a", "b", "c"]));
+    assert_eq!(select("$.order['items'][*].sku").1["matches"], json!(["a", "b", "c"]));
+    assert_eq!(select("$.missing").1["matches"], json!([]));
+
+    let (status, error) = select("order.id");
+    assert_eq!(status, 400);
+    assert!(error["error"].as_str().unwrap().contains("must start with '$'"));
+}
+
+#[test]
+fn bodies_that_are_not_json_are_unprocessable() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/orders", "not json");
+    let id = server.captures()[0]["id"].as_str().unwrap().to_string();
+    let (status, body) = get(&server, &format!("/api/requests/{id}/json"));
+    assert_eq!(status, 422);
+    assert!(body.contains("body is not JSON"));
+
+    let (status, _) = get(&server, "/api/requests/no-such-id/json");
+    assert_eq!(status, 404);
+}
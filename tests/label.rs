@@ -0,0 +1,27 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn senders_label_captures_through_the_query_string() {
+    let server = Server::start(&[("LABEL_PARAM", "label")]);
+    post_json(&server, "/hook/orders?label=run%2042&id=1", "{}");
+    post_json(&server, "/hook/orders?label=smoke", "{}");
+    post_json(&server, "/hook/orders?id=3&label=", "{}");
+
+    let captures = server.captures();
+    let labels: Vec<_> = captures.iter().map(|r| (r["url"].as_str().unwrap(), r["label"].clone())).collect();
+    assert_eq!(
+        labels,
+        [
+            ("/hook/orders?id=3", serde_json::Value::Null),
+            ("/hook/orders", "smoke".into()),
+            ("/hook/orders?id=1", "run 42".into()),
+        ]
+    );
+
+    let (_, found) = get(&server, "/api/requests?label=run%2042");
+    let found: serde_json::Value = serde_json::from_str(&found).unwrap();
+    assert_eq!(found["total"], 1);
+    assert_eq!(found["requests"][0]["url"], "/hook/orders?id=1");
+}
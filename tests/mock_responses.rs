@@ -0,0 +1,38 @@
+mod common;
+
+use common::{Server, config_file, request};
+
+#[test]
+fn first_matching_path_rule_answers_with_its_capture_groups() {
+    let rules = config_file(
+        "response_rules.json",
+        r#"[
+            {"path": "^/hook/users/(?P<id>\\d+)$", "status": 200, "body": "{\"id\": ${id}}"},
+            {"path": "^/hook/users", "status": 404, "body": "no such user", "content_type": "text/plain"}
+        ]"#,
+    );
+    let server = Server::start(&[("RESPONSE_RULES_FILE", rules.to_str().unwrap())]);
+
+    let (status, _, body) = request(&server, "GET", "/hook/users/42", &[], b"");
+    assert_eq!((status, body.as_str()), (200, r#"{"id": 42}"#));
+    let (status, head, body) = request(&server, "GET", "/hook/users/me", &[], b"");
+    assert_eq!((status, body.as_str()), (404, "no such user"));
+    assert!(head.to_ascii_lowercase().contains("content-type: text/plain"), "{head}");
+    let (status, _, body) = request(&server, "GET", "/hook/other", &[], b"");
+    assert_eq!((status, body.as_str()), (200, r#"{"ok":1}"#), "unmatched paths get the default");
+    assert_eq!(server.captures().len(), 3, "every request is captured either way");
+}
+
+#[test]
+fn method_responses_answer_mapped_methods_and_default_the_rest() {
+    let server = Server::start(&[(
+        "METHOD_RESPONSES",
+        r#"{"POST":{"status":201,"body":"{\"created\":true}"},"DELETE":{"status":204,"body":""}}"#,
+    )]);
+    let (status, _, body) = request(&server, "POST", "/hook/items", &[], b"{}");
+    assert_eq!((status, body.as_str()), (201, r#"{"created":true}"#));
+    assert_eq!(request(&server, "DELETE", "/hook/items/1", &[], b"").0, 204);
+    let (status, _, body) = request(&server, "PUT", "/hook/items/1", &[], b"{}");
+    assert_eq!((status, body.as_str()), (200, r#"{"ok":1}"#));
+    assert_eq!(server.captures().len(), 3);
+}
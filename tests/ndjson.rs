@@ -0,0 +1,22 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn ndjson_bodies_are_split_into_records() {
+    let body = b"{\"n\":1}\n\n  {\"n\":2}  \r\nnot json\n[3]\n";
+    // Also when a spilled body streams into its blob and has to be read back
+    for env in [vec![], vec![("BODY_STORAGE", "blob"), ("BODY_INLINE_LIMIT", "8")]] {
+        let server = Server::start(&env);
+        request(&server, "POST", "/hook/events", &[("Content-Type", "application/x-ndjson")], body);
+        request(&server, "POST", "/hook/json", &[("Content-Type", "application/json")], b"{\"n\":1}");
+
+        let captures = server.captures();
+        let events = captures.iter().find(|r| r["url"] == "/hook/events").unwrap();
+        let records: serde_json::Value = serde_json::from_str(events["ndjson"].as_str().unwrap()).unwrap();
+        assert_eq!(records, serde_json::json!([{"n": 1}, {"n": 2}, "not json", [3]]), "{env:?}");
+        assert_eq!(events["ndjson_count"], 4);
+        let json = captures.iter().find(|r| r["url"] == "/hook/json").unwrap();
+        assert!(json["ndjson"].is_null() && json["ndjson_count"].is_null(), "{json}");
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{Server, config_file, post_json};
+
+const SPEC: &str = r#"
+openapi: 3.0.0
+info: {title: orders, version: "1"}
+paths:
+  /orders:
+    post:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [id]
+              properties:
+                id: {type: integer}
+"#;
+
+#[test]
+fn captures_record_whether_they_match_the_spec_without_being_rejected() {
+    let spec = config_file("openapi.yaml", SPEC);
+    let server = Server::start(&[("OPENAPI_SPEC_PATH", spec.to_str().unwrap())]);
+    assert_eq!(post_json(&server, "/hook/orders", r#"{"id":1}"#).0, 200);
+    assert_eq!(post_json(&server, "/hook/orders", r#"{"id":"one"}"#).0, 200);
+    assert_eq!(post_json(&server, "/hook/refunds", "{}").0, 200);
+
+    let captures = server.captures();
+    let by_body = |body: &str| {
+        let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, body);
+        captures.iter().find(|r| r["body"] == encoded.as_str()).unwrap().clone()
+    };
+    assert_eq!(by_body(r#"{"id":1}"#)["spec_valid"], true);
+    let wrong_type = by_body(r#"{"id":"one"}"#);
+    assert_eq!(wrong_type["spec_valid"], false);
+    assert_eq!(wrong_type["spec_error"], r#"$.id: expected integer, got string"#);
+    let unknown = captures.iter().find(|r| r["url"] == "/hook/refunds").unwrap();
+    assert_eq!(unknown["spec_error"], "path /refunds is not in the spec");
+}
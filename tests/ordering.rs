@@ -0,0 +1,26 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn captures_in_the_same_millisecond_keep_their_arrival_order() {
+    let server = Server::start(&[]);
+    for i in 0..30 {
+        post_json(&server, &format!("/hook/{i}"), "{}");
+    }
+    let newest_first: Vec<String> = (0..30).rev().map(|i| format!("/hook/{i}")).collect();
+
+    let listed: Vec<String> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(listed, newest_first);
+
+    let (_, feed) = get(&server, "/api/feed?limit=30");
+    let feed: serde_json::Value = serde_json::from_str(&feed).unwrap();
+    let paths: Vec<&str> = feed["items"].as_array().unwrap().iter().map(|i| i["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, newest_first);
+
+    for (n, url) in newest_first.iter().enumerate().take(5) {
+        let (_, recent) = get(&server, &format!("/api/requests/recent?n={}", n + 1));
+        let recent: serde_json::Value = serde_json::from_str(&recent).unwrap();
+        assert_eq!(&recent["url"], url.as_str(), "recent n={}", n + 1);
+    }
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{Server, post_json, read_message, send_message, websocket};
+
+#[test]
+fn pinning_over_the_websocket_updates_every_client_and_survives_eviction() {
+    let server = Server::start(&[("MAX_REQUESTS", "2")]);
+    let mut pinner = websocket(&server, "/ws");
+    let mut watcher = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut pinner)["type"], "history");
+    assert_eq!(read_message(&mut watcher)["type"], "history");
+
+    post_json(&server, "/hook/keep", "{}");
+    let id = read_message(&mut pinner)["request"]["id"].as_str().unwrap().to_string();
+    assert_eq!(read_message(&mut watcher)["type"], "new");
+
+    send_message(&mut pinner, &format!(r#"{{"type":"pin","id":"{id}","pinned":true}}"#));
+    for ws in [&mut pinner, &mut watcher] {
+        let update = read_message(ws);
+        assert_eq!(update["type"], "update");
+        assert_eq!(update["request"]["id"], id.as_str());
+        assert_eq!(update["request"]["pinned"], true);
+    }
+
+    for path in ["/hook/a", "/hook/b", "/hook/c"] {
+        post_json(&server, path, "{}");
+    }
+    let urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert!(urls.contains(&"/hook/keep".to_string()), "{urls:?}");
+    assert!(!urls.contains(&"/hook/a".to_string()), "{urls:?}");
+
+    send_message(&mut pinner, r#"{"type":"pin","id":"missing"}"#);
+    // The new captures' broadcasts are still queued ahead of the answer
+    let error = std::iter::repeat_with(|| read_message(&mut pinner)).find(|m| m["type"] == "error").unwrap();
+    assert_eq!(error["error"], "request not found: missing");
+}
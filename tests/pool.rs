@@ -0,0 +1,37 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn the_database_is_in_wal_mode_so_a_long_read_doesnt_block_captures() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/first", "{}");
+
+    let reader = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    let mode: String = reader.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+    assert_eq!(mode, "wal");
+    // A read transaction held open, like a slow history export
+    reader.execute_batch("BEGIN; SELECT COUNT(*) FROM requests;").unwrap();
+    assert_eq!(post_json(&server, "/hook/during", "{}").0, 200);
+    assert_eq!(server.captures().len(), 2);
+    let seen: i64 = reader.query_row("SELECT COUNT(*) FROM requests", [], |row| row.get(0)).unwrap();
+    assert_eq!(seen, 1, "the reader keeps its snapshot");
+    reader.execute_batch("COMMIT").unwrap();
+}
+
+#[test]
+fn concurrent_hooks_share_a_small_pool() {
+    let server = Server::start(&[("DB_POOL_SIZE", "2")]);
+    std::thread::scope(|scope| {
+        for t in 0..8 {
+            let server = &server;
+            scope.spawn(move || {
+                for i in 0..5 {
+                    assert_eq!(post_json(server, &format!("/hook/{t}/{i}"), "{}").0, 200);
+                    assert_eq!(get(server, "/api/requests?limit=5").0, 200);
+                }
+            });
+        }
+    });
+    assert_eq!(server.captures().len(), 40);
+}
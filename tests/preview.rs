@@ -0,0 +1,36 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn list_entries_carry_a_one_line_preview_chosen_by_content_type() {
+    let server = Server::start(&[]);
+    let post = |path: &str, content_type: &str, body: &[u8]| {
+        request(&server, "POST", path, &[("Content-Type", content_type)], body);
+    };
+    post("/hook/json", "application/json", br#"{"event":"paid","order":{"id":7,"lines":[1,2]},"tags":["a","b","c"],"note":null}"#);
+    post("/hook/text", "text/plain", b"line one\n\n   line\ttwo");
+    post("/hook/binary", "application/octet-stream", &[0x00, 0xff, 0x10, 0x80, 0x7f, 0xc3, 0x28, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a]);
+    post("/hook/empty", "application/json", b"");
+
+    let captures = server.captures();
+    let preview = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap()["preview"].clone();
+    assert_eq!(preview("/hook/json"), r#"{"event":"paid","note":null,"order":"{…2 keys}","tags":"[…3 items]"}"#);
+    assert_eq!(preview("/hook/text"), "line one line two");
+    assert_eq!(preview("/hook/binary"), "00 ff 10 80 7f c3 28 01 02 03 04 05 06 07 08 09 …");
+    assert!(preview("/hook/empty").is_null());
+}
+
+#[test]
+fn long_json_previews_stay_valid_json() {
+    let server = Server::start(&[]);
+    let fields: Vec<String> = (0..40).map(|i| format!("\"field_{i:02}\":\"{}\"", "v".repeat(60))).collect();
+    request(&server, "POST", "/hook/wide", &[("Content-Type", "application/json")], format!("{{{}}}", fields.join(",")).as_bytes());
+
+    let preview = server.captures()[0]["preview"].as_str().unwrap().to_string();
+    let parsed: serde_json::Value = serde_json::from_str(&preview).unwrap();
+    assert!(preview.chars().count() <= 120, "{preview}");
+    // Strings past 40 characters are cut, and the dropped entries are counted
+    assert_eq!(parsed["field_00"], format!("{}…", "v".repeat(40)));
+    assert_eq!(parsed["…"], 38);
+}
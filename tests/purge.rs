@@ -0,0 +1,22 @@
+mod common;
+
+use common::{Server, post_json, request};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn purging_by_body_hash_deletes_only_that_payload() {
+    let server = Server::start(&[]);
+    for _ in 0..3 {
+        post_json(&server, "/hook/retry", r#"{"same":true}"#);
+    }
+    post_json(&server, "/hook/other", r#"{"same":false}"#);
+
+    let hash = format!("{:X}", Sha256::digest(br#"{"same":true}"#));
+    let (status, _, body) = request(&server, "DELETE", &format!("/api/requests?body_sha256={hash}"), &[], b"");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["deleted"], 3, "the hash matches whatever its case");
+    let left = server.captures();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0]["url"], "/hook/other");
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{Server, post_json};
+
+#[test]
+fn capture_quota_leaves_max_requests_to_eviction() {
+    let server = Server::start(&[("MAX_REQUESTS", "3"), ("CAPTURE_QUOTA", "10")]);
+    for i in 0..5 {
+        assert_eq!(post_json(&server, &format!("/hook/{i}"), "{}").0, 200);
+    }
+    assert_eq!(server.captures().len(), 3, "QUOTA_POLICY=evict still evicts beyond MAX_REQUESTS");
+}
+
+#[test]
+fn concurrent_captures_never_overshoot_the_quota() {
+    let server = Server::start(&[("CAPTURE_QUOTA", "5"), ("QUOTA_POLICY", "reject"), ("MAX_REQUESTS", "2")]);
+    let statuses: Vec<u16> = std::thread::scope(|scope| {
+        let senders: Vec<_> = (0..30)
+            .map(|i| {
+                let server = &server;
+                scope.spawn(move || post_json(server, &format!("/hook/{i}"), "{}").0)
+            })
+            .collect();
+        senders.into_iter().map(|s| s.join().unwrap()).collect()
+    });
+
+    assert_eq!(statuses.iter().filter(|&&s| s == 200).count(), 5);
+    assert_eq!(statuses.iter().filter(|&&s| s == 507).count(), 25);
+    assert_eq!(server.captures().len(), 5, "MAX_REQUESTS doesn't evict under QUOTA_POLICY=reject");
+}
+
+#[test]
+fn concurrent_writers_at_the_quota_boundary_are_never_answered_500() {
+    let server = Server::start(&[("CAPTURE_QUOTA", "40"), ("QUOTA_POLICY", "reject"), ("DB_POOL_SIZE", "8")]);
+    // Most arrive once only a few slots are left, so their inserts race inside the quota check
+    for i in 0..30 {
+        assert_eq!(post_json(&server, &format!("/hook/warmup/{i}"), "{}").0, 200);
+    }
+    let statuses: Vec<u16> = std::thread::scope(|scope| {
+        let senders: Vec<_> = (0..60)
+            .map(|i| {
+                let server = &server;
+                scope.spawn(move || post_json(server, &format!("/hook/{i}"), "{}").0)
+            })
+            .collect();
+        senders.into_iter().map(|s| s.join().unwrap()).collect()
+    });
+
+    assert!(statuses.iter().all(|&s| s == 200 || s == 507), "{statuses:?}");
+    assert_eq!(statuses.iter().filter(|&&s| s == 200).count(), 10);
+    assert_eq!(server.captures().len(), 40);
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn recent_counts_back_from_the_latest_capture() {
+    let server = Server::start(&[]);
+    for path in ["/hook/first", "/hook/second", "/hook/third"] {
+        post_json(&server, path, "{}");
+    }
+    let url_of = |path: &str| {
+        let (status, body) = get(&server, path);
+        assert_eq!(status, 200, "{body}");
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["url"].as_str().unwrap().to_string()
+    };
+    assert_eq!(url_of("/api/requests/recent"), "/hook/third");
+    assert_eq!(url_of("/api/requests/recent?n=1"), "/hook/third");
+    assert_eq!(url_of("/api/requests/recent?n=3"), "/hook/first");
+
+    let (status, body) = get(&server, "/api/requests/recent?n=4");
+    assert_eq!(status, 404);
+    assert!(body.contains("fewer than 4 requests stored"), "{body}");
+    assert_eq!(get(&server, "/api/requests/recent?n=0").0, 400);
+}
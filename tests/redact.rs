@@ -0,0 +1,36 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, post_json, request};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn listed_json_fields_are_stored_masked_at_any_depth() {
+    let server = Server::start(&[("REDACT_JSON_FIELDS", "password,card_number")]);
+    let original = r#"{"user":"ann","Password":"hunter2","payment":{"card_number":"4242","amount":5},"items":[{"password":"x"}]}"#;
+    post_json(&server, "/hook/signup", original);
+
+    let capture = &server.captures()[0];
+    let stored: serde_json::Value = serde_json::from_slice(&BASE64.decode(capture["body"].as_str().unwrap()).unwrap()).unwrap();
+    assert_eq!(
+        stored,
+        serde_json::json!({"user":"ann","Password":"***","payment":{"card_number":"***","amount":5},"items":[{"password":"***"}]})
+    );
+    assert_eq!(capture["body_redacted"], true);
+    assert_eq!(capture["body_size"], original.len());
+    assert_eq!(capture["body_sha256"], hex(&Sha256::digest(original)));
+}
+
+#[test]
+fn non_json_bodies_are_left_alone() {
+    let server = Server::start(&[("REDACT_JSON_FIELDS", "password")]);
+    request(&server, "POST", "/hook/form", &[("Content-Type", "text/plain")], br#"{"password":"hunter2"}"#);
+    let capture = &server.captures()[0];
+    assert_eq!(BASE64.decode(capture["body"].as_str().unwrap()).unwrap(), br#"{"password":"hunter2"}"#);
+    assert_eq!(capture["body_redacted"], false);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
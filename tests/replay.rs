@@ -0,0 +1,204 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, post_json};
+
+#[test]
+fn speeds_below_the_minimum_are_rejected() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/a", "{}");
+    let id = server.captures()[0]["id"].as_str().unwrap().to_string();
+
+    for speed in ["1e-300", "0.001", "0"] {
+        let timed = format!(r#"{{"ids":["{id}"],"target":"http://127.0.0.1:9","speed":{speed}}}"#);
+        assert_eq!(post_json(&server, "/api/replay/timed", &timed).0, 400, "timed speed {speed}");
+        let range = format!(
+            r#"{{"from":"2000-01-01T00:00:00Z","to":"2100-01-01T00:00:00Z","target":"http://127.0.0.1:9","speed":{speed}}}"#
+        );
+        assert_eq!(post_json(&server, "/api/replay/range", &range).0, 400, "range speed {speed}");
+    }
+
+    let slowest = format!(r#"{{"ids":["{id}"],"target":"http://127.0.0.1:9","speed":0.01}}"#);
+    assert_eq!(post_json(&server, "/api/replay/timed?dry_run=1", &slowest).0, 200);
+}
+
+#[test]
+fn recorded_replays_keep_large_bodies_out_of_the_row() {
+    for storage in ["base64", "blob"] {
+        let server = Server::start(&[("BODY_INLINE_LIMIT", "1024"), ("BODY_STORAGE", storage)]);
+        let body = format!(r#"{{"pad":"{}"}}"#, "x".repeat(5000));
+        post_json(&server, "/hook/source", &body);
+        let source = server.captures()[0]["id"].as_str().unwrap().to_string();
+
+        let replay = format!(r#"{{"target":"{}"}}"#, server.url("/hook/target"));
+        let (status, result) = post_json(&server, &format!("/api/requests/{source}/replay?record=1"), &replay);
+        assert_eq!(status, 200);
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let recorded = result["recorded_id"].as_str().expect("replay was recorded");
+
+        let listed = server.captures().into_iter().find(|r| r["id"] == recorded).unwrap();
+        assert_eq!(listed["spilled"], true, "{storage}");
+        assert_eq!(listed["body"], "", "{storage}");
+        let (_, one) = common::get(&server, &format!("/api/requests/{recorded}"));
+        let one: serde_json::Value = serde_json::from_str(&one).unwrap();
+        assert_eq!(BASE64.decode(one["body"].as_str().unwrap()).unwrap(), body.as_bytes(), "{storage}");
+    }
+}
+
+#[test]
+fn timed_replay_orders_same_millisecond_captures_by_arrival() {
+    let server = Server::start(&[]);
+    for path in ["a", "b", "c"] {
+        post_json(&server, &format!("/hook/{path}"), "{}");
+    }
+    // Same millisecond, arriving c, a, b
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    for (path, ns) in [("a", 2), ("b", 3), ("c", 1)] {
+        db.execute(
+            "UPDATE requests SET timestamp = '2026-01-01T00:00:00.000Z', received_ns = ?1 WHERE url = ?2",
+            rusqlite::params![ns, format!("/hook/{path}")],
+        )
+        .unwrap();
+    }
+    let ids: Vec<String> = server.captures().iter().map(|r| format!("{}", r["id"])).collect();
+
+    let timed = format!(r#"{{"ids":[{}],"target":"http://127.0.0.1:9","preserve_path":true}}"#, ids.join(","));
+    let (status, plan) = post_json(&server, "/api/replay/timed?dry_run=1", &timed);
+    assert_eq!(status, 200);
+    let plan: serde_json::Value = serde_json::from_str(&plan).unwrap();
+    let urls: Vec<&str> = plan["requests"].as_array().unwrap().iter().map(|r| r["url"].as_str().unwrap()).collect();
+    assert_eq!(urls, ["http://127.0.0.1:9/hook/c", "http://127.0.0.1:9/hook/a", "http://127.0.0.1:9/hook/b"]);
+}
+
+#[test]
+fn range_replay_answers_with_a_job_to_follow() {
+    let server = Server::start(&[]);
+    for path in ["a", "b"] {
+        post_json(&server, &format!("/hook/{path}"), "{}");
+    }
+    let range = format!(
+        r#"{{"from":"2000-01-01T00:00:00Z","to":"2100-01-01T00:00:00Z","target":"{}","preserve_timing":false}}"#,
+        server.url("/hook/replayed")
+    );
+    let (status, started) = post_json(&server, "/api/replay/range", &range);
+    assert_eq!(status, 202);
+    let started: serde_json::Value = serde_json::from_str(&started).unwrap();
+    assert_eq!(started["total"], 2);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let job = loop {
+        let (status, job) = common::get(&server, started["status"].as_str().unwrap());
+        assert_eq!(status, 200);
+        let job: serde_json::Value = serde_json::from_str(&job).unwrap();
+        if job["done"] == true {
+            break job;
+        }
+        assert!(std::time::Instant::now() < deadline, "replay job never finished: {job}");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    assert_eq!(job["sent"], 2);
+    assert_eq!(job["succeeded"], 2);
+    assert_eq!(job["results"].as_array().unwrap().len(), 2);
+    assert_eq!(common::get(&server, "/api/replay/jobs/unknown").0, 404);
+}
+
+#[test]
+fn dry_runs_describe_the_requests_without_sending_them() {
+    let server = Server::start(&[]);
+    common::request(&server, "PUT", "/hook/orders?id=1", &[("X-Signature", "abc"), ("Content-Type", "application/json")], b"{\"n\":1}");
+    post_json(&server, "/hook/orders?id=2", "{\"n\":2}");
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    for (query, at) in [("1", "2026-01-01T00:00:00.000Z"), ("2", "2026-01-01T00:00:01.500Z")] {
+        db.execute("UPDATE requests SET timestamp = ?1 WHERE url = ?2", rusqlite::params![at, format!("/hook/orders?id={query}")])
+            .unwrap();
+    }
+    let ids: Vec<String> = server.captures().iter().map(|r| format!("{}", r["id"])).collect();
+
+    let target = server.url("/hook/target");
+    let timed = format!(r#"{{"ids":[{}],"target":"{target}","speed":4}}"#, ids.join(","));
+    let (status, plan) = post_json(&server, "/api/replay/timed?dry_run=1", &timed);
+    assert_eq!(status, 200);
+    let plan: serde_json::Value = serde_json::from_str(&plan).unwrap();
+    assert_eq!(plan["dry_run"], true);
+    let first = &plan["requests"][0];
+    assert_eq!(first["method"], "PUT");
+    assert_eq!(first["url"], target);
+    assert_eq!(BASE64.decode(first["body"].as_str().unwrap()).unwrap(), b"{\"n\":1}");
+    let names: Vec<&str> = first["headers"].as_array().unwrap().iter().map(|h| h[0].as_str().unwrap()).collect();
+    assert!(names.contains(&"x-signature"), "{names:?}");
+    // Recomputed by the client when it's sent for real
+    assert!(!names.iter().any(|n| ["host", "content-length", "connection"].contains(n)), "{names:?}");
+    // The original gap, before `speed` divides it
+    let offsets: Vec<i64> = plan["requests"].as_array().unwrap().iter().map(|r| r["offset_ms"].as_i64().unwrap()).collect();
+    assert_eq!(offsets, [0, 1500]);
+
+    let one = format!(r#"{{"target":"{target}","preserve_path":true}}"#);
+    let (status, single) = post_json(&server, &format!("/api/requests/{}/replay?dry_run=1", ids[1].trim_matches('"')), &one);
+    assert_eq!(status, 200);
+    let single: serde_json::Value = serde_json::from_str(&single).unwrap();
+    assert_eq!(single["request"]["url"], format!("{target}/hook/orders?id=1"));
+
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    assert_eq!(server.captures().len(), 2, "a dry run sent something");
+}
+
+#[test]
+fn one_capture_is_resent_as_captured() {
+    let server = Server::start(&[]);
+    let target = Server::start(&[("HOOK_STATUS", "202"), ("HOOK_BODY", "queued")]);
+    common::request(&server, "PATCH", "/hook/orders/7?v=2", &[("X-Signature", "abc")], b"{\"n\":7}");
+    let id = server.captures()[0]["id"].as_str().unwrap().to_string();
+
+    let replay = format!(r#"{{"target":"{}","preserve_path":true}}"#, target.url("/hook/replayed"));
+    let (status, result) = post_json(&server, &format!("/api/requests/{id}/replay"), &replay);
+    assert_eq!(status, 200);
+    let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(result["id"], id.as_str());
+    assert_eq!(result["status"], 202);
+    assert_eq!(result["response_body"], "queued");
+    assert!(result["error"].is_null() && result["recorded_id"].is_null(), "{result}");
+
+    let received = &target.captures()[0];
+    assert_eq!(received["method"], "PATCH");
+    assert_eq!(received["url"], "/hook/replayed/hook/orders/7?v=2");
+    assert_eq!(BASE64.decode(received["body"].as_str().unwrap()).unwrap(), b"{\"n\":7}");
+    assert!(received["headers"].as_str().unwrap().contains("x-signature"), "{received}");
+    // Without ?record=1 nothing new is stored on the replaying side
+    assert_eq!(server.captures().len(), 1);
+
+    let unreachable = format!(r#"{{"target":"http://127.0.0.1:{}"}}"#, common::free_port());
+    let (_, failed) = post_json(&server, &format!("/api/requests/{id}/replay"), &unreachable);
+    let failed: serde_json::Value = serde_json::from_str(&failed).unwrap();
+    assert!(failed["status"].is_null() && failed["error"].is_string(), "{failed}");
+    assert_eq!(post_json(&server, "/api/requests/missing/replay", &replay).0, 404);
+}
+
+#[test]
+fn recorded_replays_are_tagged_and_listed_by_source() {
+    let target = Server::start(&[("HOOK_STATUS", "202")]);
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/source", r#"{"n":1}"#);
+    let source = server.captures()[0]["id"].as_str().unwrap().to_string();
+
+    let replay = format!(r#"{{"target":"{}"}}"#, target.url("/hook/target"));
+    let (_, plain) = post_json(&server, &format!("/api/requests/{source}/replay"), &replay);
+    let plain: serde_json::Value = serde_json::from_str(&plain).unwrap();
+    assert_eq!(plain["recorded_id"], serde_json::Value::Null);
+    let (_, recorded) = post_json(&server, &format!("/api/requests/{source}/replay?record=1"), &replay);
+    let recorded: serde_json::Value = serde_json::from_str(&recorded).unwrap();
+    let recorded = recorded["recorded_id"].as_str().unwrap();
+
+    let (_, listed) = common::get(&server, &format!("/api/requests?replay_of={source}"));
+    let listed: serde_json::Value = serde_json::from_str(&listed).unwrap();
+    assert_eq!(listed["total"], 1);
+    let capture = &listed["requests"][0];
+    assert_eq!(capture["id"], recorded);
+    assert_eq!(capture["replay_of"], source.as_str());
+    assert_eq!(capture["url"], target.url("/hook/target"));
+    assert_eq!(capture["response_status"], 202);
+    let tags: Vec<String> = serde_json::from_str(capture["tags"].as_str().unwrap()).unwrap();
+    assert!(tags.contains(&"replay".to_string()), "{tags:?}");
+    assert_eq!(BASE64.decode(capture["body"].as_str().unwrap()).unwrap(), br#"{"n":1}"#);
+    assert_eq!(target.captures().len(), 2);
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use common::{Server, request};
+
+#[test]
+fn captures_missing_a_required_header_are_refused_unstored() {
+    let server = Server::start(&[("REQUIRE_HEADERS", "X-Signature, X-Event-Id")]);
+    let (status, _, body) = request(&server, "POST", "/hook/none", &[], b"{}");
+    assert_eq!(status, 400);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body, serde_json::json!({"error": "missing required headers", "missing": ["X-Signature", "X-Event-Id"]}));
+
+    let (status, _, body) = request(&server, "POST", "/hook/half", &[("x-signature", "abc")], b"{}");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""missing":["X-Event-Id"]"#), "{body}");
+
+    // Header names match case-insensitively
+    assert_eq!(request(&server, "POST", "/hook/all", &[("x-signature", "abc"), ("X-EVENT-ID", "1")], b"{}").0, 200);
+    let urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(urls, ["/hook/all"]);
+}
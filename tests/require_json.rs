@@ -0,0 +1,26 @@
+mod common;
+
+use common::{Server, post_json, request};
+
+#[test]
+fn invalid_json_bodies_are_refused_without_being_stored() {
+    let server = Server::start(&[("REQUIRE_JSON", "1")]);
+    let (status, body) = post_json(&server, "/hook/broken", "{\"amount\":");
+    assert_eq!(status, 415);
+    assert!(body.contains("not valid JSON"), "{body}");
+
+    // Only JSON-typed requests are checked
+    assert_eq!(request(&server, "POST", "/hook/text", &[("Content-Type", "text/plain")], b"{\"amount\":").0, 200);
+    assert_eq!(post_json(&server, "/hook/ok", "{\"amount\":1}").0, 200);
+    let urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(urls, ["/hook/ok", "/hook/text"]);
+}
+
+#[test]
+fn rejected_bodies_can_still_be_stored() {
+    let server = Server::start(&[("REQUIRE_JSON", "1"), ("REQUIRE_JSON_STORE_REJECTED", "1")]);
+    assert_eq!(request(&server, "POST", "/hook/broken", &[("Content-Type", "application/vnd.api+json")], b"[1,").0, 415);
+    let captures = server.captures();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0]["url"], "/hook/broken");
+}
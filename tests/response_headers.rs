@@ -0,0 +1,33 @@
+mod common;
+
+use common::{Server, request};
+
+fn header(head: &str, name: &str) -> Option<String> {
+    head.lines().find_map(|l| {
+        let (n, v) = l.split_once(": ")?;
+        n.eq_ignore_ascii_case(name).then(|| v.to_string())
+    })
+}
+
+#[test]
+fn hook_responses_name_the_capture_and_the_stored_count() {
+    let server = Server::start(&[("HOOK_RESPONSE_HEADERS", "1"), ("MAX_REQUESTS", "2")]);
+    let mut ids = Vec::new();
+    for (n, expected_count) in [(1, "1"), (2, "2"), (3, "2")] {
+        let (status, head, _) = request(&server, "POST", &format!("/hook/{n}"), &[], b"{}");
+        assert_eq!(status, 200);
+        ids.push(header(&head, "x-capture-id").unwrap());
+        // Counted after eviction
+        assert_eq!(header(&head, "x-capture-count").as_deref(), Some(expected_count), "{head}");
+    }
+    let stored: Vec<String> = server.captures().iter().map(|r| r["id"].as_str().unwrap().to_string()).collect();
+    assert_eq!(stored, [ids[2].clone(), ids[1].clone()]);
+}
+
+#[test]
+fn the_headers_are_off_by_default() {
+    let server = Server::start(&[]);
+    let (_, head, _) = request(&server, "POST", "/hook/a", &[], b"{}");
+    assert_eq!(header(&head, "x-capture-id"), None);
+    assert_eq!(header(&head, "x-capture-count"), None);
+}
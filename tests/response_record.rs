@@ -0,0 +1,43 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use common::{Server, post_json};
+
+#[test]
+fn captures_record_what_the_sender_was_answered() {
+    let long = "r".repeat(5000);
+    let server = Server::start(&[("HOOK_STATUS", "202"), ("HOOK_BODY", &long)]);
+    post_json(&server, "/hook/a", "{}");
+    let capture = &server.captures()[0];
+    assert_eq!(capture["response_status"], 202);
+    // Cut to 4 KiB
+    assert_eq!(capture["response_body"], "r".repeat(4096));
+    assert!(capture["duration_ms"].as_i64().unwrap() >= 0);
+}
+
+#[test]
+fn duration_covers_a_slow_upstream() {
+    let upstream = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = upstream.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = upstream.accept().unwrap();
+        // The relayed request: its head and the two-byte `{}` body
+        let mut received = Vec::new();
+        let mut byte = [0u8];
+        while !received.ends_with(b"\r\n\r\n{}") {
+            stream.read_exact(&mut byte).unwrap();
+            received.push(byte[0]);
+        }
+        std::thread::sleep(Duration::from_millis(300));
+        stream.write_all(b"HTTP/1.1 409 Conflict\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndupe").unwrap();
+    });
+    let proxy = Server::start(&[("FORWARD_URL", &format!("http://{addr}"))]);
+    assert_eq!(post_json(&proxy, "/hook/slow", "{}").0, 409);
+
+    let capture = &proxy.captures()[0];
+    assert_eq!(capture["response_status"], 409);
+    assert_eq!(capture["response_body"], "dupe");
+    assert!(capture["duration_ms"].as_i64().unwrap() >= 300, "{capture}");
+}
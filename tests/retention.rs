@@ -0,0 +1,27 @@
+mod common;
+
+use common::{Server, post_json, read_message, websocket};
+
+#[test]
+fn sweeps_delete_old_unpinned_requests_and_tell_the_dashboard() {
+    let server = Server::start(&[("RETENTION_HOURS", "1"), ("RETENTION_SWEEP_SECONDS", "1")]);
+    for path in ["/hook/old", "/hook/old-pinned", "/hook/new"] {
+        post_json(&server, path, "{}");
+    }
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    db.execute("UPDATE requests SET timestamp = '2020-01-01T00:00:00.000Z' WHERE url LIKE '/hook/old%'", []).unwrap();
+    db.execute("UPDATE requests SET pinned = 1 WHERE url = '/hook/old-pinned'", []).unwrap();
+
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+    let pruned = read_message(&mut ws);
+    assert_eq!(pruned["type"], "pruned");
+    assert_eq!(pruned["deleted"], 1);
+    let before = chrono::DateTime::parse_from_rfc3339(pruned["before"].as_str().unwrap()).unwrap();
+    let age = chrono::Utc::now().signed_duration_since(before);
+    assert!((59..=61).contains(&age.num_minutes()), "{pruned}");
+
+    let mut urls: Vec<_> = server.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    urls.sort();
+    assert_eq!(urls, ["/hook/new", "/hook/old-pinned"]);
+}
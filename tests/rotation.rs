@@ -0,0 +1,45 @@
+mod common;
+
+use common::{Server, config_dir, get, post_json};
+
+#[test]
+fn day_files_are_listed_served_and_pruned() {
+    let dir = config_dir("rotation");
+    // Earlier days' files, as an unrotated logger pointed at them would leave them
+    for day in ["2019-12-31", "2020-01-01"] {
+        let db_path = dir.join(format!("test-{day}.db"));
+        let old = Server::start(&[("DB_PATH", db_path.to_str().unwrap())]);
+        post_json(&old, &format!("/hook/{day}"), "{}");
+    }
+
+    let server = Server::start(&[
+        ("DB_PATH", dir.join("test.db").to_str().unwrap()),
+        ("DB_ROTATION", "daily"),
+        ("DB_ROTATION_KEEP", "2"),
+    ]);
+    post_json(&server, "/hook/today", "{}");
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+
+    let (status, body) = get(&server, "/api/days");
+    assert_eq!(status, 200);
+    let days: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(days, serde_json::json!([{"day": today, "current": true}, {"day": "2020-01-01", "current": false}]));
+    assert!(!dir.join("test-2019-12-31.db").exists());
+
+    let urls = |day: &str| {
+        let (status, body) = get(&server, &format!("/api/days/{day}"));
+        assert_eq!(status, 200, "{body}");
+        let day: serde_json::Value = serde_json::from_str(&body).unwrap();
+        day["requests"].as_array().unwrap().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(urls(&today), ["/hook/today"]);
+    assert_eq!(urls("2020-01-01"), ["/hook/2020-01-01"]);
+    assert_eq!(get(&server, "/api/days/2019-12-31").0, 404);
+    assert_eq!(get(&server, "/api/days/yesterday").0, 400);
+}
+
+#[test]
+fn days_are_not_found_without_rotation() {
+    let server = Server::start(&[]);
+    assert_eq!(get(&server, "/api/days").0, 404);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+#[test]
+fn bursts_above_the_threshold_are_thinned_but_still_answered() {
+    let server = Server::start(&[("SAMPLE_ABOVE_RPS", "5")]);
+    for i in 0..40 {
+        let (status, _) = post_json(&server, "/hook/burst", &format!(r#"{{"i":{i}}}"#));
+        assert_eq!(status, 200);
+    }
+
+    let stored = server.captures().len() as u64;
+    let (_, stats) = get(&server, "/api/stats");
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    let sampling = &stats["sampling"];
+    assert_eq!(sampling["threshold_rps"], 5.0);
+    assert!(sampling["fraction"].as_f64().unwrap() < 1.0, "{sampling}");
+    let sampled_out = sampling["sampled_out"].as_u64().unwrap();
+    assert!(sampled_out > 0, "{sampling}");
+    assert!(stored >= 5, "{stored} stored");
+    assert_eq!(stored + sampled_out, 40);
+}
+
+#[test]
+fn without_a_threshold_everything_is_stored() {
+    let server = Server::start(&[]);
+    for _ in 0..10 {
+        post_json(&server, "/hook/burst", "{}");
+    }
+    assert_eq!(server.captures().len(), 10);
+    let (_, stats) = get(&server, "/api/stats");
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    assert_eq!(stats["sampling"]["fraction"], 1.0);
+    assert_eq!(stats["sampling"]["threshold_rps"], serde_json::Value::Null);
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use common::{Server, get};
+
+#[test]
+fn schema_lists_the_migrated_columns_and_the_stored_version() {
+    let server = Server::start(&[]);
+    let (status, body) = get(&server, "/api/schema");
+    assert_eq!(status, 200);
+    let schema: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(schema["table"], "requests");
+
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    let version: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(schema["schema_version"], version);
+
+    let columns = schema["columns"].as_array().unwrap();
+    let column = |name: &str| columns.iter().find(|c| c["name"] == name).unwrap_or_else(|| panic!("no {name} in {body}"));
+    assert_eq!(column("id")["primary_key"], true);
+    assert_eq!(column("timestamp")["not_null"], true);
+    // Added by a migration rather than the original CREATE TABLE
+    assert_eq!(column("body_dropped")["default"], "0");
+}
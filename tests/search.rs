@@ -0,0 +1,43 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+fn matching(server: &Server, query: &str) -> Vec<String> {
+    let (status, body) = get(server, &format!("/api/requests?{query}"));
+    assert_eq!(status, 200, "{query}: {body}");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let mut urls: Vec<String> = body["requests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["url"].as_str().unwrap().to_string())
+        .collect();
+    urls.sort();
+    urls
+}
+
+#[test]
+fn like_wildcards_in_search_terms_match_literally() {
+    let server = Server::start(&[]);
+    for path in ["/hook/a_b", "/hook/axb", "/hook/50%25", "/hook/500"] {
+        post_json(&server, path, "{}");
+    }
+
+    assert_eq!(matching(&server, "url_contains=_"), ["/hook/a_b"]);
+    assert_eq!(matching(&server, "url_contains=a_b"), ["/hook/a_b"]);
+    assert_eq!(matching(&server, "url_contains=%25"), ["/hook/50%25"]);
+    assert_eq!(matching(&server, "url_contains=50%25"), ["/hook/50%25"]);
+    assert!(matching(&server, "url_contains=%25%25").is_empty());
+}
+
+#[test]
+fn like_wildcards_in_body_searches_match_literally() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/percent", r#"{"off":"100%"}"#);
+    post_json(&server, "/hook/digits", r#"{"off":"1000"}"#);
+    post_json(&server, "/hook/underscore", r#"{"user_id":1}"#);
+    post_json(&server, "/hook/letter", r#"{"userxid":1}"#);
+
+    assert_eq!(matching(&server, "body_contains=100%25"), ["/hook/percent"]);
+    assert_eq!(matching(&server, "body_contains=user_id"), ["/hook/underscore"]);
+}
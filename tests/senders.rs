@@ -0,0 +1,26 @@
+mod common;
+
+use common::{Server, get, post_json, request};
+
+#[test]
+fn senders_are_counted_busiest_first() {
+    let server = Server::start(&[("TRUST_FORWARDED_FOR", "1")]);
+    for _ in 0..3 {
+        request(&server, "POST", "/hook/a", &[("X-Forwarded-For", "203.0.113.7, 10.0.0.1")], b"{}");
+    }
+    request(&server, "POST", "/hook/b", &[("X-Forwarded-For", "198.51.100.2")], b"{}");
+    post_json(&server, "/hook/local", "{}");
+    post_json(&server, "/hook/local", "{}");
+
+    let (status, body) = get(&server, "/api/senders");
+    assert_eq!(status, 200);
+    let senders: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+    let counts: Vec<_> = senders.iter().map(|s| (s["remote_addr"].as_str().unwrap(), s["count"].as_i64().unwrap())).collect();
+    assert_eq!(counts, [("203.0.113.7", 3), ("127.0.0.1", 2), ("198.51.100.2", 1)]);
+
+    let busiest = &senders[0];
+    let captures = server.captures();
+    let times: Vec<&str> = captures.iter().filter(|r| r["remote_addr"] == "203.0.113.7").map(|r| r["timestamp"].as_str().unwrap()).collect();
+    assert_eq!(busiest["first_seen"], *times.iter().min().unwrap());
+    assert_eq!(busiest["last_seen"], *times.iter().max().unwrap());
+}
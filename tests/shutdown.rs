@@ -0,0 +1,36 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use common::{Server, read_close, read_head, read_message, websocket};
+
+#[test]
+fn sigterm_finishes_in_flight_requests_and_tells_dashboards() {
+    let mut server = Server::start(&[]);
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+
+    let mut slow = server.connect();
+    write!(slow, "POST /hook/slow HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\nConnection: close\r\n\r\nhello").unwrap();
+    let upload = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        slow.write_all(b"world").unwrap();
+        let (status, _) = read_head(&mut slow);
+        slow.read_to_string(&mut String::new()).ok();
+        status
+    });
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(server.terminate().success());
+    assert_eq!(upload.join().unwrap(), 200);
+
+    // Dashboards are told as the drain starts, so they never see the capture that finished during it
+    assert_eq!(read_message(&mut ws)["type"], "shutdown");
+    assert_eq!(read_close(&mut ws), (1001, "server shutting down".to_string()));
+
+    let db = rusqlite::Connection::open(server.dir.join("test.db")).unwrap();
+    let stored: i64 = db.query_row("SELECT COUNT(*) FROM requests WHERE url = '/hook/slow'", [], |row| row.get(0)).unwrap();
+    assert_eq!(stored, 1);
+    let wal = std::fs::metadata(server.dir.join("test.db-wal")).map(|m| m.len()).unwrap_or(0);
+    assert_eq!(wal, 0, "the WAL is checkpointed on the way out");
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::{Server, post_json, wait_for};
+
+#[test]
+fn captures_are_also_posted_to_the_sink() {
+    let collector = Server::start(&[]);
+    let server = Server::start(&[("SINK_URL", &collector.url("/hook/sink"))]);
+    assert_eq!(post_json(&server, "/hook/orders", r#"{"order":7}"#).0, 200);
+
+    wait_for("the sink delivery", || !collector.captures().is_empty());
+    let delivered = &collector.captures()[0];
+    assert_eq!(delivered["url"], "/hook/sink");
+    let shipped: serde_json::Value = serde_json::from_slice(&BASE64.decode(delivered["body"].as_str().unwrap()).unwrap()).unwrap();
+    let stored = &server.captures()[0];
+    assert_eq!(shipped["id"], stored["id"]);
+    assert_eq!(shipped["url"], "/hook/orders");
+    assert_eq!(BASE64.decode(shipped["body"].as_str().unwrap()).unwrap(), br#"{"order":7}"#);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+fn count(path: &std::path::Path, table: &str) -> i64 {
+    let db = rusqlite::Connection::open(path).unwrap();
+    db.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn rotated_logger_keeps_snapshots_out_of_the_day_file() {
+    let server = Server::start(&[("DB_ROTATION", "daily"), ("DB_ROTATION_KEEP", "1")]);
+    assert_eq!(post_json(&server, "/hook/a", "{}").0, 200);
+    assert_eq!(post_json(&server, "/api/snapshots", r#"{"name":"before-rollover"}"#).0, 201);
+
+    let today = chrono::Utc::now().format("%Y-%m-%d");
+    let day_file = server.dir.join(format!("test-{today}.db"));
+    assert_eq!(count(&day_file, "requests"), 1);
+    assert_eq!(count(&day_file, "snapshots"), 0, "removing the day file must not take the snapshot with it");
+    assert_eq!(count(&server.dir.join("test-snapshots.db"), "snapshot_requests"), 1);
+
+    let (status, body) = get(&server, "/api/snapshots/before-rollover");
+    assert_eq!(status, 200);
+    let snapshot: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(snapshot["requests"][0]["url"], "/hook/a");
+}
+
+#[test]
+fn snapshot_keeps_spilled_bodies_and_needs_one_pooled_connection() {
+    let server = Server::start(&[("DB_POOL_SIZE", "1"), ("BODY_INLINE_LIMIT", "8")]);
+    assert_eq!(post_json(&server, "/hook/a", r#"{"big":"enough to spill"}"#).0, 200);
+    assert_eq!(post_json(&server, "/api/snapshots", r#"{"name":"spilled"}"#).0, 201);
+    std::fs::remove_dir_all(server.dir.join("bodies")).unwrap();
+
+    let (status, body) = get(&server, "/api/snapshots/spilled");
+    assert_eq!(status, 200);
+    let snapshot: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let req = &snapshot["requests"][0];
+    assert_eq!(req["spilled"], false);
+    assert!(req["body_file"].is_null());
+    let decoded = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, req["body"].as_str().unwrap());
+    assert_eq!(decoded.unwrap(), br#"{"big":"enough to spill"}"#);
+}
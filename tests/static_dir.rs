@@ -0,0 +1,16 @@
+mod common;
+
+use common::{Server, config_file, get};
+
+#[test]
+fn static_dir_serves_the_ui_from_disk_and_falls_back_to_embedded_files() {
+    let index = config_file("index.html", "<h1>from disk</h1>");
+    let server = Server::start(&[("STATIC_DIR", index.parent().unwrap().to_str().unwrap())]);
+
+    assert_eq!(get(&server, "/"), (200, "<h1>from disk</h1>".to_string()));
+    std::fs::write(&index, "<h1>edited</h1>").unwrap();
+    assert_eq!(get(&server, "/").1, "<h1>edited</h1>", "read on every request, no restart needed");
+    let (status, js) = get(&server, "/app.js");
+    assert_eq!(status, 200);
+    assert!(js.contains("new WebSocket"), "app.js isn't in STATIC_DIR, so the embedded copy is served");
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{Server, post_json, read_message, request, send_message, websocket};
+
+#[test]
+fn subscribed_sockets_get_stats_pushed_at_the_clamped_interval() {
+    let server = Server::start(&[]);
+    post_json(&server, "/hook/a", "{}");
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+
+    send_message(&mut ws, r#"{"type":"subscribe_stats","interval_ms":100}"#);
+    let answered = Instant::now();
+    let now = read_message(&mut ws);
+    assert_eq!((now["type"].as_str(), now["total"].as_i64()), (Some("stats"), Some(1)));
+
+    request(&server, "PUT", "/hook/b", &[], b"{}");
+    assert_eq!(read_message(&mut ws)["type"], "new");
+    let pushed = read_message(&mut ws);
+    // 100 ms is below the 500 ms minimum
+    assert!(answered.elapsed() >= Duration::from_millis(450), "{:?}", answered.elapsed());
+    assert_eq!(pushed["type"], "stats");
+    assert_eq!(pushed["total"], 2);
+    assert_eq!(pushed["by_method"], serde_json::json!({"POST": 1, "PUT": 1}));
+    assert_eq!(read_message(&mut ws)["type"], "stats");
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{Server, config_file, request};
+
+fn tags(capture: &serde_json::Value) -> Vec<String> {
+    serde_json::from_str(capture["tags"].as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn captures_get_the_tags_of_every_rule_they_match() {
+    let rules = config_file(
+        "tag_rules.json",
+        r#"[
+            {"tag": "payment", "path": "^/hook/payments"},
+            {"tag": "github-push", "header": "x-github-event", "header_contains": "push"},
+            {"tag": "refund", "method": "POST", "body_contains": "\"refund\""},
+            {"tag": "error", "status": "5xx"}
+        ]"#,
+    );
+    let server = Server::start(&[("TAG_RULES_FILE", rules.to_str().unwrap())]);
+    request(&server, "POST", "/hook/payments", &[], br#"{"type":"refund"}"#);
+    request(&server, "POST", "/hook/gh", &[("X-GitHub-Event", "push")], b"{}");
+    request(&server, "GET", "/hook/payments/x?__status=503", &[], b"");
+    request(&server, "POST", "/hook/other", &[], b"{}");
+
+    let captures = server.captures();
+    let tags_of = |url: &str| tags(captures.iter().find(|r| r["url"] == url).unwrap());
+    assert_eq!(tags_of("/hook/payments"), ["payment", "refund"]);
+    assert_eq!(tags_of("/hook/gh"), ["github-push"]);
+    assert_eq!(tags_of("/hook/payments/x"), ["payment", "error"]);
+    assert!(tags_of("/hook/other").is_empty());
+}
+
+#[test]
+fn pin_rules_keep_matching_captures_through_eviction() {
+    let rules = config_file(
+        "pin_rules.json",
+        r#"[
+            {"pin": true, "status": "5xx"},
+            {"pin": true, "tag": "audit", "path": "^/hook/audit"}
+        ]"#,
+    );
+    let server = Server::start(&[("TAG_RULES_FILE", rules.to_str().unwrap()), ("MAX_REQUESTS", "1")]);
+    request(&server, "POST", "/hook/failed?__status=502", &[], b"{}");
+    request(&server, "POST", "/hook/audit/login", &[], b"{}");
+    request(&server, "POST", "/hook/ok?__status=404", &[], b"{}");
+    for i in 0..3 {
+        request(&server, "POST", &format!("/hook/filler/{i}"), &[], b"{}");
+    }
+
+    let captures = server.captures();
+    let pinned: Vec<(&str, bool)> = captures.iter().map(|r| (r["url"].as_str().unwrap(), r["pinned"].as_bool().unwrap())).collect();
+    assert_eq!(pinned, [("/hook/filler/2", false), ("/hook/audit/login", true), ("/hook/failed", true)]);
+    let audit = captures.iter().find(|r| r["url"] == "/hook/audit/login").unwrap();
+    assert_eq!(tags(audit), ["audit"]);
+}
+
+#[test]
+fn rules_that_neither_tag_nor_pin_are_refused_at_startup() {
+    let rules = config_file("useless_rules.json", r#"[{"path": "^/hook"}]"#);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_neo_http_logger"))
+        .env("TAG_RULES_FILE", &rules)
+        .env("PORT", common::free_port().to_string())
+        .env("DB_PATH", rules.with_extension("db"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(r#"every rule needs a \"tag\" or \"pin\": true"#), "{stderr}");
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use common::{Server, post_json};
+
+#[test]
+fn captures_arrive_over_the_socket_which_is_removed_on_shutdown() {
+    // Short enough for sun_path
+    let path = std::env::temp_dir().join(format!("nhl-{}.sock", std::process::id()));
+    // Left behind by a run that didn't shut down cleanly
+    drop(UnixListener::bind(&path).unwrap());
+    let mut server = Server::start(&[("UNIX_SOCKET", path.to_str().unwrap())]);
+
+    let mut stream = UnixStream::connect(&path).unwrap();
+    write!(stream, "POST /hook/local HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    post_json(&server, "/hook/tcp", "{}");
+
+    let captures = server.captures();
+    let remote = |url: &str| captures.iter().find(|r| r["url"] == url).unwrap()["remote_addr"].clone();
+    assert_eq!(remote("/hook/local"), "unix");
+    assert_eq!(remote("/hook/tcp"), "127.0.0.1");
+
+    assert!(server.terminate().success());
+    assert!(!path.exists());
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{Server, post_json, request};
+
+#[test]
+fn websocket_upgrades_to_the_hook_are_recorded_refused_and_not_forwarded() {
+    let upstream = Server::start(&[]);
+    let server = Server::start(&[("FORWARD_URL", &upstream.url(""))]);
+    let (status, _, body) = request(
+        &server,
+        "GET",
+        "/hook/socket",
+        &[("Upgrade", "h2c, WebSocket"), ("Sec-WebSocket-Version", "13"), ("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")],
+        b"",
+    );
+    assert_eq!(status, 400);
+    assert!(body.contains("not WebSocket upgrades"), "{body}");
+    post_json(&server, "/hook/plain", "{}");
+
+    let captures = server.captures();
+    let probe = captures.iter().find(|r| r["url"] == "/hook/socket").unwrap();
+    assert_eq!(probe["websocket_attempt"], true);
+    let plain = captures.iter().find(|r| r["url"] == "/hook/plain").unwrap();
+    assert_eq!(plain["websocket_attempt"], false);
+    let relayed: Vec<_> = upstream.captures().iter().map(|r| r["url"].as_str().unwrap().to_string()).collect();
+    assert_eq!(relayed, ["/hook/plain"]);
+}
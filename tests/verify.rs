@@ -0,0 +1,38 @@
+mod common;
+
+use common::{Server, get, post_json};
+
+fn verify(server: &Server) -> serde_json::Value {
+    let (status, body) = get(server, "/api/admin/verify");
+    assert_eq!(status, 200);
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn corrupted_bodies_are_reported_wherever_they_are_kept() {
+    let large = format!(r#"{{"pad":"{}"}}"#, "x".repeat(200));
+    // (BODY_STORAGE, body, how it's corrupted)
+    let cases: [(&str, &str, &str); 4] = [
+        ("base64", "{}", "UPDATE requests SET body = 'e30K'"),
+        ("base64", &large, ""),
+        ("blob", "{}", "UPDATE requests SET body_blob = X'00'"),
+        ("blob", &large, "UPDATE body_blobs SET body = X'00'"),
+    ];
+    for (storage, body, corrupt) in cases {
+        let server = Server::start(&[("BODY_STORAGE", storage), ("BODY_INLINE_LIMIT", "100")]);
+        post_json(&server, "/hook", body);
+        let before = verify(&server);
+        assert_eq!(before["ok"], true, "{storage} {before}");
+        assert_eq!(before["verified"], 1, "{storage} {before}");
+
+        if corrupt.is_empty() {
+            let id = server.captures()[0]["id"].as_str().unwrap().to_string();
+            std::fs::write(server.dir.join("bodies").join(id), b"tampered").unwrap();
+        } else {
+            rusqlite::Connection::open(server.dir.join("test.db")).unwrap().execute(corrupt, []).unwrap();
+        }
+        let after = verify(&server);
+        assert_eq!(after["ok"], false, "{storage} {corrupt}");
+        assert_eq!(after["mismatches"][0]["reason"], "body does not match body_sha256", "{storage} {corrupt}");
+    }
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{Server, read_message, send_message, websocket};
+
+#[test]
+fn history_advertises_the_protocol_and_unsupported_commands_get_errors() {
+    let server = Server::start(&[]);
+    let mut ws = websocket(&server, "/ws");
+    let history = read_message(&mut ws);
+    assert_eq!(history["type"], "history");
+    assert_eq!(history["protocol_version"], 1);
+    let capabilities = history["capabilities"].as_array().unwrap();
+    assert!(capabilities.iter().any(|c| c == "load_more"), "{history}");
+
+    send_message(&mut ws, r#"{"type":"teleport"}"#);
+    let unknown = read_message(&mut ws);
+    assert_eq!(unknown["type"], "error");
+    send_message(&mut ws, r#"{"type":"get_stats","protocol_version":99}"#);
+    let newer = read_message(&mut ws);
+    assert_eq!(newer["error"], "unsupported protocol_version 99, server speaks 1");
+    send_message(&mut ws, "not json");
+    assert_eq!(read_message(&mut ws)["error"], "invalid JSON");
+    send_message(&mut ws, r#"{"type":"get_stats","protocol_version":1}"#);
+    assert_eq!(read_message(&mut ws)["type"], "stats");
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use common::{Server, read_close, read_message, send_message, websocket};
+
+#[test]
+fn oversized_commands_close_the_connection_with_1009() {
+    let server = Server::start(&[("WS_MAX_MESSAGE_BYTES", "256")]);
+    let mut ws = websocket(&server, "/ws");
+    assert_eq!(read_message(&mut ws)["type"], "history");
+
+    let padded = |len: usize| {
+        let mut cmd = r#"{"type":"get_stats","padding":""}"#.to_string();
+        cmd.insert_str(cmd.len() - 2, &"x".repeat(len - cmd.len()));
+        cmd
+    };
+    send_message(&mut ws, &padded(256));
+    assert_eq!(read_message(&mut ws)["type"], "stats");
+
+    send_message(&mut ws, &padded(257));
+    assert_eq!(read_close(&mut ws), (1009, "message exceeds 256 bytes".to_string()));
+}